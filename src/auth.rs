@@ -0,0 +1,546 @@
+use std::{any::TypeId, sync::Arc};
+
+use anyhow::{anyhow, bail};
+use async_graphql::Data;
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    errors::ErrorKind,
+    jwk::{AlgorithmParameters, JwkSet},
+};
+use sea_query::{Alias, Expr, Func, Keyword, Query, SimpleExpr, SqliteQueryBuilder};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tracing::{debug, warn};
+
+use crate::config::{ApiKeyConfig, ApiKeyHash, AuthConfig, JwtAlgorithm, JwtConfig};
+
+/// Role of anonymous requests and of tokens without a role claim.
+pub const ANONYMOUS_ROLE: &str = "anonymous";
+
+/// Claims of the token or API key authenticating a request.
+///
+/// Resolvers read them with `ctx.data_opt::<Claims>()`, anonymous requests have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claims {
+    values: Map<String, Value>,
+    role: String,
+}
+
+impl Claims {
+    /// Claims of a token, whose role is the string value of `role_claim`.
+    pub fn new(values: Map<String, Value>, role_claim: &str) -> Self {
+        let role = values
+            .get(role_claim)
+            .and_then(Value::as_str)
+            .unwrap_or(ANONYMOUS_ROLE)
+            .to_string();
+
+        Self { values, role }
+    }
+
+    /// Claims added to the data of a request or connection.
+    pub fn from_data(data: &Data) -> Option<&Claims> {
+        data.get(&TypeId::of::<Claims>())
+            .and_then(|claims| claims.downcast_ref::<Claims>())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    pub fn values(&self) -> &Map<String, Value> {
+        &self.values
+    }
+
+    /// Role of the request, selecting its schema and the policies that apply to it.
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    /// Role of a request, [`ANONYMOUS_ROLE`] without claims.
+    pub fn role_of(claims: Option<&Claims>) -> &str {
+        claims.map_or(ANONYMOUS_ROLE, Claims::role)
+    }
+}
+
+impl Default for Claims {
+    fn default() -> Self {
+        Self {
+            values: Map::new(),
+            role: ANONYMOUS_ROLE.to_string(),
+        }
+    }
+}
+
+/// Claim holding the role of a request unless configured otherwise.
+const DEFAULT_ROLE_CLAIM: &str = "role";
+
+/// Validates JSON Web Tokens with the keys of a [`JwtConfig`].
+pub struct JwtValidator {
+    keys: Vec<(Option<String>, DecodingKey)>, // key id and key
+    validation: Validation,
+    required_claims: Vec<String>,
+}
+
+impl TryFrom<&JwtConfig> for JwtValidator {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &JwtConfig) -> Result<Self, Self::Error> {
+        let algorithm = match config.algorithm {
+            JwtAlgorithm::Hs256 => Algorithm::HS256,
+            JwtAlgorithm::Rs256 => Algorithm::RS256,
+            JwtAlgorithm::Es256 => Algorithm::ES256,
+        };
+
+        let keys = match (
+            config.algorithm,
+            &config.secret,
+            &config.public_key_file,
+            &config.jwks_file,
+        ) {
+            (JwtAlgorithm::Hs256, Some(secret), None, None) => {
+                vec![(None, DecodingKey::from_secret(secret.as_bytes()))]
+            }
+            (JwtAlgorithm::Hs256, ..) => bail!("HS256 tokens are verified with a secret only"),
+            (_, None, Some(path), None) => {
+                let pem = std::fs::read(path)
+                    .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+
+                let key = match config.algorithm {
+                    JwtAlgorithm::Es256 => DecodingKey::from_ec_pem(&pem)?,
+                    _ => DecodingKey::from_rsa_pem(&pem)?,
+                };
+
+                vec![(None, key)]
+            }
+            (_, None, None, Some(path)) => {
+                let jwks = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+
+                // keys of other types can't verify the tokens
+                serde_json::from_str::<JwkSet>(&jwks)?
+                    .keys
+                    .iter()
+                    .filter(|jwk| match &jwk.algorithm {
+                        AlgorithmParameters::RSA(_) => config.algorithm == JwtAlgorithm::Rs256,
+                        AlgorithmParameters::EllipticCurve(_) => {
+                            config.algorithm == JwtAlgorithm::Es256
+                        }
+                        _ => false,
+                    })
+                    .map(|jwk| Ok((jwk.common.key_id.clone(), DecodingKey::from_jwk(jwk)?)))
+                    .collect::<anyhow::Result<Vec<_>>>()?
+            }
+            _ => bail!(
+                "RS256 and ES256 tokens are verified with either a public-key-file or a jwks-file"
+            ),
+        };
+
+        if keys.is_empty() {
+            bail!("No key of the jwks-file matches the algorithm of the tokens");
+        }
+
+        let mut validation = Validation::new(algorithm);
+        let mut spec_claims = vec!["exp"];
+
+        if let Some(issuer) = &config.issuer {
+            validation.set_issuer(issuer);
+            spec_claims.push("iss");
+        }
+
+        match &config.audience {
+            Some(audience) => {
+                validation.set_audience(audience);
+                spec_claims.push("aud");
+            }
+            // any audience is accepted unless configured
+            None => validation.validate_aud = false,
+        }
+
+        validation.set_required_spec_claims(&spec_claims);
+
+        if let Some(leeway) = config.leeway {
+            validation.leeway = leeway;
+        }
+
+        Ok(Self {
+            keys,
+            validation,
+            required_claims: config.required_claims.clone().unwrap_or_default(),
+        })
+    }
+}
+
+impl JwtValidator {
+    /// Verifies the signature and claims of a token, returning its claims.
+    pub fn validate(&self, token: &str) -> anyhow::Result<Map<String, Value>> {
+        let header = decode_header(token)?;
+
+        // keys without an id verify every token, and tokens without one are tried on every key
+        let mut decoded = None;
+
+        for (_, key) in self
+            .keys
+            .iter()
+            .filter(|(kid, _)| kid.is_none() || header.kid.is_none() || *kid == header.kid)
+        {
+            let result = decode::<Map<String, Value>>(token, key, &self.validation);
+
+            // only a signature of another key leaves the next keys to try
+            let is_other_key =
+                matches!(&result, Err(e) if *e.kind() == ErrorKind::InvalidSignature);
+
+            decoded = Some(result);
+
+            if !is_other_key {
+                break;
+            }
+        }
+
+        let claims = decoded
+            .ok_or(anyhow!("Unknown key id {:?}", header.kid))??
+            .claims;
+
+        if let Some(claim) = self
+            .required_claims
+            .iter()
+            .find(|claim| !claims.contains_key(claim.as_str()))
+        {
+            bail!("Missing required claim: {}", claim);
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Header holding API keys unless configured otherwise.
+const DEFAULT_API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Column holding the keys unless configured otherwise.
+const DEFAULT_API_KEY_COLUMN: &str = "key_hash";
+
+/// Validates API keys against the table of an [`ApiKeyConfig`].
+pub struct ApiKeyValidator {
+    config: ApiKeyConfig,
+    db: SqlitePool,
+}
+
+impl ApiKeyValidator {
+    pub fn new(config: ApiKeyConfig, db: SqlitePool) -> Self {
+        Self { config, db }
+    }
+
+    /// Header holding the keys.
+    pub fn header(&self) -> &str {
+        self.config
+            .header
+            .as_deref()
+            .unwrap_or(DEFAULT_API_KEY_HEADER)
+    }
+
+    /// Looks up a key, returning its claims with its role under `role_claim`.
+    ///
+    /// The last use of a valid key is recorded in the background.
+    pub async fn validate(
+        &self,
+        key: &str,
+        role_claim: &str,
+    ) -> anyhow::Result<Map<String, Value>> {
+        let config = &self.config;
+
+        let stored_key = match config.hash.unwrap_or_default() {
+            ApiKeyHash::Sha256 => format!("{:x}", Sha256::digest(key.as_bytes())),
+            ApiKeyHash::Plain => key.to_string(),
+        };
+
+        let key_condition = Expr::col(Alias::new(
+            config
+                .key_column
+                .as_deref()
+                .unwrap_or(DEFAULT_API_KEY_COLUMN),
+        ))
+        .eq(stored_key);
+
+        let column = |name: &Option<String>| match name {
+            Some(name) => Expr::col(Alias::new(name)).into(),
+            None => SimpleExpr::Keyword(Keyword::Null),
+        };
+
+        // the subject keeps its type, a text claims column is parsed as JSON
+        let query = Query::select()
+            .expr(column(&config.role_column))
+            .expr(Func::cust(Alias::new("json_quote")).arg(column(&config.subject_column)))
+            .expr(column(&config.claims_column))
+            // an expiry that is not a datetime or a timestamp expires the key
+            .expr(Expr::cust_with_exprs(
+                "? IS NOT NULL AND IFNULL(unixepoch(?, 'auto') <= unixepoch('now'), TRUE)",
+                [
+                    column(&config.expires_column),
+                    column(&config.expires_column),
+                ],
+            ))
+            .expr(Expr::cust_with_expr(
+                "IFNULL(?, 0) NOT IN (0, '0')",
+                column(&config.revoked_column),
+            ))
+            .from(Alias::new(&config.table))
+            .and_where(key_condition.clone())
+            .limit(1)
+            .to_string(SqliteQueryBuilder);
+
+        let (role, subject, claims, expired, revoked) =
+            sqlx::query_as::<_, (Option<String>, Value, Option<Value>, bool, bool)>(&query)
+                .fetch_optional(&self.db)
+                .await?
+                .ok_or(anyhow!("Unknown key"))?;
+
+        if revoked {
+            bail!("Revoked key");
+        }
+
+        if expired {
+            bail!("Expired key");
+        }
+
+        let mut values = match claims {
+            Some(Value::Object(claims)) => claims,
+            None | Some(Value::Null) => Map::new(),
+            Some(_) => bail!("Claims of the key are not a JSON object"),
+        };
+
+        if !subject.is_null() {
+            values.insert("sub".to_string(), subject);
+        }
+
+        if let Some(role) = role {
+            values.insert(role_claim.to_string(), Value::String(role));
+        }
+
+        if let Some(last_used) = &config.last_used_column {
+            let query = Query::update()
+                .table(Alias::new(&config.table))
+                .value(Alias::new(last_used), Expr::cust("datetime('now')"))
+                .and_where(key_condition)
+                .to_string(SqliteQueryBuilder);
+
+            let db = self.db.clone();
+
+            // the request doesn't wait for the write
+            tokio::spawn(async move {
+                if let Err(e) = sqlx::query(&query).execute(&db).await {
+                    warn!("Unable to record the use of an API key: {}", e);
+                }
+            });
+        }
+
+        Ok(values)
+    }
+}
+
+/// A request rejected with `401 Unauthorized` before being executed.
+#[derive(Debug)]
+pub struct AuthError(String);
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(serde_json::json!({ "errors": [{ "message": self.0 }] })),
+        )
+            .into_response()
+    }
+}
+
+/// Authenticates the requests of the server with the methods of an [`AuthConfig`].
+#[derive(Clone)]
+pub struct Authenticator {
+    jwt: Option<Arc<JwtValidator>>,
+    api_keys: Option<Arc<ApiKeyValidator>>,
+    required: bool,
+    role_claim: String,
+}
+
+impl TryFrom<&AuthConfig> for Authenticator {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &AuthConfig) -> Result<Self, Self::Error> {
+        Ok(Self {
+            jwt: config
+                .jwt
+                .as_ref()
+                .map(JwtValidator::try_from)
+                .transpose()?
+                .map(Arc::new),
+            // the key table is only known once connected, see `with_api_keys`
+            api_keys: None,
+            required: config.jwt.as_ref().and_then(|jwt| jwt.required) == Some(true)
+                || config.api_key.as_ref().and_then(|key| key.required) == Some(true),
+            role_claim: config
+                .role_claim
+                .clone()
+                .unwrap_or(DEFAULT_ROLE_CLAIM.to_string()),
+        })
+    }
+}
+
+impl Default for Authenticator {
+    fn default() -> Self {
+        Self {
+            jwt: None,
+            api_keys: None,
+            required: false,
+            role_claim: DEFAULT_ROLE_CLAIM.to_string(),
+        }
+    }
+}
+
+impl Authenticator {
+    /// Authenticates requests with the API keys of a validator as well.
+    pub fn with_api_keys(mut self, api_keys: ApiKeyValidator) -> Self {
+        self.api_keys = Some(Arc::new(api_keys));
+        self
+    }
+
+    /// Claims of an API key, or else of the bearer token of an `Authorization` header
+    /// value, `None` for anonymous requests.
+    pub async fn authenticate(
+        &self,
+        authorization: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Result<Option<Claims>, AuthError> {
+        if let (Some(api_keys), Some(key)) = (&self.api_keys, api_key) {
+            let claims = api_keys
+                .validate(key.trim(), &self.role_claim)
+                .await
+                .map_err(|e| {
+                    // the reason a key is rejected is only logged
+                    debug!("Rejected API key: {}", e);
+                    AuthError("Invalid API key".into())
+                })?;
+
+            return Ok(Some(Claims::new(claims, &self.role_claim)));
+        }
+
+        let (Some(jwt), Some(authorization)) = (&self.jwt, authorization) else {
+            if self.required {
+                return Err(AuthError(match self.api_keys {
+                    Some(_) => "Missing bearer token or API key".into(),
+                    None => "Missing bearer token".into(),
+                }));
+            }
+
+            return Ok(None);
+        };
+
+        let token = authorization
+            .strip_prefix("Bearer ")
+            .ok_or(AuthError("Expected a bearer token".into()))?;
+
+        let claims = jwt.validate(token.trim()).map_err(|e| {
+            debug!("Rejected token: {}", e);
+            AuthError(format!("Invalid token: {}", e))
+        })?;
+
+        Ok(Some(Claims::new(claims, &self.role_claim)))
+    }
+
+    /// Header holding API keys, `None` without API key authentication.
+    fn api_key_header(&self) -> Option<&str> {
+        self.api_keys.as_ref().map(|api_keys| api_keys.header())
+    }
+
+    /// Whether a request has credentials in its headers.
+    pub fn has_credentials(&self, headers: &HeaderMap) -> bool {
+        headers.contains_key(header::AUTHORIZATION)
+            || self
+                .api_key_header()
+                .is_some_and(|name| headers.contains_key(name))
+    }
+
+    /// Authenticates the request from its headers.
+    pub async fn authenticate_headers(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<Option<Claims>, AuthError> {
+        // a header that is not text is not a token either
+        let header_value = |name: &str| {
+            headers
+                .get(name)
+                .map(|value| value.to_str().unwrap_or_default())
+        };
+
+        self.authenticate(
+            header_value(header::AUTHORIZATION.as_str()),
+            self.api_key_header().and_then(header_value),
+        )
+        .await
+    }
+
+    /// Session data of a WebSocket connection.
+    ///
+    /// Browsers can't set headers on WebSocket connections, so the token or API key is
+    /// read from the `Authorization` or API key header entry of the `connection_init`
+    /// payload, falling back to the claims of the upgrade request.
+    pub async fn connection_data(
+        &self,
+        payload: &Value,
+        claims: Option<Claims>,
+    ) -> async_graphql::Result<Data> {
+        // entries are matched like header names
+        let entry = |name: &str| {
+            payload
+                .as_object()?
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .and_then(|(_, value)| value.as_str())
+        };
+
+        let authorization = entry(header::AUTHORIZATION.as_str());
+        let api_key = self.api_key_header().and_then(entry);
+
+        let claims = match (authorization, api_key, claims) {
+            (None, None, Some(claims)) => Some(claims),
+            (authorization, api_key, _) => self
+                .authenticate(authorization, api_key)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?,
+        };
+
+        let mut data = Data::default();
+
+        if let Some(claims) = claims {
+            data.insert(claims);
+        }
+
+        Ok(data)
+    }
+}
+
+/// Middleware rejecting requests with an invalid token, passing the claims of valid
+/// ones to the handler as a request extension.
+pub async fn authenticate(
+    State(auth): State<Authenticator>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    if let Some(claims) = auth.authenticate_headers(request.headers()).await? {
+        request.extensions_mut().insert(claims);
+    }
+
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use sqlx::SqliteConnection;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::parser::ChangeOperation;
+
+/// Number of changes kept for subscribers that fall behind.
+const CHANGE_BUS_CAPACITY: usize = 1024;
+
+/// Values of a row by column, as stored by SQLite.
+pub type RowValues = Vec<(String, sea_query::Value)>;
+
+/// A row written to a table, identified by its rowid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableChange {
+    pub table: String,
+    pub operation: ChangeOperation,
+    pub rowid: i64,
+    /// Values of a deleted row, when the statement deleting it returned them, see
+    /// [`ChangeBus::capture_deleted`]
+    pub row: Option<Arc<RowValues>>,
+}
+
+/// Broadcasts the committed changes of the database to subscriptions.
+///
+/// Changes are reported by SQLite's update hook on the connections passed to
+/// [`ChangeBus::watch`], so writes made by triggers and cascading foreign keys are
+/// published along with the rows written by mutations. SQLite does not report changes
+/// to `WITHOUT ROWID` tables.
+#[derive(Clone)]
+pub struct ChangeBus {
+    sender: broadcast::Sender<TableChange>,
+    watched: Arc<Mutex<HashMap<usize, Arc<Mutex<PendingChanges>>>>>, // by connection handle
+}
+
+impl Default for ChangeBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CHANGE_BUS_CAPACITY).0,
+            watched: Default::default(),
+        }
+    }
+}
+
+impl ChangeBus {
+    pub fn publish(&self, change: TableChange) {
+        // nobody is listening without subscriptions
+        let _ = self.sender.send(change);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TableChange> {
+        self.sender.subscribe()
+    }
+
+    /// Attaches the values of rows deleted through a watched connection, by rowid, to
+    /// their pending changes.
+    ///
+    /// Deleted rows can't be read anymore once the change is published, so subscriptions
+    /// match their filters against these values instead.
+    pub async fn capture_deleted(
+        &self,
+        conn: &mut SqliteConnection,
+        table: &str,
+        rows: Vec<(i64, RowValues)>,
+    ) -> sqlx::Result<()> {
+        let db = conn.lock_handle().await?.as_raw_handle().as_ptr() as usize;
+
+        let Some(pending) = self.watched.lock().unwrap().get(&db).cloned() else {
+            return Ok(());
+        };
+
+        let mut rows = rows
+            .into_iter()
+            .map(|(rowid, row)| (rowid, Arc::new(row)))
+            .collect::<HashMap<_, _>>();
+
+        let mut pending = pending.lock().unwrap();
+        let pending = &mut *pending;
+
+        // a statement outside of a transaction is already committed, latest changes first
+        // since rowids can be reused
+        for change in pending
+            .committed
+            .iter_mut()
+            .chain(pending.uncommitted.iter_mut())
+            .rev()
+            .filter(|change| change.operation == ChangeOperation::Delete && change.table == table)
+        {
+            if let Some(row) = rows.remove(&change.rowid) {
+                change.row = Some(row);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collects the changes made through a connection until the returned [`ChangeWatch`]
+    /// is dropped. Changes are dropped when their transaction is rolled back.
+    pub async fn watch(&self, conn: &mut SqliteConnection) -> sqlx::Result<ChangeWatch> {
+        let pending = Arc::new(Mutex::new(PendingChanges::default()));
+        let mut handle = conn.lock_handle().await?;
+        let db = handle.as_raw_handle().as_ptr() as usize;
+
+        handle.set_update_hook({
+            let pending = pending.clone();
+
+            move |result| {
+                let Ok(operation) = ChangeOperation::try_from(result.operation) else {
+                    return;
+                };
+
+                // temporary and attached databases are not part of the schema
+                if result.database != "main" {
+                    return;
+                }
+
+                let mut pending = pending.lock().unwrap();
+
+                if !pending.closed {
+                    pending.uncommitted.push(TableChange {
+                        table: result.table.to_string(),
+                        operation,
+                        rowid: result.rowid,
+                        row: None,
+                    });
+                }
+            }
+        });
+
+        handle.set_commit_hook({
+            let pending = pending.clone();
+
+            move || {
+                let mut pending = pending.lock().unwrap();
+                let mut uncommitted = std::mem::take(&mut pending.uncommitted);
+                pending.committed.append(&mut uncommitted);
+                true
+            }
+        });
+
+        handle.set_rollback_hook({
+            let pending = pending.clone();
+
+            move || pending.lock().unwrap().uncommitted.clear()
+        });
+
+        self.watched.lock().unwrap().insert(db, pending.clone());
+
+        Ok(ChangeWatch {
+            bus: self.clone(),
+            db,
+            pending,
+        })
+    }
+}
+
+#[derive(Default)]
+struct PendingChanges {
+    uncommitted: Vec<TableChange>,
+    committed: Vec<TableChange>,
+    closed: bool,
+}
+
+/// Changes collected on a connection by [`ChangeBus::watch`].
+///
+/// The commit hook runs before the commit is visible to other connections, so committed
+/// changes are only published when the watch is dropped, after the writes are done.
+pub struct ChangeWatch {
+    bus: ChangeBus,
+    db: usize,
+    pending: Arc<Mutex<PendingChanges>>,
+}
+
+impl Drop for ChangeWatch {
+    fn drop(&mut self) {
+        {
+            let mut watched = self.bus.watched.lock().unwrap();
+
+            // the connection may already be watched for another request
+            if watched
+                .get(&self.db)
+                .is_some_and(|pending| Arc::ptr_eq(pending, &self.pending))
+            {
+                watched.remove(&self.db);
+            }
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+
+        // the hooks stay on the connection after it returns to the pool
+        pending.closed = true;
+
+        debug!("Publishing {} committed changes", pending.committed.len());
+
+        for change in pending.committed.drain(..) {
+            self.bus.publish(change);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::anyhow;
 use clap::{Parser, Subcommand};
-use graph_sql::{GraphSQL, config::GraphSQLConfig};
+use graph_sql::{GraphSQL, config::GraphSQLConfig, permission::Permissions};
 use tracing::{debug, error, info};
 
 #[derive(Parser, Debug)]
@@ -38,6 +38,7 @@ impl Cli {
     async fn introspect(
         config: GraphSQLConfig,
         output: Option<String>,
+        role: Option<String>,
     ) -> async_graphql::Result<()> {
         let pool = config.database.create_connection().await?;
 
@@ -48,9 +49,12 @@ impl Cli {
                 .await?;
         }
 
+        let permissions = Permissions::from(config.permissions.as_deref().unwrap_or_default());
+
         let graph_sql = GraphSQL::new(config);
 
         let tables = graph_sql.introspect(&pool).await?;
+        let tables = permissions.tables_for(role.as_deref(), &tables)?;
 
         let schema = graph_sql.build_schema(tables)?.finish()?;
 
@@ -74,7 +78,9 @@ impl Cli {
         let config = load_config(&self.config)?;
 
         match &self.command {
-            Commands::Introspect { output } => Cli::introspect(config, output.to_owned()).await,
+            Commands::Introspect { output, role } => {
+                Cli::introspect(config, output.to_owned(), role.to_owned()).await
+            }
             Commands::Serve => Cli::serve(config).await,
         }
     }
@@ -89,6 +95,9 @@ pub enum Commands {
         /// Output file (stdout if not specified)
         #[arg(short, long)]
         output: Option<String>,
+        /// Role whose schema is output (schema of roles without permissions if not specified)
+        #[arg(short, long)]
+        role: Option<String>,
     },
 }
 
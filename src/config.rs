@@ -1,10 +1,15 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use async_graphql::dynamic::SchemaBuilder;
 use serde::Deserialize;
-use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use sqlx::{
+    SqlitePool,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
 use tracing::{debug, info};
 
+use crate::functions::register_functions;
+
 /// Load configuration from a TOML file.
 ///
 /// This function reads a TOML configuration file from the specified path and
@@ -71,6 +76,22 @@ pub fn load_config(config_path: &str) -> anyhow::Result<GraphSQLConfig> {
 /// [database.sqlite]
 /// filename = "data.db"
 /// foreign-keys = true
+///
+/// [views.post_summary]
+/// key = ["post_id"]
+///
+/// [auth.jwt]
+/// algorithm = "HS256"
+/// secret = "change-me"
+///
+/// [[policy]]
+/// table = "post"
+/// select = "published OR author_id = $claims.sub"
+///
+/// [[permission]]
+/// table = "user"
+/// role = "anonymous"
+/// read = { deny = ["password_hash"] }
 /// ```
 #[derive(Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
@@ -81,6 +102,19 @@ pub struct GraphSQLConfig {
     pub graphql: GraphQLConfig,
     /// Database connection and SQLite-specific settings
     pub database: DatabaseConfig,
+    /// Logical keys and foreign keys of SQL views, by view name
+    pub views: Option<HashMap<String, ViewConfig>>,
+    /// Descriptions of tables, views and their columns, by table or view name
+    pub descriptions: Option<HashMap<String, DescriptionConfig>>,
+    /// Authentication of incoming requests
+    pub auth: Option<AuthConfig>,
+    /// Row-level security policies, declared as `[[policy]]` tables
+    #[serde(rename = "policy")]
+    pub policies: Option<Vec<PolicyConfig>>,
+    /// Operations and columns of the tables each role can access, declared as
+    /// `[[permission]]` tables
+    #[serde(rename = "permission")]
+    pub permissions: Option<Vec<PermissionConfig>>,
 }
 
 impl GraphSQLConfig {
@@ -120,7 +154,7 @@ pub struct ServerConfig {
 /// All fields are optional and will use sensible defaults if not specified.
 ///
 /// See [`async_graphql::dynamic::SchemaBuilder`] for more details on these options.
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct GraphQLConfig {
     /// Enable file uploading capabilities (default: true)
@@ -162,6 +196,14 @@ pub struct GraphQLConfig {
     /// Enable Apollo Federation support (default: false)
     /// Allows this service to participate in a federated GraphQL architecture
     pub enable_federation: Option<bool>,
+
+    /// Run all root fields of every mutation operation in one transaction (default: false)
+    /// Operations can opt in individually with the `@transaction` directive
+    pub transactional_mutations: Option<bool>,
+
+    /// Tables linked as many-to-many junctions despite their payload columns (default: none)
+    /// Payload columns must be nullable or have a default, as connecting rows only sets the keys
+    pub junction_tables: Option<Vec<String>>,
 }
 
 impl GraphQLConfig {
@@ -182,7 +224,7 @@ impl GraphQLConfig {
     /// # Example
     ///
     /// ```rust,no_run
-    /// use async_graphql::dynamic::SchemaBuilder;
+    /// use async_graphql::dynamic::{Object, Schema};
     /// # use graph_sql::config::GraphQLConfig;
     ///
     /// let config = GraphQLConfig {
@@ -191,7 +233,7 @@ impl GraphQLConfig {
     ///     ..Default::default()
     /// };
     ///
-    /// let schema_builder = SchemaBuilder::new();
+    /// let schema_builder = Schema::build("Query", None, None).register(Object::new("Query"));
     /// let configured_builder = config.apply(schema_builder);
     /// ```
     pub fn apply(&self, mut schema: SchemaBuilder) -> SchemaBuilder {
@@ -235,6 +277,333 @@ impl GraphQLConfig {
     }
 }
 
+/// Logical constraints of a SQL view.
+///
+/// Views are exposed as read-only types. SQLite does not track keys or foreign keys
+/// for views, so they can be declared here to make single-row lookups and
+/// relationships into and out of the view available.
+///
+/// Views without a key are identified by all of their columns.
+///
+/// # Example
+///
+/// ```toml
+/// [views.post_summary]
+/// key = ["post_id"]
+///
+/// [[views.post_summary.foreign-keys]]
+/// column = "post_id"
+/// table = "post"
+/// to = "id"
+/// ```
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct ViewConfig {
+    /// Columns uniquely identifying a row of the view
+    pub key: Option<Vec<String>>,
+
+    /// Foreign keys from columns of the view to other tables or views
+    pub foreign_keys: Option<Vec<ForeignKeyConfig>>,
+}
+
+/// A logical foreign key declared in the configuration.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ForeignKeyConfig {
+    /// Column of the view holding the reference
+    pub column: String,
+    /// Name of the referenced table or view
+    pub table: String,
+    /// Referenced column, usually the primary key of `table`
+    pub to: String,
+}
+
+/// Documentation of a table or view and its columns.
+///
+/// Descriptions are read from the comments of the `CREATE TABLE` statements. The ones
+/// configured here take precedence, which documents tables whose SQL can't be changed.
+///
+/// # Example
+///
+/// ```toml
+/// [descriptions.post]
+/// table = "Articles published on the blog"
+///
+/// [descriptions.post.columns]
+/// slug = "URL-friendly identifier of the post"
+/// ```
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct DescriptionConfig {
+    /// Description of the table or view
+    pub table: Option<String>,
+    /// Descriptions of columns, by column name
+    pub columns: Option<HashMap<String, String>>,
+}
+
+/// Authentication of incoming requests.
+///
+/// Without an authentication method every request is anonymous.
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuthConfig {
+    /// Bearer tokens validated as JSON Web Tokens
+    pub jwt: Option<JwtConfig>,
+
+    /// API keys looked up in a table of the database
+    pub api_key: Option<ApiKeyConfig>,
+
+    /// Claim holding the role of an authenticated request (default: "role")
+    pub role_claim: Option<String>,
+}
+
+/// Validation of JSON Web Tokens passed as `Authorization: Bearer <token>`.
+///
+/// HS256 tokens are verified with a shared `secret`, RS256 and ES256 tokens with the
+/// public key of a PEM file or the keys of a JWKS file, selected by the `kid` of the
+/// token. The claims of a valid token are available to the resolvers of the request.
+///
+/// # Example
+///
+/// ```toml
+/// [auth.jwt]
+/// algorithm = "RS256"
+/// jwks-file = "keys/jwks.json"
+/// issuer = ["https://auth.example.com/"]
+/// audience = ["graph-sql"]
+/// required-claims = ["sub", "role"]
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct JwtConfig {
+    /// Signing algorithm of the tokens
+    pub algorithm: JwtAlgorithm,
+
+    /// Shared secret of HS256 tokens
+    pub secret: Option<String>,
+
+    /// PEM file holding the public key of RS256 or ES256 tokens
+    pub public_key_file: Option<PathBuf>,
+
+    /// JWKS file holding the public keys of RS256 or ES256 tokens
+    pub jwks_file: Option<PathBuf>,
+
+    /// Accepted values of the `iss` claim, which becomes required
+    pub issuer: Option<Vec<String>>,
+
+    /// Accepted values of the `aud` claim, which becomes required
+    pub audience: Option<Vec<String>>,
+
+    /// Claims every token must contain, in addition to `exp`
+    pub required_claims: Option<Vec<String>>,
+
+    /// Clock skew tolerated when checking `exp` and `nbf`, in seconds (default: 60)
+    pub leeway: Option<u64>,
+
+    /// Reject requests without a token instead of running them anonymously (default: false)
+    pub required: Option<bool>,
+}
+
+/// Signing algorithms supported for JSON Web Tokens.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    /// HMAC using SHA-256
+    #[serde(rename = "HS256")]
+    Hs256,
+    /// RSASSA-PKCS1-v1_5 using SHA-256
+    #[serde(rename = "RS256")]
+    Rs256,
+    /// ECDSA using P-256 and SHA-256
+    #[serde(rename = "ES256")]
+    Es256,
+}
+
+/// API keys passed in a header and looked up in a table of the database.
+///
+/// A key is valid when its row exists, is not revoked and has not expired. The request
+/// then gets the claims of the JSON object in `claims-column`, along with the `sub`
+/// claim from `subject-column` and the role from `role-column`, just like a token
+/// would. The table itself is left out of the schema.
+///
+/// # Example
+///
+/// ```toml
+/// [auth.api-key]
+/// table = "api_key"
+/// key-column = "key_hash"
+/// role-column = "role"
+/// subject-column = "service"
+/// expires-column = "expires_at"
+/// revoked-column = "revoked_at"
+/// last-used-column = "last_used_at"
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ApiKeyConfig {
+    /// Table holding the keys
+    pub table: String,
+
+    /// Header holding the key (default: "X-Api-Key")
+    pub header: Option<String>,
+
+    /// Column holding the key, or its hash (default: "key_hash")
+    pub key_column: Option<String>,
+
+    /// How keys are stored in `key-column` (default: sha256)
+    pub hash: Option<ApiKeyHash>,
+
+    /// Column holding the role of the key
+    pub role_column: Option<String>,
+
+    /// Column holding the `sub` claim of the key
+    pub subject_column: Option<String>,
+
+    /// Column holding a JSON object of additional claims
+    pub claims_column: Option<String>,
+
+    /// Column holding the expiry of the key as a datetime or a Unix timestamp, never
+    /// expiring when `NULL` and expired when it is neither
+    pub expires_column: Option<String>,
+
+    /// Column revoking the key when set to a value other than `NULL` or `0`
+    pub revoked_column: Option<String>,
+
+    /// Column set to the current datetime whenever the key is used
+    pub last_used_column: Option<String>,
+
+    /// Reject requests without a key or token instead of running them anonymously
+    /// (default: false)
+    pub required: Option<bool>,
+}
+
+/// Storage of the keys of an API key table.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyHash {
+    /// Lowercase hex SHA-256 digest of the key
+    #[default]
+    Sha256,
+    /// The key itself
+    Plain,
+}
+
+/// A row-level security policy of a table.
+///
+/// Each command is a SQL expression over the columns of the table, deciding which rows
+/// the command may read or write. `$claims.<name>` references a claim of the request,
+/// which is `NULL` for anonymous requests or missing claims.
+///
+/// Once a table has a policy, a command is only allowed on the rows matching one of the
+/// policies of the request's role that define it. Tables without policies are unrestricted.
+///
+/// # Example
+///
+/// ```toml
+/// [[policy]]
+/// table = "post"
+/// select = "published OR author_id = $claims.sub"
+///
+/// [[policy]]
+/// table = "post"
+/// role = "author"
+/// insert = "author_id = $claims.sub"
+/// update = "author_id = $claims.sub"
+/// delete = "author_id = $claims.sub"
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PolicyConfig {
+    /// Table the policy applies to
+    pub table: String,
+    /// Role the policy applies to, every request when omitted
+    pub role: Option<String>,
+    /// Rows that can be listed, aggregated and referenced
+    pub select: Option<String>,
+    /// Rows that can be inserted, checked against the new row
+    pub insert: Option<String>,
+    /// Rows that can be updated
+    pub update: Option<String>,
+    /// Rows that can be deleted
+    pub delete: Option<String>,
+}
+
+/// Operations and columns of a table a role can access.
+///
+/// Every role gets its own schema, leaving out the operations and columns its
+/// permissions deny. Requests without a role have the `anonymous` role, and roles
+/// without permissions of their own only get the ones without a `role`. A table
+/// with several permissions applying to a role only allows what all of them allow.
+///
+/// # Example
+///
+/// ```toml
+/// [[permission]]
+/// table = "customer"
+/// role = "anonymous"
+/// operations = { allow = ["list", "view"] }
+/// read = { deny = ["password_hash"] }
+///
+/// [[permission]]
+/// table = "customer"
+/// role = "customer"
+/// operations = { deny = ["delete"] }
+/// write = { allow = ["first_name", "last_name", "phone"] }
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PermissionConfig {
+    /// Table the permission applies to
+    pub table: String,
+    /// Role the permission applies to, every role when omitted
+    pub role: Option<String>,
+    /// Operations of the table the role can run
+    pub operations: Option<AccessList<TableOperation>>,
+    /// Columns the role can read, hidden columns can't be filtered or sorted on either
+    pub read: Option<AccessList<String>>,
+    /// Columns the role can set in inserts and updates
+    pub write: Option<AccessList<String>>,
+}
+
+/// Items allowed by a permission: only the `allow`ed ones when set, except the `deny`ed ones.
+#[derive(Deserialize, Clone, Debug)]
+pub struct AccessList<T> {
+    pub allow: Option<Vec<T>>,
+    pub deny: Option<Vec<T>>,
+}
+
+impl<T: PartialEq> AccessList<T> {
+    pub fn allows(&self, item: &T) -> bool {
+        self.allow.as_ref().is_none_or(|allow| allow.contains(item))
+            && !self.deny.as_ref().is_some_and(|deny| deny.contains(item))
+    }
+}
+
+/// Operations of a table that permissions allow or deny.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum TableOperation {
+    /// Lists, connections, aggregates, group by, relation fields and change subscriptions
+    List,
+    /// Single-row queries by primary key and the foreign key fields referencing the rows
+    View,
+    /// Inserts, upserts and connecting junction rows, including nested creates
+    Insert,
+    /// Updates and upserts
+    Update,
+    /// Deletes and disconnecting junction rows
+    Delete,
+}
+
+impl TableOperation {
+    pub const ALL: [TableOperation; 5] = [
+        TableOperation::List,
+        TableOperation::View,
+        TableOperation::Insert,
+        TableOperation::Update,
+        TableOperation::Delete,
+    ];
+}
+
 /// Database connection configuration.
 ///
 /// This structure manages database connection settings and supports multiple
@@ -280,21 +649,24 @@ impl DatabaseConfig {
     /// - The connection parameters are invalid
     /// - The SQLite driver encounters an initialization error
     pub async fn create_connection(&self) -> sqlx::Result<SqlitePool> {
+        // statements run outside of mutations can use the functions as well
+        let pool = SqlitePoolOptions::new()
+            .after_connect(|conn, _| Box::pin(async move { register_functions(conn).await }));
+
         if let Some(sqlite) = &self.sqlite {
             let options = sqlite.apply();
 
-            return SqlitePool::connect_with(options).await;
+            return pool.connect_with(options).await;
         }
 
         if self.use_env.unwrap_or(true) {
-            return SqlitePool::connect(
-                &std::env::var("DATABASE_URL").unwrap_or("sqlite://:memory:".into()),
-            )
-            .await;
+            return pool
+                .connect(&std::env::var("DATABASE_URL").unwrap_or("sqlite://:memory:".into()))
+                .await;
         }
 
         if let Some(db_url) = &self.database_url {
-            return SqlitePool::connect(db_url).await;
+            return pool.connect(db_url).await;
         }
 
         unimplemented!()
@@ -0,0 +1,268 @@
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString, c_char, c_int, c_void},
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use async_graphql::parser::types::OperationType;
+use libsqlite3_sys::{
+    SQLITE_INNOCUOUS, SQLITE_OK, SQLITE_TRANSIENT, SQLITE_UTF8, sqlite3_context,
+    sqlite3_context_db_handle, sqlite3_create_function_v2, sqlite3_result_double,
+    sqlite3_result_int64, sqlite3_result_null, sqlite3_result_text, sqlite3_value,
+    sqlite3_value_text,
+};
+use serde_json::Value;
+use sqlx::{SqliteConnection, sqlite::LockedSqliteHandle};
+
+use crate::auth::Claims;
+
+/// SQL function returning a claim of the request, e.g. `graphsql_claim('sub')`.
+pub const CLAIM_FUNCTION: &str = "graphsql_claim";
+
+/// SQL function returning the role of the request.
+pub const ROLE_FUNCTION: &str = "graphsql_role";
+
+/// SQL function returning every claim of the request as a JSON object.
+pub const CLAIMS_FUNCTION: &str = "graphsql_claims";
+
+/// SQL function returning metadata of the request, `graphsql_request('operation')` for the
+/// name of the operation and `graphsql_request('type')` for its type.
+pub const REQUEST_FUNCTION: &str = "graphsql_request";
+
+type SqlFunction = unsafe extern "C" fn(*mut sqlite3_context, c_int, *mut *mut sqlite3_value);
+
+/// The request exposed to SQL while its statements run.
+#[derive(Debug, Clone, Default)]
+pub struct RequestInfo {
+    pub claims: Claims,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<OperationType>,
+}
+
+impl RequestInfo {
+    /// Metadata returned by `graphsql_request(name)`.
+    fn get(&self, name: &str) -> Option<Value> {
+        match name {
+            "operation" => self.operation_name.clone().map(Value::String),
+            "type" => self.operation_type.map(|ty| Value::String(ty.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Request running statements through a connection.
+struct ActiveRequest {
+    id: u64,
+    info: RequestInfo,
+}
+
+/// Requests running statements through the connections with registered functions, by connection
+/// handle. Closing a connection removes it.
+static REQUESTS: LazyLock<Mutex<HashMap<usize, Option<ActiveRequest>>>> =
+    LazyLock::new(Default::default);
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Registers the functions exposing the request to SQL on a connection.
+///
+/// Outside of a [`RequestScope`] they return `NULL`.
+pub async fn register_functions(conn: &mut SqliteConnection) -> sqlx::Result<()> {
+    register(&mut conn.lock_handle().await?)
+}
+
+fn register(handle: &mut LockedSqliteHandle<'_>) -> sqlx::Result<()> {
+    let db = handle.as_raw_handle().as_ptr();
+
+    if REQUESTS.lock().unwrap().contains_key(&(db as usize)) {
+        return Ok(());
+    }
+
+    let functions: [(&str, c_int, SqlFunction); 4] = [
+        (CLAIM_FUNCTION, 1, claim_function),
+        (ROLE_FUNCTION, 0, role_function),
+        (CLAIMS_FUNCTION, 0, claims_function),
+        (REQUEST_FUNCTION, 1, request_function),
+    ];
+
+    for (name, args, function) in functions {
+        let c_name = CString::new(name).expect("function name");
+
+        // the value of the functions changes between requests, so they are not
+        // deterministic: generated columns and indexes can't use them, and CHECK
+        // constraints shouldn't, unlike defaults and triggers
+        let rc = unsafe {
+            sqlite3_create_function_v2(
+                db,
+                c_name.as_ptr(),
+                args,
+                SQLITE_UTF8 | SQLITE_INNOCUOUS,
+                db as *mut c_void,
+                Some(function),
+                None,
+                None,
+                Some(forget_connection),
+            )
+        };
+
+        if rc != SQLITE_OK {
+            return Err(match handle.last_error() {
+                Some(e) => sqlx::Error::Database(Box::new(e)),
+                None => sqlx::Error::Protocol(format!("Unable to register {}", name)),
+            });
+        }
+    }
+
+    REQUESTS.lock().unwrap().insert(db as usize, None);
+
+    Ok(())
+}
+
+/// Exposes a request to the statements run on a connection until dropped.
+///
+/// Defaults and triggers read its claims with `graphsql_claim(name)`, `graphsql_role()`
+/// and `graphsql_claims()`, its operation with `graphsql_request(name)`.
+pub struct RequestScope {
+    db: usize,
+    id: u64,
+}
+
+impl RequestScope {
+    pub async fn enter(
+        conn: &mut SqliteConnection,
+        request: Option<&RequestInfo>,
+    ) -> sqlx::Result<Self> {
+        let mut handle = conn.lock_handle().await?;
+
+        // connections of pools not created by the configuration have no functions yet
+        register(&mut handle)?;
+
+        let db = handle.as_raw_handle().as_ptr() as usize;
+        let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+
+        REQUESTS.lock().unwrap().insert(
+            db,
+            Some(ActiveRequest {
+                id,
+                info: request.cloned().unwrap_or_default(),
+            }),
+        );
+
+        Ok(Self { db, id })
+    }
+}
+
+impl Drop for RequestScope {
+    fn drop(&mut self) {
+        let mut requests = REQUESTS.lock().unwrap();
+
+        // the connection may already serve another request
+        if let Some(request) = requests.get_mut(&self.db)
+            && request
+                .as_ref()
+                .is_some_and(|request| request.id == self.id)
+        {
+            *request = None;
+        }
+    }
+}
+
+/// Runs `f` with the request of the connection evaluating a function.
+fn with_request<T>(ctx: *mut sqlite3_context, f: impl FnOnce(&RequestInfo) -> T) -> Option<T> {
+    let db = unsafe { sqlite3_context_db_handle(ctx) } as usize;
+
+    // a panic can't unwind into SQLite
+    let requests = REQUESTS.lock().ok()?;
+
+    requests.get(&db)?.as_ref().map(|request| f(&request.info))
+}
+
+/// Runs `f` with the text of the single argument of a function, `None` when it is `NULL`.
+fn with_name<T>(argv: *mut *mut sqlite3_value, f: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let name = unsafe { sqlite3_value_text(*argv) };
+
+    match name.is_null() {
+        true => None,
+        false => f(&unsafe { CStr::from_ptr(name as *const c_char) }.to_string_lossy()),
+    }
+}
+
+unsafe extern "C" fn claim_function(
+    ctx: *mut sqlite3_context,
+    _argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    let value = with_name(argv, |name| {
+        with_request(ctx, |request| request.claims.get(name).cloned()).flatten()
+    });
+
+    set_result(ctx, value.as_ref());
+}
+
+unsafe extern "C" fn request_function(
+    ctx: *mut sqlite3_context,
+    _argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    let value = with_name(argv, |name| {
+        with_request(ctx, |request| request.get(name)).flatten()
+    });
+
+    set_result(ctx, value.as_ref());
+}
+
+unsafe extern "C" fn role_function(
+    ctx: *mut sqlite3_context,
+    _argc: c_int,
+    _argv: *mut *mut sqlite3_value,
+) {
+    let role = with_request(ctx, |request| {
+        Value::String(request.claims.role().to_string())
+    });
+
+    set_result(ctx, role.as_ref());
+}
+
+unsafe extern "C" fn claims_function(
+    ctx: *mut sqlite3_context,
+    _argc: c_int,
+    _argv: *mut *mut sqlite3_value,
+) {
+    let claims = with_request(ctx, |request| {
+        Value::Object(request.claims.values().clone())
+    });
+
+    set_result(ctx, claims.as_ref());
+}
+
+/// Forgets a connection once SQLite closes it.
+unsafe extern "C" fn forget_connection(db: *mut c_void) {
+    if let Ok(mut requests) = REQUESTS.lock() {
+        requests.remove(&(db as usize));
+    }
+}
+
+/// Returns a JSON value as the SQL value it converts to, objects and arrays as JSON text.
+fn set_result(ctx: *mut sqlite3_context, value: Option<&Value>) {
+    let text = |text: &str| unsafe {
+        sqlite3_result_text(
+            ctx,
+            text.as_ptr() as *const c_char,
+            text.len() as c_int,
+            SQLITE_TRANSIENT(),
+        )
+    };
+
+    match value {
+        None | Some(Value::Null) => unsafe { sqlite3_result_null(ctx) },
+        Some(Value::Bool(value)) => unsafe { sqlite3_result_int64(ctx, *value as i64) },
+        Some(Value::Number(number)) => match number.as_i64() {
+            Some(number) => unsafe { sqlite3_result_int64(ctx, number) },
+            None => unsafe { sqlite3_result_double(ctx, number.as_f64().unwrap_or_default()) },
+        },
+        Some(Value::String(value)) => text(value),
+        Some(value) => text(&value.to_string()),
+    }
+}
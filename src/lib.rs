@@ -1,29 +1,54 @@
+use std::collections::HashMap;
+
 use async_graphql::{
-    dataloader::DataLoader,
-    dynamic::{Object, Schema, SchemaBuilder},
-    http::GraphiQLSource,
+    Executor,
+    dynamic::{Object, Scalar, Schema, SchemaBuilder, Subscription},
+    http::{ALL_WEBSOCKET_PROTOCOLS, GraphiQLSource},
+};
+use async_graphql_axum::{GraphQLBatchRequest, GraphQLProtocol, GraphQLResponse, GraphQLWebSocket};
+use axum::{
+    Extension, Router,
+    extract::{FromRef, State, WebSocketUpgrade},
+    http::HeaderMap,
+    response::{Html, Response},
+    routing::{get, post},
 };
-use async_graphql_axum::GraphQL;
-use axum::{Router, response::Html};
 use sqlx::SqlitePool;
 use tokio::net::TcpListener;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::{
+    auth::{ApiKeyValidator, AuthError, Authenticator, Claims, authenticate},
+    changes::ChangeBus,
     config::GraphSQLConfig,
-    loader::ColumnRowLoader,
-    parser::{Introspector, TableDef},
+    live::LiveSchema,
+    parser::{
+        ChangeOperation, ColDataType, FilterInput, Introspector, NullsOrder, PageInfo, SortOrder,
+        TableDef, TableRegistry,
+    },
+    permission::{Permissions, RoleSchemas},
+    policy::Policies,
     traits::GraphQLObjectOutput,
-    utils::StringFilter,
+    transaction::Transactions,
 };
 
+pub mod auth;
+pub mod changes;
 pub mod config;
+pub mod functions;
+pub mod live;
 pub mod loader;
 pub mod parser;
+pub mod permission;
+pub mod policy;
 pub mod resolvers;
 pub mod traits;
+pub mod transaction;
 pub mod utils;
 
+/// Path of the WebSocket endpoint serving subscriptions.
+pub const SUBSCRIPTION_PATH: &str = "/ws";
+
 pub struct GraphSQL {
     config: GraphSQLConfig,
 }
@@ -36,19 +61,68 @@ impl GraphSQL {
     pub async fn introspect(&self, db: &SqlitePool) -> async_graphql::Result<Vec<TableDef>> {
         info!("Starting database introspection");
 
-        Ok(TableDef::introspect(db).await?)
+        let mut tables = TableDef::introspect(db).await?;
+
+        // apply logical keys and foreign keys configured for views
+        for (name, view_config) in self.config.views.iter().flatten() {
+            let Some(view) = tables
+                .iter_mut()
+                .find(|table| table.is_view && &table.name == name)
+            else {
+                warn!("Configured view '{}' does not exist", name);
+                continue;
+            };
+
+            view.apply_view_config(view_config)?;
+        }
+
+        // configured descriptions replace the ones taken from SQL comments
+        for (name, description_config) in self.config.descriptions.iter().flatten() {
+            let Some(table) = tables.iter_mut().find(|table| &table.name == name) else {
+                warn!(
+                    "Table '{}' with configured descriptions does not exist",
+                    name
+                );
+                continue;
+            };
+
+            table.apply_description_config(description_config)?;
+        }
+
+        // tables linked as junctions even though they carry payload columns
+        for name in self.config.graphql.junction_tables.iter().flatten() {
+            let Some(table) = tables
+                .iter_mut()
+                .find(|table| !table.is_view && &table.name == name)
+            else {
+                warn!("Configured junction table '{}' does not exist", name);
+                continue;
+            };
+
+            table.is_junction = true;
+        }
+
+        TableDef::link_relations(&mut tables);
+
+        Ok(tables)
     }
 
     pub fn build_schema(&self, tables: Vec<TableDef>) -> async_graphql::Result<SchemaBuilder> {
         let mut query_object = Object::new("Query");
         let mut mutation_object = Object::new("Mutation");
+        let mut subscription_object = Subscription::new("Subscription");
+        let mut has_subscriptions = false;
 
         let mut table_objects = vec![];
         let mut inputs = vec![];
         let mut enums = vec![];
+        let mut scalars = vec![];
 
         info!("Converting {} tables to GraphQL objects", tables.len());
 
+        // nested writes look up the tables related to the one being written
+        let registry = TableRegistry::new(&tables);
+
         for table in tables {
             let name = table.name.to_string();
 
@@ -67,14 +141,35 @@ impl GraphSQL {
                 mutation_object = mutation_object.field(mutation);
             }
 
+            // add subscriptions
+            for subscription in graphql.subscriptions.into_iter() {
+                debug!("Adding subscription field for table: {}", name);
+                subscription_object = subscription_object.field(subscription);
+                has_subscriptions = true;
+            }
+
             // register types
             table_objects.push(graphql.table);
+            table_objects.extend(graphql.objects);
             inputs.extend(graphql.inputs);
             enums.extend(graphql.enums);
         }
 
-        // register filter operators
-        inputs.push(StringFilter::to_object());
+        // register pagination types
+        table_objects.push(PageInfo::to_graphql_object());
+
+        // register sort enums
+        enums.push(SortOrder::to_graphql_enum());
+        enums.push(NullsOrder::to_graphql_enum());
+
+        // register custom scalars and filter operators
+        for data_type in ColDataType::ALL {
+            if data_type.is_custom_scalar() {
+                scalars.push(Scalar::from(data_type.clone()));
+            }
+
+            inputs.push(FilterInput::from(data_type).0);
+        }
 
         info!(
             "Building GraphQL schema with {} objects and {} inputs",
@@ -85,10 +180,18 @@ impl GraphSQL {
         let mut schema = Schema::build(
             query_object.type_name(),
             Some(mutation_object.type_name()),
-            None,
+            has_subscriptions.then(|| subscription_object.type_name()),
         )
         .register(query_object)
-        .register(mutation_object);
+        .register(mutation_object)
+        .data(registry);
+
+        // a subscription root without fields is invalid
+        if has_subscriptions {
+            schema = schema
+                .register(subscription_object)
+                .register(ChangeOperation::to_graphql_enum());
+        }
 
         for object in table_objects {
             schema = schema.register(object);
@@ -102,44 +205,108 @@ impl GraphSQL {
             schema = schema.register(enum_item);
         }
 
+        for scalar in scalars {
+            schema = schema.register(scalar);
+        }
+
         info!("Successfully built GraphQL schema");
 
         Ok(self.config.graphql.apply(schema))
     }
 
-    pub async fn build(&self, db: &SqlitePool) -> async_graphql::Result<(Router, TcpListener)> {
+    /// Builds the schema of every role, executing each request with the schema of its role.
+    pub async fn build_schemas(&self, db: &SqlitePool) -> async_graphql::Result<RoleSchemas> {
         let mut tables = self.introspect(db).await?;
 
-        // remove private tables
+        let api_key_table = self
+            .config
+            .auth
+            .as_ref()
+            .and_then(|auth| auth.api_key.as_ref())
+            .map(|api_key| api_key.table.as_str());
+
+        // remove private tables, including the one holding the API keys
         tables = tables
             .into_iter()
-            .filter(|table| table.name != "_sqlx_migrations")
+            .filter(|table| {
+                table.name != "_sqlx_migrations" && Some(table.name.as_str()) != api_key_table
+            })
             .collect::<Vec<_>>();
 
-        let schema = self.build_schema(tables)?;
+        let policies = Policies::try_from(self.config.policies.as_deref().unwrap_or_default())?;
 
-        let schema = schema
-            .data(DataLoader::new(
-                ColumnRowLoader { pool: db.clone() },
-                tokio::spawn,
-            ))
-            .data(db.clone())
-            .finish()?;
+        for name in policies.tables() {
+            if !tables.iter().any(|table| &table.name == name) {
+                warn!("Table '{}' with configured policies does not exist", name);
+            }
+        }
+
+        let permissions = Permissions::from(self.config.permissions.as_deref().unwrap_or_default());
 
-        let mut router = Router::new();
+        for name in permissions.tables() {
+            if !tables.iter().any(|table| &table.name == name) {
+                warn!(
+                    "Table '{}' with configured permissions does not exist",
+                    name
+                );
+            }
+        }
+
+        let changes = ChangeBus::default();
+
+        // every role with permissions of its own gets a schema of its own
+        let mut roles = HashMap::new();
+
+        for role in permissions.roles() {
+            debug!("Building GraphQL schema of role '{}'", role);
+
+            let tables = permissions.tables_for(Some(&role), &tables)?;
+            let schema = self.finish_schema(tables, db, &changes, &policies)?;
+
+            roles.insert(role, schema);
+        }
+
+        let tables = permissions.tables_for(None, &tables)?;
+        let schema = self.finish_schema(tables, db, &changes, &policies)?;
+
+        Ok(RoleSchemas::new(schema, roles))
+    }
+
+    pub async fn build(&self, db: &SqlitePool) -> async_graphql::Result<(Router, TcpListener)> {
+        let state = ServerState {
+            schema: self.build_schemas(db).await?,
+            auth: match &self.config.auth {
+                Some(auth) => match &auth.api_key {
+                    Some(api_key) => Authenticator::try_from(auth)?
+                        .with_api_keys(ApiKeyValidator::new(api_key.clone(), db.clone())),
+                    None => Authenticator::try_from(auth)?,
+                },
+                None => Authenticator::default(),
+            },
+        };
+
+        // requests with an invalid token are rejected before being executed
+        let mut graphql_route = post(graphql_handler).route_layer(
+            axum::middleware::from_fn_with_state(state.auth.clone(), authenticate),
+        );
 
         if self.config.graphql.enable_playground.unwrap_or(true) {
-            router = router.route(
-                "/",
-                axum::routing::get(|| async move {
-                    Html(GraphiQLSource::build().endpoint("/").finish())
-                })
-                .post_service(GraphQL::new(schema)),
-            );
-        } else {
-            router = router.route("/", axum::routing::post_service(GraphQL::new(schema)));
+            graphql_route = graphql_route.get(|| async move {
+                Html(
+                    GraphiQLSource::build()
+                        .endpoint("/")
+                        .subscription_endpoint(SUBSCRIPTION_PATH)
+                        .finish(),
+                )
+            });
         }
 
+        // subscriptions and live queries are served over graphql-ws
+        let router = Router::new()
+            .route("/", graphql_route)
+            .route(SUBSCRIPTION_PATH, get(subscription_handler))
+            .with_state(state);
+
         let listener = TcpListener::bind(format!(
             "{}:{}",
             self.config.server.host, self.config.server.port
@@ -148,4 +315,70 @@ impl GraphSQL {
 
         Ok((router, listener))
     }
+
+    /// Builds the schema of the given tables with the data its resolvers share.
+    fn finish_schema(
+        &self,
+        tables: Vec<TableDef>,
+        db: &SqlitePool,
+        changes: &ChangeBus,
+        policies: &Policies,
+    ) -> async_graphql::Result<LiveSchema> {
+        let schema = self
+            .build_schema(tables)?
+            .data(db.clone())
+            .data(changes.clone())
+            .data(policies.clone())
+            .extension(Transactions::new(
+                db.clone(),
+                self.config.graphql.transactional_mutations.unwrap_or(false),
+            ))
+            .finish()?;
+
+        Ok(LiveSchema::new(schema, changes.clone()))
+    }
+}
+
+/// State shared by the handlers of the server.
+#[derive(Clone, FromRef)]
+struct ServerState {
+    schema: RoleSchemas,
+    auth: Authenticator,
+}
+
+async fn graphql_handler(
+    State(schema): State<RoleSchemas>,
+    claims: Option<Extension<Claims>>,
+    request: GraphQLBatchRequest,
+) -> GraphQLResponse {
+    let mut request = request.into_inner();
+
+    if let Some(Extension(claims)) = claims {
+        request = request.data(claims);
+    }
+
+    schema.execute_batch(request).await.into()
+}
+
+async fn subscription_handler(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AuthError> {
+    // the token is usually sent with the connection_init message instead
+    let claims = match state.auth.has_credentials(&headers) {
+        true => state.auth.authenticate_headers(&headers).await?,
+        false => None,
+    };
+
+    Ok(upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, state.schema, protocol)
+                .on_connection_init(move |payload| async move {
+                    state.auth.connection_data(&payload, claims).await
+                })
+                .serve()
+        }))
 }
@@ -0,0 +1,258 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_graphql::{
+    Data, Executor, Request, Response, ServerError, Value, Variables,
+    dynamic::Schema,
+    futures_util::{
+        StreamExt,
+        stream::{self, BoxStream},
+    },
+    parser::types::{ExecutableDocument, OperationType},
+};
+use tokio::{
+    sync::broadcast::{
+        Receiver,
+        error::{RecvError, TryRecvError},
+    },
+    time::Instant,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    auth::Claims,
+    changes::{ChangeBus, TableChange},
+    utils::{selected_operation, take_directive},
+};
+
+/// Operation directive turning a query into a live query.
+pub const LIVE_DIRECTIVE: &str = "live";
+
+/// Tables read while resolving a live query, collected by the resolvers.
+#[derive(Clone, Default)]
+pub struct LiveTables(Arc<Mutex<HashSet<String>>>);
+
+impl LiveTables {
+    pub fn insert(&self, table: &str) {
+        let mut tables = self.0.lock().unwrap();
+
+        if !tables.contains(table) {
+            tables.insert(table.to_string());
+        }
+    }
+
+    fn take(&self) -> HashSet<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Executor of the schema serving live queries on the subscription transport.
+///
+/// A query operation marked with `@live`, e.g. `query @live(throttle: 500) { ... }`, is
+/// re-run whenever a change is committed to a table it read, and its result is pushed
+/// again when it differs from the previous one. `throttle` is the minimal delay between
+/// two runs in milliseconds. Other operations are executed by the schema.
+#[derive(Clone)]
+pub struct LiveSchema {
+    schema: Schema,
+    changes: ChangeBus,
+}
+
+impl LiveSchema {
+    pub fn new(schema: Schema, changes: ChangeBus) -> Self {
+        Self { schema, changes }
+    }
+
+    /// Removes the `@live` directive of a query operation, returning the live query it
+    /// describes.
+    fn take_live_query(
+        &self,
+        request: &mut Request,
+    ) -> Result<Option<LiveQuery>, Vec<ServerError>> {
+        let mut document = request.parsed_query().map_err(|e| vec![e])?.clone();
+
+        let Some(operation) = selected_operation(&mut document, request.operation_name.as_deref())
+        else {
+            return Ok(None);
+        };
+
+        if operation.ty != OperationType::Query {
+            return Ok(None);
+        }
+
+        let Some(live) = take_directive(operation, LIVE_DIRECTIVE) else {
+            return Ok(None);
+        };
+
+        let throttle = match live.get_argument("throttle") {
+            Some(value) => {
+                let pos = value.pos;
+
+                // the definition of a variable only used by @live would be reported as
+                // unused once the directive is removed
+                let value = value.node.clone().into_const();
+
+                let invalid = || {
+                    vec![ServerError::new(
+                        "Argument throttle of @live must be a non-negative Int literal",
+                        Some(pos),
+                    )]
+                };
+
+                match value {
+                    Some(Value::Null) => None,
+                    Some(Value::Number(ms)) => {
+                        Some(Duration::from_millis(ms.as_u64().ok_or_else(invalid)?))
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+            None => None,
+        };
+
+        request.set_parsed_query(document.clone());
+
+        Ok(Some(LiveQuery {
+            schema: self.schema.clone(),
+            changes: self.changes.subscribe(),
+            query: request.query.clone(),
+            operation_name: request.operation_name.clone(),
+            variables: request.variables.clone(),
+            document,
+            throttle,
+            data: Some(std::mem::take(&mut request.data)),
+            claims: None,
+            tables: HashSet::new(),
+            previous: None,
+            last_run: None,
+        }))
+    }
+}
+
+impl Executor for LiveSchema {
+    async fn execute(&self, mut request: Request) -> Response {
+        // a single response is all that can be sent over HTTP
+        match self.take_live_query(&mut request) {
+            Ok(_) => self.schema.execute(request).await,
+            Err(errors) => Response::from_errors(errors),
+        }
+    }
+
+    fn execute_stream(
+        &self,
+        mut request: Request,
+        session_data: Option<Arc<Data>>,
+    ) -> BoxStream<'static, Response> {
+        let mut live = match self.take_live_query(&mut request) {
+            Ok(Some(live)) => live,
+            Ok(None) => {
+                return self
+                    .schema
+                    .execute_stream_with_session_data(request, session_data.unwrap_or_default())
+                    .boxed();
+            }
+            Err(errors) => return stream::once(async { Response::from_errors(errors) }).boxed(),
+        };
+
+        // the session data of the connection can't be passed to queries, but its claims
+        // authenticate every run
+        live.claims = session_data.as_deref().and_then(Claims::from_data).cloned();
+
+        stream::unfold(Some(live), |live| async move {
+            let mut live = live?;
+
+            loop {
+                if live.last_run.is_some() && !live.wait_for_change().await {
+                    return None;
+                }
+
+                let response = live.run().await;
+
+                // nothing can change the result of a query not reading any table
+                if live.tables.is_empty() {
+                    return Some((response, None));
+                }
+
+                if response.is_err() || live.previous.as_ref() != Some(&response.data) {
+                    live.previous = Some(response.data.clone());
+                    return Some((response, Some(live)));
+                }
+
+                debug!("Live query result is unchanged");
+            }
+        })
+        .boxed()
+    }
+}
+
+/// State of a live query between two runs.
+struct LiveQuery {
+    schema: Schema,
+    changes: Receiver<TableChange>,
+    query: String,
+    operation_name: Option<String>,
+    variables: Variables,
+    document: ExecutableDocument,
+    throttle: Option<Duration>,
+    data: Option<Data>, // data of the request, only available to the first run
+    claims: Option<Claims>,
+    tables: HashSet<String>,
+    previous: Option<Value>,
+    last_run: Option<Instant>,
+}
+
+impl LiveQuery {
+    async fn run(&mut self) -> Response {
+        let tables = LiveTables::default();
+
+        let mut request = Request::new(self.query.clone()).variables(self.variables.clone());
+        request.operation_name = self.operation_name.clone();
+        request.data = self.data.take().unwrap_or_default();
+        request.set_parsed_query(self.document.clone());
+
+        if let Some(claims) = &self.claims {
+            request = request.data(claims.clone());
+        }
+
+        self.last_run = Some(Instant::now());
+
+        let response = self.schema.execute(request.data(tables.clone())).await;
+
+        self.tables = tables.take();
+
+        debug!("Live query read tables: {:?}", self.tables);
+
+        response
+    }
+
+    /// Waits for a change to one of the tables read by the last run, returning `false`
+    /// once no more changes are published.
+    async fn wait_for_change(&mut self) -> bool {
+        loop {
+            match self.changes.recv().await {
+                Ok(change) if self.tables.contains(&change.table) => break,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Live query missed {} changes, running it again", skipped);
+                    break;
+                }
+                Err(RecvError::Closed) => return false,
+            }
+        }
+
+        if let (Some(throttle), Some(last_run)) = (self.throttle, self.last_run) {
+            tokio::time::sleep_until(last_run + throttle).await;
+        }
+
+        // the next run sees every change published in the meantime
+        while matches!(
+            self.changes.try_recv(),
+            Ok(_) | Err(TryRecvError::Lagged(_))
+        ) {}
+
+        true
+    }
+}
@@ -1,20 +1,43 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_graphql::dataloader::*;
-use sea_query::{Alias, Expr, Iden, Query, SqliteQueryBuilder};
-use sqlx::SqlitePool;
+use sea_query::{
+    Alias, Asterisk, Condition, Expr, Iden, JoinType, Order, Query, SqliteQueryBuilder,
+    WindowStatement,
+};
 use tracing::{debug, instrument};
 
+use crate::{
+    functions::RequestInfo,
+    parser::ColDataType,
+    resolvers::DynamicSortCondition,
+    transaction::Database,
+    utils::{json_array_expr, json_to_sql_value, json_value_expr},
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ColumnRowDef {
     pub table: Alias,
     pub column: Alias,
-    pub value: serde_json::Value,
-    pub primary_column: Alias,
+    pub data_type: Option<ColDataType>, // selects the column for output, `None` loads it as stored
+    pub value: Vec<serde_json::Value>,
+    pub primary_columns: Vec<Alias>,
+    pub filter: Option<String>, // rows not matching it are not loaded
 }
 
+/// Keys loaded with a single query: table, primary key columns, column, its data type and
+/// the filter of the rows.
+type ColumnGroup = (
+    Alias,
+    Vec<Alias>,
+    Alias,
+    Option<ColDataType>,
+    Option<String>,
+);
+
 pub struct ColumnRowLoader {
-    pub pool: SqlitePool,
+    pub db: Database,
+    pub request: RequestInfo, // exposed to the SQL functions while loading
 }
 
 impl Loader<ColumnRowDef> for ColumnRowLoader {
@@ -27,14 +50,15 @@ impl Loader<ColumnRowDef> for ColumnRowLoader {
         keys: &[ColumnRowDef],
     ) -> Result<std::collections::HashMap<ColumnRowDef, Self::Value>, Self::Error> {
         debug!("Loading {} keys", keys.len());
-        let mut grouped_keys: HashMap<(Alias, Alias, Alias), Vec<serde_json::Value>> =
-            HashMap::new();
+        let mut grouped_keys: HashMap<ColumnGroup, Vec<Vec<serde_json::Value>>> = HashMap::new();
 
         for key in keys {
             let group = (
                 key.table.clone(),
-                key.primary_column.clone(),
+                key.primary_columns.clone(),
                 key.column.clone(),
+                key.data_type.clone(),
+                key.filter.clone(),
             );
             grouped_keys
                 .entry(group)
@@ -44,29 +68,66 @@ impl Loader<ColumnRowDef> for ColumnRowLoader {
 
         debug!("Grouped keys into {} queries", grouped_keys.len());
         let mut final_results: HashMap<ColumnRowDef, Self::Value> = HashMap::new();
+        let mut conn = self.db.acquire().await?;
 
-        for ((table, pk_col, val_col), pk_values) in grouped_keys {
+        conn.enter_request(Some(&self.request)).await?;
+
+        for ((table, pk_cols, val_col, data_type, filter), pk_values) in grouped_keys {
             debug!(
-                "Processing query for table: {:?}, pk_col: {:?}, val_col: {:?}, {} values",
+                "Processing query for table: {:?}, pk_cols: {:?}, val_col: {:?}, {} values",
                 table,
-                pk_col,
+                pk_cols,
                 val_col,
                 pk_values.len()
             );
 
-            let sql = Query::select()
+            // single column keys use a plain IN, composite keys match every column of a key
+            // with IS, since the logical key of a view may contain nulls
+            let condition = match &pk_cols[..] {
+                [pk_col] => Condition::all().add(
+                    Expr::col(pk_col.clone())
+                        .is_in(pk_values.iter().flatten().map(json_to_sql_value)),
+                ),
+                _ => pk_values
+                    .into_iter()
+                    .fold(Condition::any(), |condition, values| {
+                        condition.add(pk_cols.iter().zip(values).fold(
+                            Condition::all(),
+                            |key, (pk_col, value)| {
+                                key.add(Expr::col(pk_col.clone()).is(json_to_sql_value(&value)))
+                            },
+                        ))
+                    }),
+            };
+
+            let mut query = Query::select();
+
+            query
                 .from(table.clone())
                 .expr(Expr::cust(format!(
                     "json_object('id', {}, 'value', {})",
-                    pk_col.to_string(),
-                    val_col.to_string()
+                    json_array_expr(
+                        &pk_cols
+                            .iter()
+                            .map(|col| col.to_string())
+                            .collect::<Vec<_>>()
+                    ),
+                    match &data_type {
+                        Some(data_type) => json_value_expr(&val_col.to_string(), data_type),
+                        None => format!("\"{}\"", val_col.to_string()),
+                    }
                 )))
-                .and_where(Expr::col(pk_col.clone()).is_in(pk_values))
-                .to_string(SqliteQueryBuilder);
+                .cond_where(condition);
+
+            if let Some(filter) = &filter {
+                query.and_where(Expr::cust(filter));
+            }
+
+            let sql = query.to_string(SqliteQueryBuilder);
 
             debug!("Generated SQL: {}", sql);
             let rows = sqlx::query_as::<_, (serde_json::Value,)>(&sql)
-                .fetch_all(&self.pool)
+                .fetch_all(&mut *conn)
                 .await?;
             debug!("Fetched {} rows from database", rows.len());
 
@@ -74,9 +135,15 @@ impl Loader<ColumnRowDef> for ColumnRowLoader {
                 final_results.insert(
                     ColumnRowDef {
                         table: table.clone(),
-                        primary_column: pk_col.clone(),
+                        primary_columns: pk_cols.clone(),
                         column: val_col.clone(),
-                        value: row.get("id").unwrap().clone(),
+                        data_type: data_type.clone(),
+                        filter: filter.clone(),
+                        value: row
+                            .get("id")
+                            .and_then(|id| id.as_array())
+                            .cloned()
+                            .unwrap_or_default(),
                     },
                     row.get("value").unwrap().clone(),
                 );
@@ -88,3 +155,197 @@ impl Loader<ColumnRowDef> for ColumnRowLoader {
         Ok(final_results)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RelationRowDef {
+    pub table: Alias,
+    pub foreign_column: Alias,
+    pub primary_columns: Vec<Alias>,
+    pub filter: Option<String>,
+    pub sorts: Vec<DynamicSortCondition>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+    pub junction: Option<JunctionRowDef>,
+    pub value: serde_json::Value,
+}
+
+/// Junction table joined in between the parent and the related table of a many-to-many relation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JunctionRowDef {
+    pub table: Alias,           // name of the junction table
+    pub local_column: Alias,    // junction column referencing the parent
+    pub remote_column: Alias,   // junction column referencing the related table
+    pub foreign_column: Alias,  // column of the related table referenced by `remote_column`
+    pub filter: Option<String>, // select policy of the junction table
+}
+
+pub struct RelationLoader {
+    pub db: Database,
+    pub request: RequestInfo, // exposed to the SQL functions while loading
+}
+
+impl Loader<RelationRowDef> for RelationLoader {
+    type Error = Arc<sqlx::Error>;
+    type Value = Vec<serde_json::Value>;
+
+    #[instrument(skip(self), level = "debug")]
+    async fn load(
+        &self,
+        keys: &[RelationRowDef],
+    ) -> Result<HashMap<RelationRowDef, Self::Value>, Self::Error> {
+        debug!("Loading {} keys", keys.len());
+
+        // keys that only differ by the parent value can share a single query
+        let mut grouped_keys: HashMap<RelationRowDef, Vec<serde_json::Value>> = HashMap::new();
+
+        for key in keys {
+            grouped_keys
+                .entry(RelationRowDef {
+                    value: serde_json::Value::Null,
+                    ..key.clone()
+                })
+                .or_default()
+                .push(key.value.clone());
+        }
+
+        debug!("Grouped keys into {} queries", grouped_keys.len());
+        let mut final_results: HashMap<RelationRowDef, Self::Value> = HashMap::new();
+        let mut conn = self.db.acquire().await?;
+
+        conn.enter_request(Some(&self.request)).await?;
+
+        for (group, parent_values) in grouped_keys {
+            // number the children of every parent so each one can be paginated independently
+            let mut window = WindowStatement::partition_by(group.foreign_column.clone());
+
+            for sort in group.sorts.iter() {
+                sort.apply(&mut window);
+            }
+
+            let mut inner = Query::select();
+
+            match &group.junction {
+                // expose the related rows together with the parent key of the junction, under
+                // the related table's name so the filter and sorts keep working unqualified
+                Some(junction) => {
+                    let mut related = Query::select();
+
+                    related
+                        .column((group.table.clone(), Asterisk))
+                        .expr_as(
+                            Expr::col((junction.table.clone(), junction.local_column.clone())),
+                            group.foreign_column.clone(),
+                        )
+                        .from(group.table.clone());
+
+                    // junction rows hidden by their policies don't link the related rows,
+                    // filtered apart so the policy's columns can't clash with the related ones
+                    let mut links = Query::select();
+
+                    links.column(Asterisk).from(junction.table.clone());
+
+                    if let Some(filter) = &junction.filter {
+                        links.and_where(Expr::cust(filter));
+                    }
+
+                    related.join_subquery(
+                        JoinType::InnerJoin,
+                        links,
+                        junction.table.clone(),
+                        Expr::col((junction.table.clone(), junction.remote_column.clone()))
+                            .equals((group.table.clone(), junction.foreign_column.clone())),
+                    );
+
+                    // `*` leaves out the implicit rowid, so select the key explicitly
+                    for pk_col in group.primary_columns.iter() {
+                        related.expr_as(
+                            Expr::col((group.table.clone(), pk_col.clone())),
+                            pk_col.clone(),
+                        );
+                    }
+
+                    inner.from_subquery(related, group.table.clone());
+                }
+                None => {
+                    inner.from(group.table.clone());
+                }
+            }
+
+            inner
+                .expr_as(
+                    Expr::col(group.foreign_column.clone()),
+                    Alias::new("parent"),
+                )
+                .expr_as(
+                    Expr::cust(json_array_expr(
+                        &group
+                            .primary_columns
+                            .iter()
+                            .map(|col| col.to_string())
+                            .collect::<Vec<_>>(),
+                    )),
+                    Alias::new("id"),
+                )
+                .expr_window_as(Expr::cust("ROW_NUMBER()"), window, Alias::new("row_number"))
+                .and_where(
+                    Expr::col(group.foreign_column.clone())
+                        .is_in(parent_values.iter().map(json_to_sql_value)),
+                );
+
+            if let Some(filter) = &group.filter {
+                inner.and_where(Expr::cust(filter));
+            }
+
+            let mut query = Query::select();
+
+            query
+                .from_subquery(inner, Alias::new("children"))
+                .expr(Expr::cust("json_object('parent', parent, 'id', json(id))"))
+                .order_by(Alias::new("parent"), Order::Asc)
+                .order_by(Alias::new("row_number"), Order::Asc);
+
+            let offset = group.offset.unwrap_or(0);
+
+            if offset > 0 {
+                query.and_where(Expr::col(Alias::new("row_number")).gt(offset));
+            }
+
+            if let Some(limit) = group.limit {
+                query.and_where(Expr::col(Alias::new("row_number")).lte(offset + limit));
+            }
+
+            let sql = query.to_string(SqliteQueryBuilder);
+
+            debug!("Generated SQL: {}", sql);
+            let rows = sqlx::query_as::<_, (serde_json::Value,)>(&sql)
+                .fetch_all(&mut *conn)
+                .await?;
+            debug!("Fetched {} rows from database", rows.len());
+
+            // parents without children still get an (empty) entry
+            for value in parent_values {
+                final_results.insert(
+                    RelationRowDef {
+                        value,
+                        ..group.clone()
+                    },
+                    vec![],
+                );
+            }
+
+            for (row,) in rows {
+                final_results
+                    .entry(RelationRowDef {
+                        value: row.get("parent").unwrap().clone(),
+                        ..group.clone()
+                    })
+                    .or_default()
+                    .push(row.get("id").unwrap().clone());
+            }
+        }
+
+        debug!("Returning {} results", final_results.len());
+
+        Ok(final_results)
+    }
+}
@@ -1,18 +1,29 @@
+use std::collections::HashMap;
+
 use anyhow::anyhow;
 use async_graphql::dynamic::{
-    Enum, EnumItem, Field, InputObject, InputValue, Object, Scalar, TypeRef,
+    Enum, EnumItem, Field, InputObject, InputValue, Object, Scalar, SubscriptionField, TypeRef,
 };
-use sqlx::SqlitePool;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use sqlx::{SqlitePool, sqlite::SqliteOperation};
 use stringcase::Caser;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::{
+    config::{DescriptionConfig, PermissionConfig, TableOperation, ViewConfig},
     resolvers::{
-        column_resolver, delete_resolver, foreign_key_resolver, insert_resolver, list_resolver,
-        update_resolver, view_resolver,
+        aggregate_resolver, changed_subscription_resolver, column_resolver, connect_resolver,
+        connection_resolver, delete_many_resolver, delete_resolver, disconnect_resolver,
+        foreign_key_resolver, group_by_resolver, insert_many_resolver, insert_resolver,
+        junction_resolver, list_resolver, relation_resolver, total_count_resolver,
+        typed_value_resolver, update_many_resolver, update_resolver, upsert_resolver,
+        value_resolver, view_resolver,
+    },
+    traits::{GraphQLObjectOutput, WithDescription},
+    utils::{
+        SqlComments, check_constraint_enums, decode_hex, is_valid_date, is_valid_datetime,
+        sql_comments, strip_id_suffix,
     },
-    traits::GraphQLObjectOutput,
-    utils::strip_id_suffix,
 };
 
 pub trait Introspector
@@ -26,20 +37,54 @@ where
 
 #[derive(Clone, Debug)]
 pub struct TableDef {
-    pub name: String,                // name of the table
-    pub columns: Vec<ColDef>,        // column definitions
-    pub description: Option<String>, // table description
+    pub name: String,                           // name of the table
+    pub columns: Vec<ColDef>,                   // column definitions
+    pub description: Option<String>,            // table description
+    pub relations: Vec<RelationDef>, // foreign keys in other tables pointing to this table
+    pub junctions: Vec<JunctionDef>, // many-to-many relations through junction tables
+    pub is_view: bool,               // read-only SQL view
+    pub without_rowid: bool,         // WITHOUT ROWID table, whose changes SQLite doesn't report
+    pub is_junction: bool,           // configured as a junction despite its payload columns
+    pub unique_keys: Vec<Vec<String>>, // columns of each UNIQUE constraint or index
+    pub denied_operations: Vec<TableOperation>, // operations the permissions of the schema deny
+}
+
+/// Every table of the schema by name, for resolvers writing to related tables.
+#[derive(Clone, Debug, Default)]
+pub struct TableRegistry(HashMap<String, TableDef>);
+
+impl TableRegistry {
+    pub fn new(tables: &[TableDef]) -> Self {
+        Self(
+            tables
+                .iter()
+                .map(|table| (table.name.clone(), table.clone()))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, name: &str) -> anyhow::Result<&TableDef> {
+        self.0
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown table '{}'", name))
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ColDef {
-    pub table_name: String,          // name of the table that it belongs to
-    pub name: String,                // name of the column
-    pub data_type: ColDataType,      // data type of the column
-    pub not_null: bool,              // has not null constraint
-    pub is_primary: bool,            // is primary key
+    pub table_name: String,            // name of the table that it belongs to
+    pub name: String,                  // name of the column
+    pub data_type: ColDataType,        // data type of the column
+    pub not_null: bool,                // has not null constraint
+    pub is_primary: bool,              // is primary key
+    pub primary_key_ordinal: usize,    // position in the primary key, 0 if not part of it
+    pub is_rowid: bool, // implicit rowid of a table without a declared primary key, read-only
+    pub auto_increment: bool, // INTEGER PRIMARY KEY aliasing the rowid, assigned on insert
     pub description: Option<String>, // column description / comment
+    pub default_value: Option<String>, // default value expression
+    pub enum_values: Vec<String>, // allowed values from a `CHECK (col IN (...))` constraint
     pub relationship: Option<ForeignColDef>,
+    pub writable: bool, // can be set by inserts and updates under the permissions of the schema
 }
 
 #[derive(Clone, Debug)]
@@ -48,20 +93,64 @@ pub struct ForeignColDef {
     pub from: String,  // The name of the column in the child table (the table you're querying).
     pub to: String,    // The name of the column in the parent table that is referenced.
     pub main_table: String, // the name of the current table that is resides in
+    pub readable: bool, // the parent rows can be viewed under the permissions of the schema
 }
 
 #[derive(Clone, Debug)]
+pub struct RelationDef {
+    pub foreign: ForeignColDef, // the foreign key in the child table
+    pub table: TableDef,        // the child table, without its own relations
+}
+
+#[derive(Clone, Debug)]
+pub struct JunctionDef {
+    pub junction: String,      // name of the junction table
+    pub local: ForeignColDef,  // the foreign key in the junction table pointing to this table
+    pub remote: ForeignColDef, // the foreign key in the junction table pointing to the other table
+    pub table: TableDef,       // the other table, without its own relations
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ColDataType {
     String,
     Integer,
+    BigInt, // 64-bit integers that may not fit GraphQL's `Int`
     Float,
     Boolean,
+    Bytes, // blobs, exposed as base64
+    Json,
+    DateTime,
+    Date,
 }
 
-pub struct ListQuery(async_graphql::dynamic::Field);
+pub struct ListQuery(
+    async_graphql::dynamic::Field,
+    Vec<async_graphql::dynamic::InputObject>,
+);
+
+/// `totalCount` of the offset-paginated list query, whose plain list can't carry it.
+pub struct TotalCountQuery(async_graphql::dynamic::Field);
 
 pub struct ViewQuery(async_graphql::dynamic::Field);
 
+pub struct AggregateQuery(
+    async_graphql::dynamic::Field,
+    Vec<async_graphql::dynamic::Object>,
+);
+
+pub struct GroupByQuery(
+    async_graphql::dynamic::Field,
+    Vec<async_graphql::dynamic::Object>,
+    Vec<async_graphql::dynamic::InputObject>,
+);
+
+pub struct HavingInput(Vec<async_graphql::dynamic::InputObject>);
+
+pub struct ConnectionQuery(
+    async_graphql::dynamic::Field,
+    Vec<async_graphql::dynamic::Object>,
+);
+
 pub struct NodeInputValues(
     async_graphql::dynamic::InputValue,
     async_graphql::dynamic::InputValue,
@@ -72,273 +161,1796 @@ pub struct InsertMutation(
     Vec<async_graphql::dynamic::InputObject>,
 );
 
-pub struct UpdateMutation(
-    async_graphql::dynamic::Field,
-    Vec<async_graphql::dynamic::InputObject>,
-);
+pub struct UpdateMutation(
+    async_graphql::dynamic::Field,
+    Vec<async_graphql::dynamic::InputObject>,
+);
+
+pub struct DeleteMutation(async_graphql::dynamic::Field);
+
+pub struct InsertManyMutation(async_graphql::dynamic::Field);
+
+/// Input fields writing related rows along with a row: one per foreign key, connecting or
+/// creating the referenced row, and one per reverse relationship, creating children.
+pub struct RelationInputValues(Vec<async_graphql::dynamic::InputValue>);
+
+/// Input types used by the relationship fields of insert and update inputs.
+pub struct RelationInputs(Vec<async_graphql::dynamic::InputObject>);
+
+pub struct UpsertMutation(
+    async_graphql::dynamic::Field,
+    Vec<async_graphql::dynamic::InputObject>,
+    async_graphql::dynamic::Enum,
+);
+
+pub struct UpdateManyMutation(async_graphql::dynamic::Field);
+
+pub struct DeleteManyMutation(async_graphql::dynamic::Field);
+
+pub struct ChangedSubscription(
+    async_graphql::dynamic::SubscriptionField,
+    async_graphql::dynamic::Object,
+);
+
+pub struct MutationResponse(async_graphql::dynamic::Object);
+
+pub struct PkInput(Option<async_graphql::dynamic::InputObject>);
+
+pub struct ConnectMutation(async_graphql::dynamic::Field);
+
+pub struct DisconnectMutation(async_graphql::dynamic::Field);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn to_graphql_enum() -> async_graphql::dynamic::Enum {
+        Enum::new("sort_order".to_pascal_case())
+            .item(EnumItem::new("ASC"))
+            .item(EnumItem::new("DESC"))
+    }
+}
+
+impl TryFrom<&str> for SortOrder {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "ASC" => Ok(Self::Asc),
+            "DESC" => Ok(Self::Desc),
+            _ => Err(anyhow!("unsupported sort order")),
+        }
+    }
+}
+
+/// Kind of write reported to subscriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl ChangeOperation {
+    pub fn to_graphql_enum() -> async_graphql::dynamic::Enum {
+        Enum::new("change_operation".to_pascal_case())
+            .item(EnumItem::new("INSERT"))
+            .item(EnumItem::new("UPDATE"))
+            .item(EnumItem::new("DELETE"))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Insert => "INSERT",
+            Self::Update => "UPDATE",
+            Self::Delete => "DELETE",
+        }
+    }
+}
+
+impl TryFrom<SqliteOperation> for ChangeOperation {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SqliteOperation) -> Result<Self, Self::Error> {
+        match value {
+            SqliteOperation::Insert => Ok(Self::Insert),
+            SqliteOperation::Update => Ok(Self::Update),
+            SqliteOperation::Delete => Ok(Self::Delete),
+            SqliteOperation::Unknown(code) => Err(anyhow!("unsupported operation {}", code)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+impl NullsOrder {
+    pub fn to_graphql_enum() -> async_graphql::dynamic::Enum {
+        Enum::new("nulls_order".to_pascal_case())
+            .item(EnumItem::new("FIRST"))
+            .item(EnumItem::new("LAST"))
+    }
+}
+
+impl TryFrom<&str> for NullsOrder {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "FIRST" => Ok(Self::First),
+            "LAST" => Ok(Self::Last),
+            _ => Err(anyhow!("unsupported nulls order")),
+        }
+    }
+}
+
+impl From<TableDef> for async_graphql::dynamic::Enum {
+    fn from(value: TableDef) -> Self {
+        let mut enum_field = Enum::new(format!("{}_enum_fields", value.name).to_pascal_case());
+
+        for col in value.columns.iter() {
+            enum_field = enum_field.item(EnumItem::new(col.enum_item_name()))
+        }
+
+        enum_field
+    }
+}
+
+impl TableDef {
+    /// Finds the column matching an item of the `<table>_enum_fields` enum.
+    pub fn column_by_enum_item(&self, item: &str) -> Option<&ColDef> {
+        self.columns.iter().find(|col| col.enum_item_name() == item)
+    }
+
+    /// Name of the field listing the children of a reverse relationship.
+    ///
+    /// This is the pluralized child table name, suffixed with the foreign key when
+    /// the child table references this table more than once
+    /// (e.g. `ordersByBillingAddress` and `ordersByShippingAddress`).
+    pub fn relation_field_name(&self, relation: &RelationDef) -> String {
+        let children = pluralizer::pluralize(&relation.table.name, 2, false);
+
+        let is_ambiguous = self
+            .relations
+            .iter()
+            .filter(|other| other.table.name == relation.table.name)
+            .count()
+            > 1;
+
+        if is_ambiguous {
+            format!(
+                "{}_by_{}",
+                children,
+                strip_id_suffix(&relation.foreign.from)
+            )
+            .to_camel_case()
+        } else {
+            children.to_camel_case()
+        }
+    }
+
+    /// Name of the field listing the other side of a many-to-many relationship.
+    ///
+    /// This is the pluralized name of the other table. When several junctions lead to the
+    /// same table it is suffixed with the junction (e.g. `studentsViaWishlist`), and both
+    /// directions of a self-referencing junction are told apart by their foreign key
+    /// (e.g. `user_follow` yields `usersByFollower` and `usersByFollowee`).
+    pub fn junction_field_name(&self, junction: &JunctionDef) -> String {
+        let targets = pluralizer::pluralize(&junction.table.name, 2, false);
+
+        let same_target = self
+            .junctions
+            .iter()
+            .filter(|other| other.table.name == junction.table.name)
+            .collect::<Vec<_>>();
+
+        let is_self_referencing = same_target
+            .iter()
+            .filter(|other| other.junction == junction.junction)
+            .count()
+            > 1;
+
+        let is_ambiguous = same_target.len() > 1
+            || self
+                .relations
+                .iter()
+                .any(|relation| self.relation_field_name(relation) == targets.to_camel_case());
+
+        if is_self_referencing {
+            format!("{}_by_{}", targets, strip_id_suffix(&junction.remote.from)).to_camel_case()
+        } else if is_ambiguous {
+            format!("{}_via_{}", targets, junction.junction).to_camel_case()
+        } else {
+            targets.to_camel_case()
+        }
+    }
+
+    /// The two foreign key columns of a junction table.
+    ///
+    /// A table, never a view, is treated as a junction when it has exactly two foreign keys and
+    /// every other column is part of the primary key. Tables carrying payload columns are only
+    /// linked when configured as junctions, and then only if a row can be created from the pair
+    /// of keys alone, their payload staying reachable through the junction rows themselves.
+    pub fn junction_columns(&self) -> Option<(&ColDef, &ColDef)> {
+        // views can't be written, and their columns all look nullable
+        if self.is_view {
+            return None;
+        }
+
+        let foreign_cols = self
+            .columns
+            .iter()
+            .filter(|col| col.relationship.is_some())
+            .collect::<Vec<_>>();
+
+        let [local, remote] = foreign_cols[..] else {
+            return None;
+        };
+
+        let mut payload_cols = self
+            .columns
+            .iter()
+            .filter(|col| col.relationship.is_none() && !col.is_primary);
+
+        let is_junction = if self.is_junction {
+            payload_cols.all(|col| !col.not_null || col.default_value.is_some())
+        } else {
+            payload_cols.next().is_none()
+        };
+
+        is_junction.then_some((local, remote))
+    }
+
+    /// Columns of the primary key, in key order.
+    ///
+    /// Views without a configured key are identified by all of their columns.
+    pub fn primary_key_columns(&self) -> Vec<&ColDef> {
+        let columns = self.declared_primary_key_columns();
+
+        if columns.is_empty() && self.is_view {
+            return self.columns.iter().collect();
+        }
+
+        columns
+    }
+
+    fn declared_primary_key_columns(&self) -> Vec<&ColDef> {
+        let mut columns = self
+            .columns
+            .iter()
+            .filter(|col| col.is_primary)
+            .collect::<Vec<_>>();
+
+        columns.sort_by_key(|col| col.primary_key_ordinal);
+
+        columns
+    }
+
+    /// Argument identifying a single row in view, update and delete fields.
+    ///
+    /// Single-column keys are passed directly (e.g. `post(id: 1)`), composite keys through
+    /// the `<Table>PkInput` object (e.g. `postTag(pk: {postId: 1, tagId: 2})`).
+    pub fn primary_key_argument(&self) -> Option<InputValue> {
+        match self.declared_primary_key_columns()[..] {
+            [] => None,
+            [pk_col] => Some(InputValue::new(
+                pk_col.name.clone(),
+                TypeRef::named_nn(pk_col.type_name()),
+            )),
+            _ => Some(InputValue::new(
+                "pk",
+                TypeRef::named_nn(format!("{}_pk_input", self.name).to_pascal_case()),
+            )),
+        }
+    }
+
+    /// Reverse relationships whose children can be created along with a row of this table.
+    pub fn writable_relations(&self) -> Vec<&RelationDef> {
+        self.relations
+            .iter()
+            .filter(|relation| !relation.table.is_view)
+            .collect()
+    }
+
+    /// Column sets an upsert can conflict on, paired with their item in the
+    /// `<table>_constraint` enum.
+    ///
+    /// The primary key comes first as `PRIMARY_KEY` when its values can be inserted, followed
+    /// by the UNIQUE constraints named after their columns (e.g. `SKU` or `POST_ID_TAG_ID`).
+    pub fn conflict_targets(&self) -> Vec<(String, Vec<String>)> {
+        let mut targets = vec![];
+
+        let pk_cols = self.declared_primary_key_columns();
+
+        // rowids and their aliases are assigned by SQLite, so they never conflict on insert
+        if !pk_cols.is_empty()
+            && pk_cols
+                .iter()
+                .all(|col| !col.is_rowid && !col.auto_increment)
+        {
+            targets.push((
+                "PRIMARY_KEY".to_string(),
+                pk_cols.iter().map(|col| col.name.clone()).collect(),
+            ));
+        }
+
+        for key in self.unique_keys.iter() {
+            if targets.iter().any(|(_, columns)| columns == key) {
+                continue;
+            }
+
+            let item = key
+                .iter()
+                .map(|name| name.to_snake_case().to_uppercase())
+                .collect::<Vec<_>>()
+                .join("_");
+
+            targets.push((item, key.clone()));
+        }
+
+        targets
+    }
+
+    /// Columns that support numeric aggregates (`sum`, `avg`, `min`, `max`).
+    pub fn numeric_columns(&self) -> Vec<&ColDef> {
+        self.columns
+            .iter()
+            .filter(|col| {
+                matches!(
+                    col.data_type,
+                    ColDataType::Integer | ColDataType::BigInt | ColDataType::Float
+                )
+            })
+            .filter(|col| !col.is_rowid)
+            .collect()
+    }
+
+    /// Links reverse relationships and many-to-many junctions between the given tables.
+    ///
+    /// Existing links are discarded, so this can be called again after foreign keys change.
+    pub fn link_relations(tables: &mut [TableDef]) {
+        for table in tables.iter_mut() {
+            table.relations.clear();
+            table.junctions.clear();
+        }
+
+        // attach the reverse side of every foreign key to the referenced table
+        let children = tables.to_vec();
+
+        for table in tables.iter_mut() {
+            for child in children.iter() {
+                for col in child.columns.iter() {
+                    // a parent that can't be viewed doesn't lead to its children either
+                    if let Some(foreign) = &col.relationship
+                        && foreign.readable
+                        && foreign.table == table.name
+                    {
+                        table.relations.push(RelationDef {
+                            foreign: foreign.clone(),
+                            table: child.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // link both sides of every junction table to each other
+        for junction in children.iter() {
+            let Some((first, second)) = junction.junction_columns() else {
+                continue;
+            };
+
+            let (first, second) = (
+                first.relationship.clone().expect("foreign key"),
+                second.relationship.clone().expect("foreign key"),
+            );
+
+            for (local, remote) in [(&first, &second), (&second, &first)] {
+                let Some(target) = children.iter().find(|table| table.name == remote.table) else {
+                    continue;
+                };
+
+                if let Some(table) = tables.iter_mut().find(|table| table.name == local.table) {
+                    table.junctions.push(JunctionDef {
+                        junction: junction.name.clone(),
+                        local: local.clone(),
+                        remote: remote.clone(),
+                        table: target.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Applies the logical primary key and foreign keys configured for a view.
+    ///
+    /// Views carry no constraints of their own, so without a configured key they can only be
+    /// listed, and without configured foreign keys they have no relationship fields.
+    pub fn apply_view_config(&mut self, config: &ViewConfig) -> anyhow::Result<()> {
+        let view_name = self.name.clone();
+
+        if let Some(key) = &config.key {
+            for (index, name) in key.iter().enumerate() {
+                let col = self
+                    .columns
+                    .iter_mut()
+                    .find(|col| &col.name == name)
+                    .ok_or_else(|| anyhow!("view {} has no column {}", view_name, name))?;
+
+                col.is_primary = true;
+                col.primary_key_ordinal = index + 1;
+            }
+        }
+
+        for foreign_key in config.foreign_keys.iter().flatten() {
+            let col = self
+                .columns
+                .iter_mut()
+                .find(|col| col.name == foreign_key.column)
+                .ok_or_else(|| {
+                    anyhow!("view {} has no column {}", view_name, foreign_key.column)
+                })?;
+
+            col.relationship = Some(ForeignColDef {
+                table: foreign_key.table.clone(),
+                from: foreign_key.column.clone(),
+                to: foreign_key.to.clone(),
+                main_table: view_name.clone(),
+                readable: true,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the descriptions taken from SQL comments with the configured ones.
+    pub fn apply_description_config(&mut self, config: &DescriptionConfig) -> anyhow::Result<()> {
+        if let Some(description) = &config.table {
+            self.description = Some(description.clone());
+        }
+
+        for (name, description) in config.columns.iter().flatten() {
+            let col = self
+                .columns
+                .iter_mut()
+                .find(|col| &col.name == name)
+                .ok_or_else(|| anyhow!("table {} has no column {}", self.name, name))?;
+
+            col.description = Some(description.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Whether the permissions of the schema allow an operation on this table.
+    pub fn allows(&self, operation: TableOperation) -> bool {
+        !self.denied_operations.contains(&operation)
+    }
+
+    /// Removes the columns a permission hides and marks the columns and operations it
+    /// denies.
+    ///
+    /// The primary key identifies the rows of every field, so its columns can't be hidden.
+    pub fn apply_permission_config(&mut self, config: &PermissionConfig) -> anyhow::Result<()> {
+        if let Some(read) = &config.read {
+            if let Some(col) = self
+                .primary_key_columns()
+                .into_iter()
+                .find(|col| !read.allows(&col.name))
+            {
+                return Err(anyhow!(
+                    "primary key column {} of {} can't be hidden",
+                    col.name,
+                    self.name
+                ));
+            }
+
+            self.columns.retain(|col| read.allows(&col.name));
+
+            // upserts can't target constraints on hidden columns
+            self.unique_keys
+                .retain(|key| key.iter().all(|name| read.allows(name)));
+        }
+
+        if let Some(write) = &config.write {
+            for col in self.columns.iter_mut() {
+                col.writable &= write.allows(&col.name);
+            }
+
+            // the insert input is registered even when inserts are denied, and can't be empty
+            if !self.is_view
+                && !self
+                    .columns
+                    .iter()
+                    .any(|col| col.writable && !col.is_rowid && !col.auto_increment)
+            {
+                return Err(anyhow!(
+                    "no column of {} is left writable, deny its insert and update operations instead",
+                    self.name
+                ));
+            }
+        }
+
+        if let Some(operations) = &config.operations {
+            for operation in TableOperation::ALL {
+                if !operations.allows(&operation) && self.allows(operation) {
+                    self.denied_operations.push(operation);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ForeignColDef {
+    /// Name of the field holding the referenced row, e.g. `author` for `author_id`.
+    pub fn field_name(&self) -> String {
+        strip_id_suffix(&self.from).to_camel_case()
+    }
+
+    /// Name of the input field connecting or creating the referenced row, `None` when it would
+    /// clash with the input field of the column itself (foreign keys without an `_id` suffix).
+    pub fn nested_field_name(&self) -> Option<String> {
+        let name = self.field_name();
+
+        (name != self.from.to_camel_case()).then_some(name)
+    }
+
+    /// Name of the input connecting or creating the referenced row, e.g. `UserIdParentInput`.
+    pub fn parent_input_name(&self) -> String {
+        format!("{}_{}_parent_input", self.table, self.to).to_pascal_case()
+    }
+
+    /// Name of the input identifying the referenced row by the referenced column,
+    /// e.g. `UserIdConnectInput`.
+    pub fn connect_input_name(&self) -> String {
+        format!("{}_{}_connect_input", self.table, self.to).to_pascal_case()
+    }
+}
+
+impl ColDef {
+    /// Name of the item representing this column in the `<table>_enum_fields` enum.
+    pub fn enum_item_name(&self) -> String {
+        self.name.to_snake_case().to_uppercase()
+    }
+
+    /// Name of the enum generated from the column's `CHECK (col IN (...))` constraint,
+    /// e.g. `AddressType` for `address.type`.
+    pub fn enum_type_name(&self) -> Option<String> {
+        if self.enum_values.is_empty() {
+            return None;
+        }
+
+        Some(format!("{}_{}", self.table_name, self.name).to_pascal_case())
+    }
+
+    /// Name of the GraphQL type of the column's values, its enum or its scalar.
+    pub fn type_name(&self) -> String {
+        self.enum_type_name()
+            .unwrap_or_else(|| Scalar::from(self.data_type.clone()).type_name().to_string())
+    }
+}
+
+impl TryFrom<ColDef> for async_graphql::dynamic::Enum {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ColDef) -> Result<Self, Self::Error> {
+        let name = value
+            .enum_type_name()
+            .ok_or(anyhow!("{} has no enum values", value.name))?;
+
+        // items keep the stored values so they map to the database one-to-one
+        Ok(Enum::new(name).items(value.enum_values.iter().map(EnumItem::new)))
+    }
+}
+
+pub struct SortInput(async_graphql::dynamic::InputObject);
+
+impl From<TableDef> for SortInput {
+    fn from(value: TableDef) -> Self {
+        let mut input = InputObject::new(format!("{}_sort_arg", value.name).to_pascal_case());
+
+        let enum_field = Enum::from(value.clone());
+
+        input = input.field(InputValue::new(
+            "field",
+            TypeRef::named_nn(enum_field.type_name()),
+        ));
+        input = input.field(
+            InputValue::new(
+                "order",
+                TypeRef::named_nn(SortOrder::to_graphql_enum().type_name()),
+            )
+            .default_value(async_graphql::Value::Enum(async_graphql::Name::new("ASC"))),
+        );
+        input = input.field(InputValue::new(
+            "nulls",
+            TypeRef::named(NullsOrder::to_graphql_enum().type_name()),
+        ));
+
+        Self(input)
+    }
+}
+
+pub struct PageInfo;
+
+impl PageInfo {
+    pub fn to_graphql_object() -> async_graphql::dynamic::Object {
+        Object::new("page_info".to_pascal_case())
+            .field(Field::new(
+                "hasNextPage",
+                TypeRef::named_nn(TypeRef::BOOLEAN),
+                |ctx| value_resolver("hasNextPage", ctx),
+            ))
+            .field(Field::new(
+                "hasPreviousPage",
+                TypeRef::named_nn(TypeRef::BOOLEAN),
+                |ctx| value_resolver("hasPreviousPage", ctx),
+            ))
+            .field(Field::new(
+                "startCursor",
+                TypeRef::named(TypeRef::STRING),
+                |ctx| value_resolver("startCursor", ctx),
+            ))
+            .field(Field::new(
+                "endCursor",
+                TypeRef::named(TypeRef::STRING),
+                |ctx| value_resolver("endCursor", ctx),
+            ))
+    }
+}
+
+pub struct FilterInput(pub async_graphql::dynamic::InputObject);
+
+impl From<ColDataType> for FilterInput {
+    fn from(value: ColDataType) -> Self {
+        let scalar = Scalar::from(value.clone());
+
+        let mut input = InputObject::new(format!("{}_filter", scalar.type_name()).to_pascal_case())
+            .field(InputValue::new("eq", TypeRef::named(scalar.type_name())))
+            .field(InputValue::new("ne", TypeRef::named(scalar.type_name())));
+
+        // booleans, blobs and JSON documents only support equality checks
+        if !matches!(
+            value,
+            ColDataType::Boolean | ColDataType::Bytes | ColDataType::Json
+        ) {
+            input = input
+                .field(InputValue::new("gt", TypeRef::named(scalar.type_name())))
+                .field(InputValue::new("gte", TypeRef::named(scalar.type_name())))
+                .field(InputValue::new("lt", TypeRef::named(scalar.type_name())))
+                .field(InputValue::new("lte", TypeRef::named(scalar.type_name())))
+                .field(InputValue::new(
+                    "in",
+                    TypeRef::named_nn_list(scalar.type_name()),
+                ));
+        }
+
+        input = input.field(InputValue::new("isNull", TypeRef::named(TypeRef::BOOLEAN)));
+
+        if matches!(value, ColDataType::String) {
+            input = input
+                .field(InputValue::new(
+                    "contains",
+                    TypeRef::named(scalar.type_name()),
+                ))
+                .field(InputValue::new(
+                    "startsWith",
+                    TypeRef::named(scalar.type_name()),
+                ));
+        }
+
+        Self(input)
+    }
+}
+
+impl TryFrom<ColDef> for FilterInput {
+    type Error = anyhow::Error;
+
+    /// Filter of a column restricted to the values of its enum.
+    fn try_from(value: ColDef) -> Result<Self, Self::Error> {
+        let name = value
+            .enum_type_name()
+            .ok_or(anyhow!("{} has no enum values", value.name))?;
+
+        let input = InputObject::new(format!("{}_filter", name).to_pascal_case())
+            .field(InputValue::new("eq", TypeRef::named(&name)))
+            .field(InputValue::new("ne", TypeRef::named(&name)))
+            .field(InputValue::new("in", TypeRef::named_nn_list(&name)))
+            .field(InputValue::new("isNull", TypeRef::named(TypeRef::BOOLEAN)));
+
+        Ok(Self(input))
+    }
+}
+
+pub struct WhereInput(async_graphql::dynamic::InputObject);
+
+impl From<TableDef> for WhereInput {
+    fn from(value: TableDef) -> Self {
+        let type_name = format!("{}_where", value.name).to_pascal_case();
+
+        let mut input = InputObject::new(&type_name);
+
+        for col in value.columns.iter() {
+            let FilterInput(filter) = FilterInput::try_from(col.clone())
+                .unwrap_or_else(|_| FilterInput::from(col.data_type.clone()));
+
+            input = input.field(InputValue::new(
+                col.name.to_camel_case(),
+                TypeRef::named(filter.type_name()),
+            ));
+        }
+
+        input = input
+            .field(InputValue::new("and", TypeRef::named_nn_list(&type_name)))
+            .field(InputValue::new("or", TypeRef::named_nn_list(&type_name)))
+            .field(InputValue::new("not", TypeRef::named(&type_name)));
+
+        Self(input)
+    }
+}
+
+impl ColDataType {
+    /// All supported column data types, used to register their scalars and filter inputs once.
+    pub const ALL: [ColDataType; 9] = [
+        ColDataType::String,
+        ColDataType::Integer,
+        ColDataType::BigInt,
+        ColDataType::Float,
+        ColDataType::Boolean,
+        ColDataType::Bytes,
+        ColDataType::Json,
+        ColDataType::DateTime,
+        ColDataType::Date,
+    ];
+
+    /// Maps the declared type of a column following SQLite's type affinity rules.
+    ///
+    /// A few well-known type names are recognised first (booleans, JSON, dates and 64-bit
+    /// integers), everything else gets the type of its affinity as described in
+    /// <https://www.sqlite.org/datatype3.html#determination_of_column_affinity>.
+    /// Columns without a declared type, such as computed view columns, are exposed as strings.
+    ///
+    /// # Examples
+    /// ```
+    /// # use graph_sql::parser::ColDataType;
+    /// assert_eq!(ColDataType::from_declared_type("VARCHAR(255)"), ColDataType::String);
+    /// assert_eq!(ColDataType::from_declared_type("DECIMAL(10,2)"), ColDataType::Float);
+    /// assert_eq!(ColDataType::from_declared_type("UNSIGNED BIG INT"), ColDataType::BigInt);
+    /// assert_eq!(ColDataType::from_declared_type("BLOB"), ColDataType::Bytes);
+    /// assert_eq!(ColDataType::from_declared_type("DATETIME"), ColDataType::DateTime);
+    /// ```
+    pub fn from_declared_type(declared_type: &str) -> Self {
+        let declared_type = declared_type.trim().to_uppercase();
+
+        // ignore size arguments such as VARCHAR(255) or DECIMAL(10,2)
+        let name = declared_type
+            .split('(')
+            .next()
+            .unwrap_or_default()
+            .trim_end();
+
+        match name {
+            "" | "STRING" => return Self::String,
+            "BOOLEAN" | "BOOL" => return Self::Boolean,
+            "JSON" | "JSONB" => return Self::Json,
+            "DATETIME" | "TIMESTAMP" => return Self::DateTime,
+            "DATE" => return Self::Date,
+            "BIGINT" | "INT8" | "UNSIGNED BIG INT" => return Self::BigInt,
+            _ => {}
+        }
+
+        if name.contains("INT") {
+            Self::Integer
+        } else if ["CHAR", "CLOB", "TEXT"]
+            .iter()
+            .any(|affinity| name.contains(affinity))
+        {
+            Self::String
+        } else if name.contains("BLOB") {
+            Self::Bytes
+        } else {
+            // REAL and NUMERIC affinity
+            Self::Float
+        }
+    }
+
+    /// Whether the type is exposed through a custom scalar that has to be registered.
+    pub fn is_custom_scalar(&self) -> bool {
+        !matches!(
+            self,
+            ColDataType::String | ColDataType::Integer | ColDataType::Float | ColDataType::Boolean
+        )
+    }
+
+    /// Converts a value selected with [`json_value_expr`](crate::utils::json_value_expr) to its
+    /// GraphQL representation.
+    ///
+    /// Nulls are returned as `None`, since custom scalars reject explicit null values.
+    pub fn to_graphql_value(
+        &self,
+        value: serde_json::Value,
+    ) -> async_graphql::Result<Option<async_graphql::Value>> {
+        match (self, value) {
+            (_, serde_json::Value::Null) => Ok(None),
+            (ColDataType::Bytes, serde_json::Value::String(hex)) => {
+                let bytes = decode_hex(&hex).ok_or(anyhow!("Invalid blob value"))?;
+
+                Ok(Some(async_graphql::Value::String(
+                    BASE64_STANDARD.encode(bytes),
+                )))
+            }
+            (_, value) => Ok(Some(async_graphql::Value::from_json(value)?)),
+        }
+    }
+}
+
+impl TryFrom<String> for ColDataType {
+    type Error = anyhow::Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "text" => Ok(Self::String),
+            "integer" => Ok(Self::Integer),
+            "bigint" => Ok(Self::BigInt),
+            "float" => Ok(Self::Float),
+            "boolean" => Ok(Self::Boolean),
+            "bytes" => Ok(Self::Bytes),
+            "json" => Ok(Self::Json),
+            "datetime" => Ok(Self::DateTime),
+            "date" => Ok(Self::Date),
+            _ => Err(anyhow!("unsupported data type")),
+        }
+    }
+}
+
+impl From<ColDataType> for async_graphql::dynamic::Scalar {
+    fn from(value: ColDataType) -> Self {
+        match value {
+            ColDataType::String => Scalar::new(TypeRef::STRING),
+            ColDataType::Integer => Scalar::new(TypeRef::INT),
+            ColDataType::Float => Scalar::new(TypeRef::FLOAT),
+            ColDataType::Boolean => Scalar::new(TypeRef::BOOLEAN),
+            ColDataType::BigInt => Scalar::new("BigInt")
+                .description("A 64-bit signed integer, accepted as a number or a numeric string")
+                .validator(|value| match value {
+                    async_graphql::Value::Number(number) => number.is_i64(),
+                    async_graphql::Value::String(number) => number.parse::<i64>().is_ok(),
+                    _ => false,
+                }),
+            ColDataType::Bytes => Scalar::new("Base64")
+                .description("Binary data encoded as standard base64")
+                .validator(|value| {
+                    matches!(value, async_graphql::Value::String(data) if BASE64_STANDARD.decode(data).is_ok())
+                }),
+            ColDataType::Json => Scalar::new("JSON").description("An arbitrary JSON value"),
+            ColDataType::DateTime => Scalar::new("DateTime")
+                .description("An ISO 8601 date and time, e.g. `2024-01-31 12:30:00` or `2024-01-31T12:30:00Z`")
+                .validator(|value| {
+                    matches!(value, async_graphql::Value::String(datetime) if is_valid_datetime(datetime))
+                }),
+            ColDataType::Date => Scalar::new("Date")
+                .description("An ISO 8601 calendar date, e.g. `2024-01-31`")
+                .validator(|value| {
+                    matches!(value, async_graphql::Value::String(date) if is_valid_date(date))
+                }),
+        }
+    }
+}
+
+impl From<ColDef> for async_graphql::dynamic::TypeRef {
+    fn from(value: ColDef) -> Self {
+        if value.not_null {
+            TypeRef::named_nn(value.type_name())
+        } else {
+            TypeRef::named(value.type_name())
+        }
+    }
+}
+
+impl From<ColDef> for async_graphql::dynamic::Field {
+    fn from(value: ColDef) -> Self {
+        let description = value.description.clone();
+
+        // foreign keys to rows the schema can't view are plain columns
+        if let Some(foreign_info) = value.clone().relationship
+            && foreign_info.readable
+        {
+            let stripped_name = strip_id_suffix(&foreign_info.from);
+
+            let not_null = value.not_null;
+
+            let type_ref = if not_null {
+                TypeRef::named_nn(format!("{}_node", foreign_info.table).to_camel_case())
+            } else {
+                TypeRef::named(format!("{}_node", foreign_info.table).to_camel_case())
+            };
+
+            return Field::new(stripped_name.to_camel_case(), type_ref, move |ctx| {
+                foreign_key_resolver(foreign_info.clone(), not_null, ctx)
+            })
+            .with_description(description);
+        }
+
+        Field::new(
+            value.name.clone().to_camel_case(),
+            TypeRef::from(value.clone()),
+            move |ctx| column_resolver(value.clone(), ctx),
+        )
+        .with_description(description)
+    }
+}
+
+impl From<ColDef> for NodeInputValues {
+    fn from(value: ColDef) -> Self {
+        let type_name = value.type_name();
+
+        // columns with a default can be omitted on insert, and foreign keys can be set
+        // through their relationship field instead
+        let has_relation_input = value
+            .relationship
+            .as_ref()
+            .and_then(|foreign| foreign.nested_field_name())
+            .is_some();
+
+        let type_ref = if value.not_null && value.default_value.is_none() && !has_relation_input {
+            TypeRef::named_nn(&type_name)
+        } else {
+            TypeRef::named(&type_name)
+        };
+
+        let description = value.description.clone();
+
+        let insert_description = match (&description, &value.default_value) {
+            (Some(description), Some(default_value)) => Some(format!(
+                "{}\n\nDefaults to `{}`",
+                description, default_value
+            )),
+            (None, Some(default_value)) => Some(format!("Defaults to `{}`", default_value)),
+            (description, None) => description.clone(),
+        };
+
+        NodeInputValues(
+            InputValue::new(value.name.to_string().to_camel_case(), type_ref)
+                .with_description(insert_description),
+            InputValue::new(
+                value.name.to_string().to_camel_case(),
+                TypeRef::named(&type_name),
+            )
+            .with_description(description),
+        )
+    }
+}
+
+impl From<TableDef> for async_graphql::dynamic::Object {
+    fn from(value: TableDef) -> Self {
+        let mut table_node = Object::new(format!("{}_node", value.name).to_camel_case());
+
+        for col in value.columns.iter() {
+            table_node = table_node.field(Field::from(col.clone()));
+        }
+
+        // relationships to tables the schema can't list are left out
+        for relation in value
+            .relations
+            .iter()
+            .filter(|relation| relation.table.allows(TableOperation::List))
+        {
+            let child = &relation.table;
+
+            let relation_field = relation.clone();
+
+            table_node = table_node.field(
+                Field::new(
+                    value.relation_field_name(relation),
+                    TypeRef::named_nn_list_nn(format!("{}_node", child.name).to_camel_case()),
+                    move |ctx| relation_resolver(relation_field.clone(), ctx),
+                )
+                .argument(InputValue::new("page", TypeRef::named(TypeRef::INT)))
+                .argument(InputValue::new("perPage", TypeRef::named(TypeRef::INT)))
+                .argument(InputValue::new(
+                    "where",
+                    TypeRef::named(format!("{}_where", child.name).to_pascal_case()),
+                ))
+                .argument(InputValue::new(
+                    "orderBy",
+                    TypeRef::named_nn_list(format!("{}_sort_arg", child.name).to_pascal_case()),
+                )),
+            );
+        }
+
+        for junction in value
+            .junctions
+            .iter()
+            .filter(|junction| junction.table.allows(TableOperation::List))
+        {
+            let target = &junction.table;
+
+            let junction_field = junction.clone();
+
+            table_node = table_node.field(
+                Field::new(
+                    value.junction_field_name(junction),
+                    TypeRef::named_nn_list_nn(format!("{}_node", target.name).to_camel_case()),
+                    move |ctx| junction_resolver(junction_field.clone(), ctx),
+                )
+                .argument(InputValue::new("page", TypeRef::named(TypeRef::INT)))
+                .argument(InputValue::new("perPage", TypeRef::named(TypeRef::INT)))
+                .argument(InputValue::new(
+                    "where",
+                    TypeRef::named(format!("{}_where", target.name).to_pascal_case()),
+                ))
+                .argument(InputValue::new(
+                    "orderBy",
+                    TypeRef::named_nn_list(format!("{}_sort_arg", target.name).to_pascal_case()),
+                )),
+            );
+        }
+
+        table_node.with_description(value.description)
+    }
+}
+
+impl From<TableDef> for ListQuery {
+    fn from(value: TableDef) -> Self {
+        let description = value.description.clone();
+
+        let WhereInput(where_input) = WhereInput::from(value.clone());
+        let SortInput(sort_input) = SortInput::from(value.clone());
+
+        let field = Field::new(
+            pluralizer::pluralize(&value.name.clone(), 2, false).to_camel_case(), // todo: make this plural properly
+            TypeRef::named_list(format!("{}_node", value.name).to_camel_case()),
+            move |ctx| list_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new("page", TypeRef::named_nn(TypeRef::INT)))
+        .argument(InputValue::new("perPage", TypeRef::named_nn(TypeRef::INT)))
+        .argument(InputValue::new(
+            "where",
+            TypeRef::named(where_input.type_name()),
+        ))
+        .argument(InputValue::new(
+            "orderBy",
+            TypeRef::named_nn_list(sort_input.type_name()),
+        ));
+
+        ListQuery(
+            field.with_description(description),
+            vec![where_input, sort_input],
+        )
+    }
+}
+
+impl From<TableDef> for TotalCountQuery {
+    fn from(value: TableDef) -> Self {
+        let WhereInput(where_input) = WhereInput::from(value.clone());
+
+        let field = Field::new(
+            format!(
+                "{}_total_count",
+                pluralizer::pluralize(&value.name.clone(), 2, false)
+            )
+            .to_camel_case(),
+            TypeRef::named_nn(TypeRef::INT),
+            move |ctx| total_count_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new(
+            "where",
+            TypeRef::named(where_input.type_name()),
+        ));
+
+        TotalCountQuery(
+            field.description("Number of rows matching `where`, across every page of the list"),
+        )
+    }
+}
+
+impl From<TableDef> for ConnectionQuery {
+    fn from(value: TableDef) -> Self {
+        let description = value.description.clone();
+
+        let WhereInput(where_input) = WhereInput::from(value.clone());
+        let SortInput(sort_input) = SortInput::from(value.clone());
+
+        let edge = Object::new(format!("{}_edge", value.name).to_camel_case())
+            .field(Field::new(
+                "cursor",
+                TypeRef::named_nn(TypeRef::STRING),
+                |ctx| value_resolver("cursor", ctx),
+            ))
+            .field(Field::new(
+                "node",
+                TypeRef::named_nn(format!("{}_node", value.name).to_camel_case()),
+                |ctx| value_resolver("node", ctx),
+            ));
+
+        let connection = Object::new(format!("{}_connection", value.name).to_camel_case())
+            .field(Field::new(
+                "edges",
+                TypeRef::named_nn_list_nn(edge.type_name()),
+                |ctx| value_resolver("edges", ctx),
+            ))
+            .field(Field::new(
+                "pageInfo",
+                TypeRef::named_nn(PageInfo::to_graphql_object().type_name()),
+                |ctx| value_resolver("pageInfo", ctx),
+            ))
+            .field(Field::new(
+                "totalCount",
+                TypeRef::named_nn(TypeRef::INT),
+                |ctx| value_resolver("totalCount", ctx),
+            ));
+
+        let field = Field::new(
+            format!(
+                "{}_connection",
+                pluralizer::pluralize(&value.name.clone(), 2, false)
+            )
+            .to_camel_case(),
+            TypeRef::named_nn(connection.type_name()),
+            move |ctx| connection_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new("first", TypeRef::named(TypeRef::INT)))
+        .argument(InputValue::new("after", TypeRef::named(TypeRef::STRING)))
+        .argument(InputValue::new("last", TypeRef::named(TypeRef::INT)))
+        .argument(InputValue::new("before", TypeRef::named(TypeRef::STRING)))
+        .argument(InputValue::new(
+            "where",
+            TypeRef::named(where_input.type_name()),
+        ))
+        .argument(InputValue::new(
+            "orderBy",
+            TypeRef::named_nn_list(sort_input.type_name()),
+        ));
+
+        ConnectionQuery(field.with_description(description), vec![connection, edge])
+    }
+}
+
+/// Adds the `count` and, for tables with numeric columns, the `sum`/`avg`/`min`/`max`
+/// fields shared by the aggregate and group by result types.
+fn with_aggregate_fields(mut object: Object, table: &TableDef) -> Object {
+    object = object.field(Field::new(
+        "count",
+        TypeRef::named_nn(TypeRef::INT),
+        |ctx| value_resolver("count", ctx),
+    ));
+
+    if table.numeric_columns().is_empty() {
+        return object;
+    }
+
+    for func in ["sum", "min", "max"] {
+        object = object.field(Field::new(
+            func,
+            TypeRef::named_nn(format!("{}_aggregate_fields", table.name).to_camel_case()),
+            move |ctx| value_resolver(func, ctx),
+        ));
+    }
+
+    object.field(Field::new(
+        "avg",
+        TypeRef::named_nn(format!("{}_aggregate_avg_fields", table.name).to_camel_case()),
+        |ctx| value_resolver("avg", ctx),
+    ))
+}
+
+impl From<TableDef> for AggregateQuery {
+    fn from(value: TableDef) -> Self {
+        let WhereInput(where_input) = WhereInput::from(value.clone());
+
+        let mut objects = vec![];
+
+        // sum/avg/min/max are only available when the table has numeric columns
+        if !value.numeric_columns().is_empty() {
+            let mut fields =
+                Object::new(format!("{}_aggregate_fields", value.name).to_camel_case());
+            let mut avg_fields =
+                Object::new(format!("{}_aggregate_avg_fields", value.name).to_camel_case());
+
+            for col in value.numeric_columns() {
+                let name = col.name.to_camel_case();
+
+                fields = fields.field(Field::new(
+                    name.clone(),
+                    TypeRef::named(Scalar::from(col.data_type.clone()).type_name()),
+                    {
+                        let name = name.clone();
+                        move |ctx| value_resolver(name.clone(), ctx)
+                    },
+                ));
+                avg_fields = avg_fields.field(Field::new(
+                    name.clone(),
+                    TypeRef::named(TypeRef::FLOAT),
+                    move |ctx| value_resolver(name.clone(), ctx),
+                ));
+            }
+
+            objects.push(fields);
+            objects.push(avg_fields);
+        }
+
+        let aggregate = with_aggregate_fields(
+            Object::new(format!("{}_aggregate", value.name).to_camel_case()),
+            &value,
+        );
+
+        let field = Field::new(
+            format!("{}_aggregate", value.name).to_camel_case(),
+            TypeRef::named_nn(aggregate.type_name()),
+            move |ctx| aggregate_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new(
+            "where",
+            TypeRef::named(where_input.type_name()),
+        ));
+
+        objects.push(aggregate);
+
+        AggregateQuery(field, objects)
+    }
+}
+
+impl From<TableDef> for HavingInput {
+    fn from(value: TableDef) -> Self {
+        let type_name = format!("{}_having", value.name).to_pascal_case();
+
+        let FilterInput(int_filter) = FilterInput::from(ColDataType::Integer);
+        let FilterInput(float_filter) = FilterInput::from(ColDataType::Float);
+
+        let mut inputs = vec![];
+
+        let mut input = InputObject::new(&type_name).field(InputValue::new(
+            "count",
+            TypeRef::named(int_filter.type_name()),
+        ));
+
+        if !value.numeric_columns().is_empty() {
+            let mut fields =
+                InputObject::new(format!("{}_having_fields", value.name).to_pascal_case());
+
+            for col in value.numeric_columns() {
+                fields = fields.field(InputValue::new(
+                    col.name.to_camel_case(),
+                    TypeRef::named(float_filter.type_name()),
+                ));
+            }
+
+            for func in ["sum", "avg", "min", "max"] {
+                input = input.field(InputValue::new(func, TypeRef::named(fields.type_name())));
+            }
+
+            inputs.push(fields);
+        }
+
+        input = input
+            .field(InputValue::new("and", TypeRef::named_nn_list(&type_name)))
+            .field(InputValue::new("or", TypeRef::named_nn_list(&type_name)))
+            .field(InputValue::new("not", TypeRef::named(&type_name)));
+
+        inputs.push(input);
+
+        Self(inputs)
+    }
+}
+
+impl From<TableDef> for GroupByQuery {
+    fn from(value: TableDef) -> Self {
+        let WhereInput(where_input) = WhereInput::from(value.clone());
+        let HavingInput(having_inputs) = HavingInput::from(value.clone());
+
+        let having_name = format!("{}_having", value.name).to_pascal_case();
+        let enum_name = Enum::from(value.clone()).type_name().to_string();
+
+        let mut key = Object::new(format!("{}_group_key", value.name).to_camel_case());
+
+        // only the grouped columns are populated, so every key field is nullable
+        for col in value.columns.iter() {
+            let name = col.name.to_camel_case();
+
+            let data_type = col.data_type.clone();
+
+            key = key.field(Field::new(
+                name.clone(),
+                TypeRef::named(col.type_name()),
+                move |ctx| typed_value_resolver(name.clone(), data_type.clone(), ctx),
+            ));
+        }
+
+        let group = with_aggregate_fields(
+            Object::new(format!("{}_group", value.name).to_camel_case()).field(Field::new(
+                "key",
+                TypeRef::named_nn(key.type_name()),
+                |ctx| value_resolver("key", ctx),
+            )),
+            &value,
+        );
+
+        let field = Field::new(
+            format!("{}_group_by", value.name).to_camel_case(),
+            TypeRef::named_nn_list_nn(group.type_name()),
+            move |ctx| group_by_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new("by", TypeRef::named_nn_list_nn(enum_name)))
+        .argument(InputValue::new(
+            "where",
+            TypeRef::named(where_input.type_name()),
+        ))
+        .argument(InputValue::new("having", TypeRef::named(having_name)));
+
+        GroupByQuery(field, vec![key, group], having_inputs)
+    }
+}
+
+impl TryFrom<TableDef> for ViewQuery {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        let description = value.description.clone();
+
+        let pk_arg = value
+            .primary_key_argument()
+            .ok_or(anyhow!("{} has no primary key", value.name))?;
+
+        let field = Field::new(
+            pluralizer::pluralize(&value.name.clone(), 1, false).to_camel_case(), // todo: make this plural properly
+            TypeRef::named(format!("{}_node", value.name).to_camel_case()),
+            move |ctx| view_resolver(value.clone(), ctx),
+        )
+        .argument(pk_arg);
+
+        Ok(ViewQuery(field.with_description(description)))
+    }
+}
+
+impl From<TableDef> for PkInput {
+    fn from(value: TableDef) -> Self {
+        let pk_cols = value.declared_primary_key_columns();
 
-pub struct DeleteMutation(async_graphql::dynamic::Field);
+        if pk_cols.len() < 2 {
+            return PkInput(None);
+        }
 
-pub enum SortOrder {
-    Asc,
-    Desc,
-}
+        let mut input = InputObject::new(format!("{}_pk_input", value.name).to_pascal_case());
 
-impl SortOrder {
-    fn to_graphql_enum() -> async_graphql::dynamic::Enum {
-        Enum::new("sort_order".to_pascal_case())
-            .item(EnumItem::new("ASC"))
-            .item(EnumItem::new("DESC"))
+        for col in pk_cols {
+            input = input.field(InputValue::new(
+                col.name.to_camel_case(),
+                TypeRef::named_nn(col.type_name()),
+            ));
+        }
+
+        PkInput(Some(input))
     }
 }
 
-impl From<TableDef> for async_graphql::dynamic::Enum {
+impl From<TableDef> for InsertMutation {
     fn from(value: TableDef) -> Self {
-        let mut enum_field = Enum::new(format!("{}_enum_fields", value.name).to_pascal_case());
+        let mut input = InputObject::new(format!("insert_{}_input", value.name).to_camel_case());
 
-        for col in value.columns.iter() {
-            enum_field = enum_field.item(EnumItem::new(col.name.to_snake_case().to_uppercase()))
+        // keys assigned by SQLite are left out
+        for col in value
+            .columns
+            .iter()
+            .filter(|col| !col.is_rowid && !col.auto_increment && col.writable)
+        {
+            let NodeInputValues(insert, _) = NodeInputValues::from(col.clone());
+            input = input.field(insert);
         }
 
-        enum_field
+        for relation_input in RelationInputValues::from(value.clone()).0 {
+            input = input.field(relation_input);
+        }
+
+        let field = Field::new(
+            format!("insert_{}", value.name.clone()).to_camel_case(), // todo: make this plural properly
+            TypeRef::named(format!("{}_node", value.name).to_camel_case()),
+            move |ctx| insert_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new(
+            "value",
+            TypeRef::named_nn(input.type_name()),
+        ));
+
+        InsertMutation(field, vec![input])
     }
 }
 
-pub struct SortInput(async_graphql::dynamic::InputObject);
+impl TryFrom<TableDef> for UpdateMutation {
+    type Error = anyhow::Error;
 
-impl From<TableDef> for SortInput {
-    fn from(value: TableDef) -> Self {
-        let mut input = InputObject::new("sort_arg");
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        let mut input = InputObject::new(format!("update_{}_input", value.name).to_camel_case());
 
-        let enum_field = Enum::from(value.clone());
+        let pk_arg = value
+            .primary_key_argument()
+            .ok_or(anyhow!("{} has no primary key", value.name))?;
 
-        input = input.field(InputValue::new(
-            "field",
-            TypeRef::named_nn(enum_field.type_name()),
-        ));
-        input = input.field(InputValue::new(
-            "order",
-            TypeRef::named_nn(SortOrder::to_graphql_enum().type_name()),
+        for col in value
+            .columns
+            .iter()
+            .filter(|col| !col.is_rowid && col.writable)
+        {
+            let NodeInputValues(_, update) = NodeInputValues::from(col.clone());
+            input = input.field(update);
+        }
+
+        for relation_input in RelationInputValues::from(value.clone()).0 {
+            input = input.field(relation_input);
+        }
+
+        let field = Field::new(
+            format!("update_{}", value.name.clone()).to_camel_case(), // todo: make this plural properly
+            TypeRef::named(format!("{}_node", value.name).to_camel_case()),
+            move |ctx| update_resolver(value.clone(), ctx),
+        )
+        .argument(pk_arg)
+        .argument(InputValue::new(
+            "value",
+            TypeRef::named_nn(input.type_name()),
         ));
 
-        Self(input)
+        Ok(UpdateMutation(field, vec![input]))
     }
 }
 
-impl TryFrom<String> for ColDataType {
+impl TryFrom<TableDef> for DeleteMutation {
     type Error = anyhow::Error;
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "text" => Ok(Self::String),
-            "integer" => Ok(Self::Integer),
-            "float" => Ok(Self::Float),
-            "boolean" => Ok(Self::Boolean),
-            _ => Err(anyhow!("unsupported data type")),
-        }
+
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        let pk_arg = value
+            .primary_key_argument()
+            .ok_or(anyhow!("{} has no primary key", value.name))?;
+
+        let field = Field::new(
+            format!("delete_{}", value.name.clone()).to_camel_case(), // todo: make this plural properly
+            TypeRef::named(TypeRef::INT),
+            move |ctx| delete_resolver(value.clone(), ctx),
+        )
+        .argument(pk_arg);
+
+        Ok(DeleteMutation(field))
     }
 }
 
-impl From<ColDataType> for async_graphql::dynamic::Scalar {
-    fn from(value: ColDataType) -> Self {
-        match value {
-            ColDataType::String => Scalar::new(TypeRef::STRING),
-            ColDataType::Integer => Scalar::new(TypeRef::INT),
-            ColDataType::Float => Scalar::new(TypeRef::FLOAT),
-            ColDataType::Boolean => Scalar::new(TypeRef::BOOLEAN),
-        }
+impl From<TableDef> for MutationResponse {
+    fn from(value: TableDef) -> Self {
+        let response = Object::new(format!("{}_mutation_response", value.name).to_camel_case())
+            .field(Field::new(
+                "affectedRows",
+                TypeRef::named_nn(TypeRef::INT),
+                |ctx| value_resolver("affectedRows", ctx),
+            ))
+            .field(Field::new(
+                "nodes",
+                TypeRef::named_nn_list_nn(format!("{}_node", value.name).to_camel_case()),
+                |ctx| value_resolver("nodes", ctx),
+            ));
+
+        MutationResponse(response)
     }
 }
 
-impl From<ColDef> for async_graphql::dynamic::TypeRef {
-    fn from(value: ColDef) -> Self {
-        let graphql_type = Scalar::from(value.data_type);
+impl From<TableDef> for RelationInputValues {
+    fn from(value: TableDef) -> Self {
+        let mut input_values = vec![];
 
-        if value.not_null {
-            TypeRef::named_nn(graphql_type.type_name())
-        } else {
-            TypeRef::named(graphql_type.type_name())
+        for foreign in value
+            .columns
+            .iter()
+            .filter(|col| col.writable)
+            .filter_map(|col| col.relationship.as_ref())
+            .filter(|foreign| foreign.readable)
+        {
+            let Some(field_name) = foreign.nested_field_name() else {
+                continue;
+            };
+
+            input_values.push(
+                InputValue::new(field_name, TypeRef::named(foreign.parent_input_name()))
+                    .description(format!(
+                        "Connects an existing `{}` or creates a new one, instead of setting `{}`",
+                        foreign.table,
+                        foreign.from.to_camel_case()
+                    )),
+            );
+        }
+
+        for relation in value.writable_relations() {
+            input_values.push(
+                InputValue::new(
+                    value.relation_field_name(relation),
+                    TypeRef::named(
+                        format!("{}_children_input", relation.table.name).to_pascal_case(),
+                    ),
+                )
+                .description(format!(
+                    "Creates `{}` rows referencing this row through `{}`",
+                    relation.table.name, relation.foreign.from
+                )),
+            );
         }
+
+        RelationInputValues(input_values)
     }
 }
 
-impl From<ColDef> for async_graphql::dynamic::Field {
-    fn from(value: ColDef) -> Self {
-        let description = value.description.clone().unwrap_or_default();
+impl From<TableDef> for RelationInputs {
+    fn from(value: TableDef) -> Self {
+        let mut inputs = vec![];
 
-        if let Some(foreign_info) = value.clone().relationship {
-            let stripped_name = strip_id_suffix(&foreign_info.from);
+        // the same column may be referenced by several foreign keys
+        let mut referenced = value
+            .writable_relations()
+            .into_iter()
+            .map(|relation| relation.foreign.clone())
+            .collect::<Vec<_>>();
 
-            let type_ref = if value.not_null {
-                TypeRef::named_nn(format!("{}_node", foreign_info.table).to_camel_case())
-            } else {
-                TypeRef::named(format!("{}_node", foreign_info.table).to_camel_case())
+        referenced.sort_by(|a, b| a.to.cmp(&b.to));
+        referenced.dedup_by(|a, b| a.to == b.to);
+
+        for foreign in referenced {
+            let Some(col) = value.columns.iter().find(|col| col.name == foreign.to) else {
+                continue;
             };
 
-            return Field::new(stripped_name.to_camel_case(), type_ref, move |ctx| {
-                foreign_key_resolver(foreign_info.clone(), ctx)
-            })
-            .description(description.clone());
+            let connect = InputObject::new(foreign.connect_input_name()).field(InputValue::new(
+                foreign.to.to_camel_case(),
+                TypeRef::named_nn(col.type_name()),
+            ));
+
+            let parent = InputObject::new(foreign.parent_input_name())
+                .oneof()
+                .field(
+                    InputValue::new("connect", TypeRef::named(connect.type_name())).description(
+                        format!("Connects the `{}` with this `{}`", value.name, foreign.to),
+                    ),
+                )
+                .field(
+                    InputValue::new(
+                        "create",
+                        TypeRef::named(format!("insert_{}_input", value.name).to_camel_case()),
+                    )
+                    .description(format!("Creates a new `{}`", value.name)),
+                );
+
+            inputs.push(connect);
+            inputs.push(parent);
         }
 
-        Field::new(
-            value.name.clone().to_camel_case(),
-            TypeRef::from(value.clone()),
-            move |ctx| column_resolver(value.clone(), ctx),
-        )
-        .description(description)
+        // children are created through the relationships of the tables they reference
+        if value.columns.iter().any(|col| col.relationship.is_some()) {
+            inputs.push(
+                InputObject::new(format!("{}_children_input", value.name).to_pascal_case()).field(
+                    InputValue::new(
+                        "create",
+                        TypeRef::named_nn_list_nn(
+                            format!("insert_{}_input", value.name).to_camel_case(),
+                        ),
+                    ),
+                ),
+            );
+        }
+
+        RelationInputs(inputs)
     }
 }
 
-impl From<ColDef> for NodeInputValues {
-    fn from(value: ColDef) -> Self {
-        let graphql_type = Scalar::from(value.data_type);
-
-        let type_ref = if value.not_null {
-            TypeRef::named_nn(graphql_type.type_name())
-        } else {
-            TypeRef::named(graphql_type.type_name())
-        };
+impl From<TableDef> for InsertManyMutation {
+    fn from(value: TableDef) -> Self {
+        let input_type = format!("insert_{}_input", value.name).to_camel_case();
 
-        NodeInputValues(
-            InputValue::new(value.name.to_string().to_camel_case(), type_ref),
-            InputValue::new(
-                value.name.to_string().to_camel_case(),
-                TypeRef::named(graphql_type.type_name()),
-            ),
+        let field = Field::new(
+            format!("insert_{}_many", value.name).to_camel_case(),
+            TypeRef::named_nn(format!("{}_mutation_response", value.name).to_camel_case()),
+            move |ctx| insert_many_resolver(value.clone(), ctx),
         )
+        .argument(InputValue::new(
+            "values",
+            TypeRef::named_nn_list_nn(input_type),
+        ))
+        .description("Inserts all values in a single transaction");
+
+        InsertManyMutation(field)
     }
 }
 
-impl From<TableDef> for async_graphql::dynamic::Object {
-    fn from(value: TableDef) -> Self {
-        let mut table_node = Object::new(format!("{}_node", value.name).to_camel_case());
+impl TryFrom<TableDef> for UpsertMutation {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        let targets = value.conflict_targets();
 
-        for col in value.columns {
-            table_node = table_node.field(Field::from(col));
+        if targets.is_empty() {
+            return Err(anyhow!("{} has no unique key", value.name));
         }
 
-        table_node.description(value.description.unwrap_or_default())
+        let constraint = targets.into_iter().fold(
+            Enum::new(format!("{}_constraint", value.name).to_pascal_case()),
+            |constraint, (item, columns)| {
+                constraint.item(
+                    EnumItem::new(item)
+                        .description(format!("Conflict on `{}`", columns.join("`, `"))),
+                )
+            },
+        );
+
+        let on_conflict = InputObject::new(format!("{}_on_conflict", value.name).to_pascal_case())
+            .field(InputValue::new(
+                "constraint",
+                TypeRef::named_nn(constraint.type_name()),
+            ))
+            .field(
+                InputValue::new(
+                    "update",
+                    TypeRef::named_nn_list_nn(format!("{}_enum_fields", value.name).to_pascal_case()),
+                )
+                .description("Columns updated with the new values on conflict, the existing row is kept when empty"),
+            );
+
+        let input_type = format!("insert_{}_input", value.name).to_camel_case();
+
+        let field = Field::new(
+            format!("upsert_{}", value.name).to_camel_case(),
+            TypeRef::named(format!("{}_node", value.name).to_camel_case()),
+            move |ctx| upsert_resolver(value.clone(), ctx),
+        )
+        .argument(InputValue::new("value", TypeRef::named_nn(input_type)))
+        .argument(InputValue::new(
+            "onConflict",
+            TypeRef::named_nn(on_conflict.type_name()),
+        ))
+        .description("Inserts a row, or updates the row it conflicts with. Returns null when the conflicting row is kept as is");
+
+        Ok(UpsertMutation(field, vec![on_conflict], constraint))
     }
 }
 
-impl From<TableDef> for ListQuery {
-    fn from(value: TableDef) -> Self {
-        let description = value.description.clone().unwrap_or_default();
+impl TryFrom<TableDef> for UpdateManyMutation {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        if value.primary_key_columns().is_empty() {
+            return Err(anyhow!("{} has no primary key", value.name));
+        }
+
+        let where_type = format!("{}_where", value.name).to_pascal_case();
+        let input_type = format!("update_{}_input", value.name).to_camel_case();
 
         let field = Field::new(
-            pluralizer::pluralize(&value.name.clone(), 2, false).to_camel_case(), // todo: make this plural properly
-            TypeRef::named_list(format!("{}_node", value.name).to_camel_case()),
-            move |ctx| list_resolver(value.clone(), ctx),
+            format!("update_{}_many", value.name).to_camel_case(),
+            TypeRef::named_nn(format!("{}_mutation_response", value.name).to_camel_case()),
+            move |ctx| update_many_resolver(value.clone(), ctx),
         )
-        .argument(InputValue::new("page", TypeRef::named_nn(TypeRef::INT)))
-        .argument(InputValue::new("perPage", TypeRef::named_nn(TypeRef::INT)));
+        .argument(InputValue::new("where", TypeRef::named_nn(where_type)))
+        .argument(InputValue::new("set", TypeRef::named_nn(input_type)))
+        .description("Updates every row matching `where`");
 
-        ListQuery(field.description(description))
+        Ok(UpdateManyMutation(field))
     }
 }
 
-impl From<TableDef> for ViewQuery {
-    fn from(value: TableDef) -> Self {
-        let description = value.clone().description.unwrap_or_default();
+impl TryFrom<TableDef> for DeleteManyMutation {
+    type Error = anyhow::Error;
 
-        let pk_col = value
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .expect("Primary column required")
-            .clone();
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        if value.primary_key_columns().is_empty() {
+            return Err(anyhow!("{} has no primary key", value.name));
+        }
+
+        let where_type = format!("{}_where", value.name).to_pascal_case();
 
         let field = Field::new(
-            pluralizer::pluralize(&value.name.clone(), 1, false).to_camel_case(), // todo: make this plural properly
-            TypeRef::named(format!("{}_node", value.name).to_camel_case()),
-            move |ctx| view_resolver(value.clone(), ctx),
+            format!("delete_{}_many", value.name).to_camel_case(),
+            TypeRef::named_nn(format!("{}_mutation_response", value.name).to_camel_case()),
+            move |ctx| delete_many_resolver(value.clone(), ctx),
         )
-        .argument(InputValue::new(
-            pk_col.name,
-            TypeRef::named_nn(Scalar::from(pk_col.data_type).type_name()),
-        ));
+        .argument(InputValue::new("where", TypeRef::named_nn(where_type)))
+        .description("Deletes every row matching `where`, returning the deleted rows");
 
-        ViewQuery(field.description(description))
+        Ok(DeleteManyMutation(field))
     }
 }
 
-impl From<TableDef> for InsertMutation {
-    fn from(value: TableDef) -> Self {
-        let mut input = InputObject::new(format!("insert_{}_input", value.name).to_camel_case());
+impl TryFrom<TableDef> for ChangedSubscription {
+    type Error = anyhow::Error;
 
-        for col in value.columns.iter() {
-            let NodeInputValues(insert, _) = NodeInputValues::from(col.clone());
-            input = input.field(insert);
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        if value.is_view || value.without_rowid {
+            return Err(anyhow!("SQLite does not report changes to {}", value.name));
         }
 
-        let field = Field::new(
-            format!("insert_{}", value.name.clone()).to_camel_case(), // todo: make this plural properly
-            TypeRef::named(format!("{}_node", value.name).to_camel_case()),
-            move |ctx| insert_resolver(value.clone(), ctx),
+        if value.primary_key_columns().is_empty() {
+            return Err(anyhow!("{} has no primary key", value.name));
+        }
+
+        let event_type = format!("{}_change_event", value.name).to_camel_case();
+        let where_type = format!("{}_where", value.name).to_pascal_case();
+
+        let event = Object::new(&event_type)
+            .field(Field::new(
+                "operation",
+                TypeRef::named_nn("change_operation".to_pascal_case()),
+                |ctx| value_resolver("operation", ctx),
+            ))
+            .field(
+                Field::new(
+                    "node",
+                    TypeRef::named(format!("{}_node", value.name).to_camel_case()),
+                    |ctx| value_resolver("node", ctx),
+                )
+                .description("The inserted or updated row, null for deletes"),
+            );
+
+        let field = SubscriptionField::new(
+            format!("{}_changed", value.name).to_camel_case(),
+            TypeRef::named_nn(event_type),
+            move |ctx| changed_subscription_resolver(value.clone(), ctx),
         )
-        .argument(InputValue::new(
-            "value",
-            TypeRef::named_nn(input.type_name()),
-        ));
+        .argument(InputValue::new("where", TypeRef::named(where_type)))
+        .description("Notifies committed writes to rows matching `where`");
 
-        InsertMutation(field, vec![input])
+        Ok(ChangedSubscription(field, event))
     }
 }
 
-impl From<TableDef> for UpdateMutation {
-    fn from(value: TableDef) -> Self {
-        let mut input = InputObject::new(format!("update_{}_input", value.name).to_camel_case());
+impl TryFrom<TableDef> for ConnectMutation {
+    type Error = anyhow::Error;
 
-        let pk_col = value
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .expect("Primary column required")
-            .clone();
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        let (local, remote) = value
+            .junction_columns()
+            .ok_or(anyhow!("{} is not a junction table", value.name))?;
 
-        for col in value.columns.iter() {
-            let NodeInputValues(_, update) = NodeInputValues::from(col.clone());
-            input = input.field(update);
-        }
+        let (local, remote) = (local.clone(), remote.clone());
 
         let field = Field::new(
-            format!("update_{}", value.name.clone()).to_camel_case(), // todo: make this plural properly
+            format!("connect_{}", value.name).to_camel_case(),
             TypeRef::named(format!("{}_node", value.name).to_camel_case()),
-            move |ctx| update_resolver(value.clone(), ctx),
+            move |ctx| connect_resolver(value.clone(), ctx),
         )
         .argument(InputValue::new(
-            pk_col.name,
-            TypeRef::named_nn(Scalar::from(pk_col.data_type).type_name()),
+            local.name.to_camel_case(),
+            TypeRef::named_nn(local.type_name()),
         ))
         .argument(InputValue::new(
-            "value",
-            TypeRef::named_nn(input.type_name()),
+            remote.name.to_camel_case(),
+            TypeRef::named_nn(remote.type_name()),
         ));
 
-        UpdateMutation(field, vec![input])
+        Ok(ConnectMutation(field))
     }
 }
 
-impl From<TableDef> for DeleteMutation {
-    fn from(value: TableDef) -> Self {
-        let pk_col = value
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .expect("Primary column required")
-            .clone();
+impl TryFrom<TableDef> for DisconnectMutation {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TableDef) -> Result<Self, Self::Error> {
+        let (local, remote) = value
+            .junction_columns()
+            .ok_or(anyhow!("{} is not a junction table", value.name))?;
+
+        let (local, remote) = (local.clone(), remote.clone());
 
         let field = Field::new(
-            format!("delete_{}", value.name.clone()).to_camel_case(), // todo: make this plural properly
-            TypeRef::named(TypeRef::INT),
-            move |ctx| delete_resolver(value.clone(), ctx),
+            format!("disconnect_{}", value.name).to_camel_case(),
+            TypeRef::named_nn(TypeRef::INT),
+            move |ctx| disconnect_resolver(value.clone(), ctx),
         )
         .argument(InputValue::new(
-            pk_col.name,
-            TypeRef::named_nn(Scalar::from(pk_col.data_type).type_name()),
+            local.name.to_camel_case(),
+            TypeRef::named_nn(local.type_name()),
+        ))
+        .argument(InputValue::new(
+            remote.name.to_camel_case(),
+            TypeRef::named_nn(remote.type_name()),
         ));
 
-        DeleteMutation(field)
+        Ok(DisconnectMutation(field))
     }
 }
 
@@ -347,32 +1959,133 @@ impl From<TableDef> for crate::traits::GraphQLObjectOutput {
         let mut inputs = vec![];
         let mut mutations = vec![];
         let mut queries = vec![];
+        let mut enums = vec![];
+        let mut objects = vec![];
+        let mut subscriptions = vec![];
 
         let table_obj_node = Object::from(value.clone());
 
-        let insert_mutation = InsertMutation::from(value.clone());
-        let update_mutation = UpdateMutation::from(value.clone());
-        let delete_mutation = DeleteMutation::from(value.clone());
-
         let list_query = ListQuery::from(value.clone());
-        let view_query = ViewQuery::from(value.clone());
+        let connection_query = ConnectionQuery::from(value.clone());
+        let aggregate_query = AggregateQuery::from(value.clone());
+        let group_by_query = GroupByQuery::from(value.clone());
+
+        // the types of denied operations are still registered, other tables refer to them
+        let list = value.allows(TableOperation::List);
+        let insert = value.allows(TableOperation::Insert);
+        let update = value.allows(TableOperation::Update);
+        let delete = value.allows(TableOperation::Delete);
+
+        if list {
+            queries.push(list_query.0);
+            queries.push(TotalCountQuery::from(value.clone()).0);
+        }
+
+        // looking up, updating or deleting a single row requires a primary key
+        if let Ok(view_query) = ViewQuery::try_from(value.clone())
+            && value.allows(TableOperation::View)
+        {
+            queries.push(view_query.0);
+        }
+
+        if list {
+            queries.push(connection_query.0);
+            queries.push(aggregate_query.0);
+            queries.push(group_by_query.0);
+        }
+
+        // views are read-only
+        if !value.is_view {
+            let insert_mutation = InsertMutation::from(value.clone());
+
+            if insert {
+                mutations.push(insert_mutation.0);
+                mutations.push(InsertManyMutation::from(value.clone()).0);
+            }
+
+            inputs.push(insert_mutation.1);
+            objects.push(MutationResponse::from(value.clone()).0);
+            inputs.push(RelationInputs::from(value.clone()).0);
+
+            if let Ok(upsert_mutation) = UpsertMutation::try_from(value.clone())
+                && insert
+                && update
+            {
+                mutations.push(upsert_mutation.0);
+                inputs.push(upsert_mutation.1);
+                enums.push(upsert_mutation.2);
+            }
+
+            if let (Ok(update_mutation), Ok(update_many_mutation)) = (
+                UpdateMutation::try_from(value.clone()),
+                UpdateManyMutation::try_from(value.clone()),
+            ) && update
+            {
+                mutations.push(update_mutation.0);
+                mutations.push(update_many_mutation.0);
+                inputs.push(update_mutation.1);
+            }
+
+            if let (Ok(delete_mutation), Ok(delete_many_mutation)) = (
+                DeleteMutation::try_from(value.clone()),
+                DeleteManyMutation::try_from(value.clone()),
+            ) && delete
+            {
+                mutations.push(delete_mutation.0);
+                mutations.push(delete_many_mutation.0);
+            }
+
+            if let Ok(changed_subscription) = ChangedSubscription::try_from(value.clone())
+                && list
+            {
+                subscriptions.push(changed_subscription.0);
+                objects.push(changed_subscription.1);
+            }
 
-        queries.push(list_query.0);
-        queries.push(view_query.0);
+            if let (Ok(connect_mutation), Ok(disconnect_mutation)) = (
+                ConnectMutation::try_from(value.clone()),
+                DisconnectMutation::try_from(value.clone()),
+            ) {
+                if insert {
+                    mutations.push(connect_mutation.0);
+                }
+
+                if delete {
+                    mutations.push(disconnect_mutation.0);
+                }
+            }
+        }
 
-        mutations.push(insert_mutation.0);
-        mutations.push(update_mutation.0);
-        mutations.push(delete_mutation.0);
+        inputs.push(list_query.1);
+        inputs.push(group_by_query.2);
+        enums.push(Enum::from(value.clone()));
+        inputs.extend(PkInput::from(value.clone()).0.map(|input| vec![input]));
 
-        inputs.push(insert_mutation.1);
-        inputs.push(update_mutation.1);
+        // enums generated from CHECK constraints and their filters
+        for col in value.columns.iter() {
+            if let (Ok(col_enum), Ok(FilterInput(filter))) = (
+                Enum::try_from(col.clone()),
+                FilterInput::try_from(col.clone()),
+            ) {
+                enums.push(col_enum);
+                inputs.push(vec![filter]);
+            }
+        }
 
         GraphQLObjectOutput {
             table: table_obj_node,
+            objects: connection_query
+                .1
+                .into_iter()
+                .chain(aggregate_query.1)
+                .chain(group_by_query.1)
+                .chain(objects)
+                .collect::<Vec<_>>(),
             queries,
             mutations,
+            subscriptions,
             inputs: inputs.into_iter().flatten().collect::<Vec<_>>(),
-            enums: vec![],
+            enums,
         }
     }
 }
@@ -380,19 +2093,33 @@ impl From<TableDef> for crate::traits::GraphQLObjectOutput {
 impl Introspector for TableDef {
     async fn introspect(pool: &SqlitePool) -> async_graphql::Result<Vec<Self>> {
         // get the table info and its column
-        let table_query =
-            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'";
+        let table_query = "SELECT m.name, m.type = 'view', t.wr, m.sql FROM sqlite_master m JOIN pragma_table_list t ON t.schema = 'main' AND t.name = m.name WHERE m.type IN ('table', 'view') AND m.name NOT LIKE 'sqlite_%'";
 
-        let tables = sqlx::query_as::<_, (String,)>(table_query)
+        let tables = sqlx::query_as::<_, (String, bool, bool, Option<String>)>(table_query)
             .fetch_all(pool)
-            .await?
-            .into_iter()
-            .map(|(table,)| table)
-            .collect::<Vec<_>>();
+            .await?;
 
         let mut result = Vec::new();
 
-        for table_name in tables {
+        for (table_name, is_view, without_rowid, sql) in tables {
+            // enums come from CHECK constraints, which only exist in the table's SQL
+            let mut enums = match sql.as_deref().map(check_constraint_enums).transpose() {
+                Ok(enums) => enums.unwrap_or_default(),
+                Err(e) => {
+                    warn!("Unable to parse the SQL of '{}': {}", table_name, e);
+                    HashMap::new()
+                }
+            };
+
+            // descriptions come from the comments kept in the table's SQL
+            let mut comments = match sql.as_deref().map(sql_comments).transpose() {
+                Ok(comments) => comments.unwrap_or_default(),
+                Err(e) => {
+                    warn!("Unable to read the comments of '{}': {}", table_name, e);
+                    SqlComments::default()
+                }
+            };
+
             // Get column information using pragma_table_info
             let column_query =
                 "SELECT cid, name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?)";
@@ -403,24 +2130,17 @@ impl Introspector for TableDef {
                     .fetch_all(pool)
                     .await?;
 
+            let primary_key_count = column_rows.iter().filter(|row| row.5 > 0).count();
+
             let mut columns = Vec::new();
 
-            for (_, col_name, col_type, not_null, _default_value, is_primary) in column_rows {
-                // Convert SQLite type to our ColDataType
-                let data_type = match col_type.to_lowercase().as_str() {
-                    "text" | "varchar" | "char" | "string" => ColDataType::String,
-                    "integer" | "int" | "bigint" | "smallint" => ColDataType::Integer,
-                    "real" | "float" | "double" | "numeric" => ColDataType::Float,
-                    "boolean" | "bool" => ColDataType::Boolean,
-                    _ => {
-                        // Default to string for unknown types
-                        debug!(
-                            "Unknown column type '{}' for column '{}', defaulting to String",
-                            col_type, col_name
-                        );
-                        ColDataType::String
-                    }
-                };
+            for (_, col_name, col_type, not_null, default_value, pk_ordinal) in column_rows {
+                let data_type = ColDataType::from_declared_type(&col_type);
+
+                debug!(
+                    "Mapped column '{}' of type '{}' to {:?}",
+                    col_name, col_type, data_type
+                );
 
                 // Get foreign key information for this column
                 let fk_query = "SELECT \"table\", \"from\", \"to\" FROM pragma_foreign_key_list(?) WHERE \"from\" = ?";
@@ -436,31 +2156,133 @@ impl Introspector for TableDef {
                     from: from.clone(),
                     to: to.clone(),
                     main_table: table_name.clone(),
+                    readable: true,
                 });
 
+                let enum_values = enums.remove(&col_name).unwrap_or_default();
+                let description = comments.columns.remove(&col_name);
+
+                // a single INTEGER PRIMARY KEY is an alias of the rowid
+                let auto_increment = !is_view
+                    && !without_rowid
+                    && pk_ordinal > 0
+                    && primary_key_count == 1
+                    && col_type.eq_ignore_ascii_case("integer");
+
                 let col_def = ColDef {
                     table_name: table_name.clone(),
                     name: col_name,
                     data_type,
                     not_null: not_null == 1,
-                    is_primary: is_primary == 1,
-                    description: None, // Skip description for now
+                    is_primary: pk_ordinal > 0,
+                    primary_key_ordinal: pk_ordinal as usize,
+                    description,
+                    default_value,
+                    enum_values,
                     relationship,
+                    is_rowid: false,
+                    auto_increment,
+                    writable: true,
                 };
 
                 columns.push(col_def);
             }
 
+            // tables without a declared primary key are addressed by their implicit rowid
+            if !is_view && !columns.iter().any(|col| col.is_primary) {
+                if without_rowid {
+                    warn!(
+                        "Skipping WITHOUT ROWID table '{}' without a usable primary key",
+                        table_name
+                    );
+                    continue;
+                }
+
+                // the rowid is shadowed by real columns using one of its aliases
+                let Some(rowid) = ["rowid", "oid"].into_iter().find(|alias| {
+                    !columns
+                        .iter()
+                        .any(|col| col.name.eq_ignore_ascii_case(alias))
+                }) else {
+                    warn!(
+                        "Skipping table '{}' whose rowid is shadowed by its columns",
+                        table_name
+                    );
+                    continue;
+                };
+
+                debug!("Using implicit {} as key of table '{}'", rowid, table_name);
+
+                columns.insert(
+                    0,
+                    ColDef {
+                        table_name: table_name.clone(),
+                        name: rowid.to_string(),
+                        data_type: ColDataType::Integer,
+                        not_null: true,
+                        is_primary: true,
+                        primary_key_ordinal: 1,
+                        description: Some("Implicit SQLite rowid".to_string()),
+                        default_value: None,
+                        enum_values: vec![],
+                        auto_increment: false,
+                        relationship: None,
+                        is_rowid: true,
+                        writable: false,
+                    },
+                );
+            }
+
+            // UNIQUE constraints and indexes, which upserts can target; partial and
+            // expression indexes can't be used as a conflict target
+            let index_query = "SELECT name FROM pragma_index_list(?) WHERE \"unique\" AND NOT partial AND origin <> 'pk'";
+
+            let index_rows = sqlx::query_as::<_, (String,)>(index_query)
+                .bind(&table_name)
+                .fetch_all(pool)
+                .await?;
+
+            let mut unique_keys = Vec::new();
+
+            for (index_name,) in index_rows {
+                let index_columns = sqlx::query_as::<_, (Option<String>,)>(
+                    "SELECT name FROM pragma_index_info(?) ORDER BY seqno",
+                )
+                .bind(&index_name)
+                .fetch_all(pool)
+                .await?
+                .into_iter()
+                .map(|(name,)| name)
+                .collect::<Option<Vec<_>>>();
+
+                match index_columns {
+                    Some(index_columns) if !unique_keys.contains(&index_columns) => {
+                        unique_keys.push(index_columns)
+                    }
+                    Some(_) => {}
+                    None => debug!("Skipping expression index '{}'", index_name),
+                }
+            }
+
             let table_def = TableDef {
                 name: table_name,
                 columns,
-                description: None, // Skip description for now
+                description: comments.table,
+                relations: vec![],
+                junctions: vec![],
+                is_view,
+                without_rowid,
+                unique_keys,
+                is_junction: false,
+                denied_operations: vec![],
             };
 
             result.push(table_def);
         }
 
-        println!("{:#?}", result);
+        Self::link_relations(&mut result);
+
+        debug!("Introspected {} tables", result.len());
 
         Ok(result)
     }
@@ -0,0 +1,151 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::anyhow;
+use async_graphql::{Data, Executor, Request, Response, futures_util::stream::BoxStream};
+
+use crate::{
+    auth::Claims,
+    config::{PermissionConfig, TableOperation},
+    live::LiveSchema,
+    parser::TableDef,
+};
+
+/// Permissions of the tables, deciding the schema of every role.
+#[derive(Clone, Default)]
+pub struct Permissions(Vec<PermissionConfig>);
+
+impl From<&[PermissionConfig]> for Permissions {
+    fn from(value: &[PermissionConfig]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl Permissions {
+    /// Names of the tables with permissions.
+    pub fn tables(&self) -> HashSet<&String> {
+        self.0.iter().map(|permission| &permission.table).collect()
+    }
+
+    /// Roles with permissions of their own, each getting its own schema.
+    pub fn roles(&self) -> Vec<String> {
+        let mut roles = vec![];
+
+        for role in self
+            .0
+            .iter()
+            .filter_map(|permission| permission.role.as_ref())
+        {
+            if !roles.contains(role) {
+                roles.push(role.clone());
+            }
+        }
+
+        roles
+    }
+
+    /// Tables as seen by a role, or by roles without permissions of their own.
+    pub fn tables_for(
+        &self,
+        role: Option<&str>,
+        tables: &[TableDef],
+    ) -> anyhow::Result<Vec<TableDef>> {
+        let mut tables = tables.to_vec();
+
+        for permission in self
+            .0
+            .iter()
+            .filter(|permission| permission.role.is_none() || permission.role.as_deref() == role)
+        {
+            let Some(table) = tables
+                .iter_mut()
+                .find(|table| table.name == permission.table)
+            else {
+                continue;
+            };
+
+            table.apply_permission_config(permission)?;
+        }
+
+        // foreign key fields only lead to parents the role can view, otherwise the column is
+        // exposed as it is
+        let viewable = tables
+            .iter()
+            .map(|table| (table.name.clone(), table.allows(TableOperation::View)))
+            .collect::<HashMap<_, _>>();
+
+        for foreign in tables
+            .iter_mut()
+            .flat_map(|table| table.columns.iter_mut())
+            .filter_map(|col| col.relationship.as_mut())
+        {
+            foreign.readable = viewable.get(&foreign.table).copied().unwrap_or(true);
+        }
+
+        // the parent of a foreign key is looked up by its referenced columns
+        for table in tables.iter() {
+            for foreign in table
+                .columns
+                .iter()
+                .filter_map(|col| col.relationship.as_ref())
+            {
+                let parent = tables.iter().find(|parent| parent.name == foreign.table);
+
+                if parent
+                    .is_some_and(|parent| !parent.columns.iter().any(|col| col.name == foreign.to))
+                {
+                    return Err(anyhow!(
+                        "column {} of {} is referenced by {} and can't be hidden",
+                        foreign.to,
+                        foreign.table,
+                        table.name
+                    ));
+                }
+            }
+        }
+
+        // relations are linked again without the hidden foreign keys
+        TableDef::link_relations(&mut tables);
+
+        Ok(tables)
+    }
+}
+
+/// Executor picking the schema of the role of each request.
+///
+/// Requests of roles without permissions of their own are executed by the default
+/// schema.
+#[derive(Clone)]
+pub struct RoleSchemas {
+    default: LiveSchema,
+    roles: HashMap<String, LiveSchema>,
+}
+
+impl RoleSchemas {
+    pub fn new(default: LiveSchema, roles: HashMap<String, LiveSchema>) -> Self {
+        Self { default, roles }
+    }
+
+    /// Schema of the role of a request.
+    pub fn schema(&self, claims: Option<&Claims>) -> &LiveSchema {
+        self.roles
+            .get(Claims::role_of(claims))
+            .unwrap_or(&self.default)
+    }
+}
+
+impl Executor for RoleSchemas {
+    async fn execute(&self, request: Request) -> Response {
+        self.schema(Claims::from_data(&request.data))
+            .execute(request)
+            .await
+    }
+
+    fn execute_stream(
+        &self,
+        request: Request,
+        session_data: Option<std::sync::Arc<Data>>,
+    ) -> BoxStream<'static, Response> {
+        self.schema(session_data.as_deref().and_then(Claims::from_data))
+            .execute_stream(request, session_data)
+    }
+}
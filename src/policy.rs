@@ -0,0 +1,169 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::anyhow;
+use async_graphql::dynamic::ResolverContext;
+use sea_query::{Condition, Expr, SimpleExpr};
+
+use crate::{
+    auth::Claims,
+    config::PolicyConfig,
+    utils::{claim_placeholders, json_to_sql_value},
+};
+
+/// Statement a policy expression restricts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyCommand {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A policy expression whose `$claims.<name>` references are bound when it is applied.
+#[derive(Debug, Clone)]
+pub struct PolicyExpr {
+    sql: String,         // expression with a `?` placeholder per claim reference
+    claims: Vec<String>, // claims bound to the placeholders, in order
+}
+
+impl TryFrom<&str> for PolicyExpr {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (sql, claims) = claim_placeholders(value)?;
+
+        Ok(Self { sql, claims })
+    }
+}
+
+impl PolicyExpr {
+    /// Binds the claims of a request, missing claims and anonymous requests bind `NULL`.
+    pub fn to_simple_expr(&self, claims: Option<&Claims>) -> SimpleExpr {
+        let values = self.claims.iter().map(|name| {
+            json_to_sql_value(
+                claims
+                    .and_then(|claims| claims.get(name))
+                    .unwrap_or(&serde_json::Value::Null),
+            )
+        });
+
+        Expr::cust_with_values(&self.sql, values)
+    }
+}
+
+/// The policy expressions of a table for one role, or every request.
+#[derive(Debug, Clone)]
+struct TablePolicy {
+    role: Option<String>,
+    select: Option<PolicyExpr>,
+    insert: Option<PolicyExpr>,
+    update: Option<PolicyExpr>,
+    delete: Option<PolicyExpr>,
+}
+
+impl TryFrom<&PolicyConfig> for TablePolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &PolicyConfig) -> Result<Self, Self::Error> {
+        let parse = |sql: &Option<String>| {
+            sql.as_deref()
+                .map(PolicyExpr::try_from)
+                .transpose()
+                .map_err(|e| anyhow!("Invalid policy of table '{}': {}", config.table, e))
+        };
+
+        Ok(Self {
+            role: config.role.clone(),
+            select: parse(&config.select)?,
+            insert: parse(&config.insert)?,
+            update: parse(&config.update)?,
+            delete: parse(&config.delete)?,
+        })
+    }
+}
+
+impl TablePolicy {
+    fn expr(&self, command: PolicyCommand) -> Option<&PolicyExpr> {
+        match command {
+            PolicyCommand::Select => self.select.as_ref(),
+            PolicyCommand::Insert => self.insert.as_ref(),
+            PolicyCommand::Update => self.update.as_ref(),
+            PolicyCommand::Delete => self.delete.as_ref(),
+        }
+    }
+}
+
+/// Row-level security policies of the tables, by table name.
+#[derive(Debug, Clone, Default)]
+pub struct Policies(Arc<HashMap<String, Vec<TablePolicy>>>);
+
+impl TryFrom<&[PolicyConfig]> for Policies {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &[PolicyConfig]) -> Result<Self, Self::Error> {
+        let mut policies: HashMap<String, Vec<TablePolicy>> = HashMap::new();
+
+        for config in value {
+            policies
+                .entry(config.table.clone())
+                .or_default()
+                .push(TablePolicy::try_from(config)?);
+        }
+
+        Ok(Self(Arc::new(policies)))
+    }
+}
+
+impl Policies {
+    /// Names of the tables with policies.
+    pub fn tables(&self) -> impl Iterator<Item = &String> {
+        self.0.keys()
+    }
+
+    /// Condition matching the rows of `table` a command may access, `None` when the table
+    /// has no policies.
+    ///
+    /// The expressions of the policies applying to the role of the request are combined
+    /// with `OR`, so a command none of them defines matches no rows.
+    pub fn condition(
+        &self,
+        table: &str,
+        command: PolicyCommand,
+        claims: Option<&Claims>,
+    ) -> Option<Condition> {
+        let policies = self.0.get(table)?;
+
+        let role = Claims::role_of(claims);
+
+        Some(
+            policies
+                .iter()
+                .filter(|policy| policy.role.as_deref().is_none_or(|name| name == role))
+                .filter_map(|policy| policy.expr(command))
+                .fold(Condition::any(), |condition, expr| {
+                    condition.add(expr.to_simple_expr(claims))
+                }),
+        )
+    }
+}
+
+/// Policies of the schema together with the claims of the request they are applied to.
+#[derive(Clone, Copy, Default)]
+pub struct PolicyScope<'a> {
+    policies: Option<&'a Policies>,
+    claims: Option<&'a Claims>,
+}
+
+impl<'a> PolicyScope<'a> {
+    pub fn from_context(ctx: &ResolverContext<'a>) -> Self {
+        Self {
+            policies: ctx.data_opt::<Policies>(),
+            claims: ctx.data_opt::<Claims>(),
+        }
+    }
+
+    /// See [`Policies::condition`].
+    pub fn condition(&self, table: &str, command: PolicyCommand) -> Option<Condition> {
+        self.policies?.condition(table, command, self.claims)
+    }
+}
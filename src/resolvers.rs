@@ -1,412 +1,2442 @@
+use std::{collections::HashMap, pin::Pin};
+
 use anyhow::anyhow;
 use async_graphql::{
     Value,
     dataloader::DataLoader,
-    dynamic::{FieldFuture, ResolverContext},
+    dynamic::{
+        FieldFuture, ObjectAccessor, ResolverContext, SubscriptionFieldFuture, ValueAccessor,
+    },
+    futures_util::stream,
+};
+use sea_query::{
+    Alias, BinOper, CommonTableExpression, Condition, Expr, Iden, InsertStatement, Keyword,
+    LikeExpr, NullOrdering, OnConflict, Order, OrderedStatement, Query, QueryBuilder, SimpleExpr,
+    SqliteQueryBuilder, WithClause,
 };
-use sea_query::{Alias, Expr, Query, SqliteQueryBuilder};
-use sqlx::{SqlitePool, prelude::FromRow};
-use tracing::debug;
+use sqlx::{Column, Connection, Row, SqliteConnection, sqlite::SqliteRow};
+use stringcase::Caser;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
 
 use crate::{
-    loader::{ColumnRowDef, ColumnRowLoader},
-    parser::{ColDef, ForeignColDef, TableDef},
-    traits::ToSimpleExpr,
+    changes::{ChangeBus, RowValues, TableChange},
+    config::TableOperation,
+    functions::RequestInfo,
+    live::LiveTables,
+    loader::{ColumnRowDef, ColumnRowLoader, JunctionRowDef, RelationLoader, RelationRowDef},
+    parser::{
+        ChangeOperation, ColDataType, ColDef, ForeignColDef, JunctionDef, NullsOrder, RelationDef,
+        SortOrder, TableDef, TableRegistry,
+    },
+    policy::{PolicyCommand, PolicyScope},
+    traits::{ToCondition, ToSimpleExpr},
+    transaction::{Database, DbConnection},
+    utils::{
+        decode_cursor, encode_cursor, escape_like, json_array_expr, json_row_expr,
+        json_to_sql_value, json_value_expr, stored_value, stored_value_expr,
+    },
 };
 
-#[derive(Clone, FromRow)]
+/// Key of a single row, passed down as the parent value to the fields of a node.
+#[derive(Clone, Debug)]
 pub struct ColumnResolverArgs {
-    name: String,
-    id: serde_json::Value,
+    key: Vec<(String, serde_json::Value)>, // primary key columns and their values
+    row: Option<serde_json::Map<String, serde_json::Value>>, // column values of a deleted row
+}
+
+impl ColumnResolverArgs {
+    /// Pairs the key columns with the values selected by [`json_array_expr`].
+    pub fn new(columns: &[&ColDef], values: &serde_json::Value) -> anyhow::Result<Self> {
+        let values = values
+            .as_array()
+            .ok_or(anyhow!("Unable to get primary key values"))?;
+
+        Ok(Self {
+            key: columns
+                .iter()
+                .map(|col| col.name.clone())
+                .zip(values.iter().cloned())
+                .collect(),
+            row: None,
+        })
+    }
+
+    /// Attaches the column values of a row that can no longer be loaded by its key, such as
+    /// a deleted one. The values are selected by [`json_row_expr`].
+    pub fn with_row(mut self, row: serde_json::Value) -> anyhow::Result<Self> {
+        match row {
+            serde_json::Value::Object(row) => {
+                self.row = Some(row);
+                Ok(self)
+            }
+            _ => Err(anyhow!("Unable to get row values")),
+        }
+    }
+
+    /// Reads the key of the row being resolved from the parent value.
+    pub fn from_parent(ctx: &ResolverContext<'_>) -> async_graphql::Result<Self> {
+        let parent_value = ctx.parent_value.try_to_value()?.clone().into_json()?;
+
+        let key = parent_value
+            .get("key")
+            .and_then(|key| key.as_object())
+            .ok_or(anyhow!("Unable to get parent key"))?;
+
+        Ok(Self {
+            key: key
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            row: parent_value
+                .get("row")
+                .and_then(|row| row.as_object())
+                .cloned(),
+        })
+    }
+
+    /// Value of a column of the attached row, see [`ColumnResolverArgs::with_row`].
+    pub fn row_value(&self, column: &str) -> Option<&serde_json::Value> {
+        self.row.as_ref().and_then(|row| row.get(column))
+    }
+
+    pub fn columns(&self) -> Vec<Alias> {
+        self.key.iter().map(|(name, _)| Alias::new(name)).collect()
+    }
+
+    pub fn values(&self) -> Vec<serde_json::Value> {
+        self.key.iter().map(|(_, value)| value.clone()).collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "key": self.key.iter().cloned().collect::<serde_json::Map<_, _>>(),
+        });
+
+        if let Some(row) = &self.row {
+            value["row"] = serde_json::Value::Object(row.clone());
+        }
+
+        value
+    }
 }
 
 impl From<ColumnResolverArgs> for async_graphql::Value {
     fn from(value: ColumnResolverArgs) -> Self {
-        let mut map = async_graphql::indexmap::IndexMap::new();
-        map.insert(async_graphql::Name::new("name"), Value::String(value.name));
-        map.insert(
-            async_graphql::Name::new("id"),
-            Value::from_json(value.id).unwrap(),
-        );
-        Value::Object(map)
+        Value::from_json(value.to_json()).unwrap()
+    }
+}
+
+/// Selects the primary key of a table as a JSON array, see [`ColumnResolverArgs::new`].
+fn primary_key_expr(table: &TableDef) -> async_graphql::Result<String> {
+    let pk_cols = table.primary_key_columns();
+
+    if pk_cols.is_empty() {
+        return Err(anyhow!("Unable to find primary key").into());
+    }
+
+    Ok(json_array_expr(
+        &pk_cols.iter().map(|col| &col.name).collect::<Vec<_>>(),
+    ))
+}
+
+/// Condition matching the row identified by the primary key argument of a view, update or
+/// delete field, see [`TableDef::primary_key_argument`].
+fn primary_key_condition(
+    table: &TableDef,
+    ctx: &ResolverContext<'_>,
+) -> async_graphql::Result<Condition> {
+    let mut condition = Condition::all();
+
+    match table.primary_key_columns()[..] {
+        [] => return Err(anyhow!("Unable to find primary key").into()),
+        [pk_col] => {
+            let id = ctx.args.try_get(&pk_col.name)?;
+
+            condition = condition.add(
+                Expr::col(Alias::new(pk_col.name.clone()))
+                    .eq(id.to_simple_expr(&pk_col.data_type)?),
+            );
+        }
+        ref pk_cols => {
+            let pk = ctx.args.try_get("pk")?.object()?;
+
+            for pk_col in pk_cols {
+                let id = pk.try_get(&pk_col.name.to_camel_case())?;
+
+                condition = condition.add(
+                    Expr::col(Alias::new(pk_col.name.clone()))
+                        .eq(id.to_simple_expr(&pk_col.data_type)?),
+                );
+            }
+        }
     }
+
+    Ok(condition)
 }
 
 pub enum FilterOperator {
-    Eq,
-    Gte,
-    Gt,
-    Lte,
-    Lt,
-    Ne,
+    Eq(SimpleExpr),
+    Gte(SimpleExpr),
+    Gt(SimpleExpr),
+    Lte(SimpleExpr),
+    Lt(SimpleExpr),
+    Ne(SimpleExpr),
+    In(Vec<SimpleExpr>),
+    IsNull(bool),
+    Contains(String),
+    StartsWith(String),
+}
+
+impl FilterOperator {
+    pub fn parse(
+        name: &str,
+        value: ValueAccessor<'_>,
+        data_type: &ColDataType,
+    ) -> async_graphql::Result<Self> {
+        Ok(match name {
+            "eq" => Self::Eq(value.to_simple_expr(data_type)?),
+            "ne" => Self::Ne(value.to_simple_expr(data_type)?),
+            "gt" => Self::Gt(value.to_simple_expr(data_type)?),
+            "gte" => Self::Gte(value.to_simple_expr(data_type)?),
+            "lt" => Self::Lt(value.to_simple_expr(data_type)?),
+            "lte" => Self::Lte(value.to_simple_expr(data_type)?),
+            "in" => Self::In(
+                value
+                    .list()?
+                    .iter()
+                    .map(|item| item.to_simple_expr(data_type))
+                    .collect::<async_graphql::Result<Vec<_>>>()?,
+            ),
+            "isNull" => Self::IsNull(value.boolean()?),
+            "contains" => Self::Contains(value.string()?.to_string()),
+            "startsWith" => Self::StartsWith(value.string()?.to_string()),
+            _ => return Err(anyhow!("Unsupported filter operator '{}'", name).into()),
+        })
+    }
 }
 
 pub struct DynamicFilterCondition {
-    field: String,
+    field: SimpleExpr,
     op: FilterOperator,
 }
 
+impl DynamicFilterCondition {
+    pub fn new(field: String, op: FilterOperator) -> Self {
+        Self::with_expr(Expr::col(Alias::new(field)).into(), op)
+    }
+
+    /// Creates a condition on an arbitrary expression, e.g. an aggregate in a `HAVING` clause.
+    pub fn with_expr(field: SimpleExpr, op: FilterOperator) -> Self {
+        Self { field, op }
+    }
+}
+
+impl From<DynamicFilterCondition> for SimpleExpr {
+    fn from(value: DynamicFilterCondition) -> Self {
+        let col = Expr::expr(value.field);
+
+        match value.op {
+            FilterOperator::Eq(val) => col.eq(val),
+            FilterOperator::Ne(val) => col.ne(val),
+            FilterOperator::Gt(val) => col.gt(val),
+            FilterOperator::Gte(val) => col.gte(val),
+            FilterOperator::Lt(val) => col.lt(val),
+            FilterOperator::Lte(val) => col.lte(val),
+            FilterOperator::In(values) => col.is_in(values),
+            FilterOperator::IsNull(true) => col.is_null(),
+            FilterOperator::IsNull(false) => col.is_not_null(),
+            FilterOperator::Contains(val) => {
+                col.like(LikeExpr::new(format!("%{}%", escape_like(&val))).escape('\\'))
+            }
+            FilterOperator::StartsWith(val) => {
+                col.like(LikeExpr::new(format!("{}%", escape_like(&val))).escape('\\'))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DynamicSortCondition {
+    field: String,
+    order: SortOrder,
+    nulls: Option<NullsOrder>,
+}
+
+impl DynamicSortCondition {
+    /// Parses the `orderBy` argument of a list query.
+    ///
+    /// The primary key is always appended as the last sort key (unless it is already
+    /// sorted on) so that rows are returned in a stable order across pages.
+    pub fn parse_list(
+        table: &TableDef,
+        order_by: Option<ValueAccessor<'_>>,
+    ) -> async_graphql::Result<Vec<Self>> {
+        let mut conditions = vec![];
+
+        if let Some(order_by) = order_by {
+            for item in order_by.list()?.iter() {
+                let item = item.object()?;
+
+                let field = item.try_get("field")?;
+                let field = field.enum_name()?;
+
+                let col = table
+                    .column_by_enum_item(field)
+                    .ok_or(anyhow!("Unknown sort field '{}'", field))?;
+
+                let order = match item.get("order") {
+                    Some(order) if !order.is_null() => SortOrder::try_from(order.enum_name()?)?,
+                    _ => SortOrder::Asc,
+                };
+
+                let nulls = match item.get("nulls") {
+                    Some(nulls) if !nulls.is_null() => {
+                        Some(NullsOrder::try_from(nulls.enum_name()?)?)
+                    }
+                    _ => None,
+                };
+
+                conditions.push(Self {
+                    field: col.name.clone(),
+                    order,
+                    nulls,
+                });
+            }
+        }
+
+        for pk_col in table.primary_key_columns() {
+            if !conditions.iter().any(|cond| cond.field == pk_col.name) {
+                conditions.push(Self {
+                    field: pk_col.name.clone(),
+                    order: SortOrder::Asc,
+                    nulls: None,
+                });
+            }
+        }
+
+        Ok(conditions)
+    }
+
+    /// Whether NULL values of this key come before all other values.
+    ///
+    /// Without an explicit `nulls` option SQLite treats NULL as the smallest value.
+    fn nulls_first(&self) -> bool {
+        match self.nulls {
+            Some(NullsOrder::First) => true,
+            Some(NullsOrder::Last) => false,
+            None => matches!(self.order, SortOrder::Asc),
+        }
+    }
+
+    /// Returns the same key sorted in the opposite direction, used for backward pagination.
+    pub fn reversed(&self) -> Self {
+        Self {
+            field: self.field.clone(),
+            order: match self.order {
+                SortOrder::Asc => SortOrder::Desc,
+                SortOrder::Desc => SortOrder::Asc,
+            },
+            nulls: Some(if self.nulls_first() {
+                NullsOrder::Last
+            } else {
+                NullsOrder::First
+            }),
+        }
+    }
+
+    /// Builds the condition matching rows that sort strictly after `value` on this key.
+    fn after(&self, value: SimpleExpr, is_null: bool) -> SimpleExpr {
+        let col = Expr::col(Alias::new(self.field.clone()));
+
+        if is_null {
+            return if self.nulls_first() {
+                col.is_not_null()
+            } else {
+                Expr::value(false)
+            };
+        }
+
+        let cmp = match self.order {
+            SortOrder::Asc => col.gt(value),
+            SortOrder::Desc => col.lt(value),
+        };
+
+        if self.nulls_first() {
+            cmp
+        } else {
+            cmp.or(Expr::col(Alias::new(self.field.clone())).is_null())
+        }
+    }
+
+    /// Builds the keyset condition selecting the rows that come after `cursor`
+    /// when ordered by `sorts`.
+    ///
+    /// For keys `(a, b, id)` this expands to
+    /// `a > ?1 OR (a IS ?1 AND b > ?2) OR (a IS ?1 AND b IS ?2 AND id > ?3)`
+    /// with the comparisons flipped for descending keys and NULLs placed
+    /// according to each key's nulls ordering.
+    pub fn keyset_condition(
+        table: &TableDef,
+        sorts: &[Self],
+        cursor: Vec<(String, serde_json::Value)>,
+    ) -> async_graphql::Result<Condition> {
+        if cursor.len() != sorts.len()
+            || cursor
+                .iter()
+                .zip(sorts.iter())
+                .any(|((name, _), sort)| *name != sort.field)
+        {
+            return Err(anyhow!("Cursor does not match the requested orderBy").into());
+        }
+
+        let mut condition = Condition::any();
+        let mut equal_prefix = Condition::all();
+
+        for (sort, (_, value)) in sorts.iter().zip(cursor) {
+            let col = table
+                .columns
+                .iter()
+                .find(|col| col.name == sort.field)
+                .ok_or(anyhow!("Unable to find column '{}'", sort.field))?;
+
+            let is_null = value.is_null();
+            let value = value.to_simple_expr(&col.data_type)?;
+
+            condition = condition.add(equal_prefix.clone().add(sort.after(value.clone(), is_null)));
+
+            equal_prefix = equal_prefix
+                .add(Expr::col(Alias::new(sort.field.clone())).binary(BinOper::Is, value));
+        }
+
+        Ok(condition)
+    }
+
+    pub fn apply<T: OrderedStatement>(&self, query: &mut T) {
+        let col = Alias::new(self.field.clone());
+
+        let order = match self.order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        };
+
+        match self.nulls {
+            Some(NullsOrder::First) => query.order_by_with_nulls(col, order, NullOrdering::First),
+            Some(NullsOrder::Last) => query.order_by_with_nulls(col, order, NullOrdering::Last),
+            None => query.order_by(col, order),
+        };
+    }
+}
+
 pub fn list_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
     FieldFuture::new(async move {
-        let db = ctx.data::<SqlitePool>()?;
+        track_read(&ctx, &table.name);
 
-        let table_name = table.name;
+        let mut db = read_connection(&ctx).await?;
 
-        let pk_col = table
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .ok_or(anyhow!("Unable to find primary key"))?;
+        let table_name = table.name.clone();
+
+        let pk_expr = primary_key_expr(&table)?;
 
         let page = ctx.args.try_get("page")?.u64()?;
         let per_page = ctx.args.try_get("perPage")?.u64()?;
 
-        let query = Query::select()
+        let mut query = Query::select();
+
+        query
             .from(Alias::new(table_name))
-            .expr(Expr::cust(format!("json_object('id',{})", pk_col.name)))
+            .expr(Expr::cust(pk_expr))
             .offset((page - 1) * per_page)
-            .limit(per_page)
-            .to_string(SqliteQueryBuilder);
+            .limit(per_page);
+
+        if let Some(filter) = ctx.args.get("where") {
+            query.cond_where(filter.to_condition(&table)?);
+        }
+
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Select) {
+            query.cond_where(policy);
+        }
+
+        for sort in DynamicSortCondition::parse_list(&table, ctx.args.get("orderBy"))? {
+            sort.apply(&mut query);
+        }
+
+        let query = query.to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
 
         let result = sqlx::query_as::<_, (serde_json::Value,)>(&query)
-            .fetch_all(db)
+            .fetch_all(&mut *db)
             .await
             .map_err(|e| {
                 debug!("Database query failed: {}", e);
                 e
             })?
             .into_iter()
-            .map(|(val,)| ColumnResolverArgs {
-                name: pk_col.name.clone(),
-                id: val.get("id").unwrap().clone(),
-            })
-            .map(async_graphql::Value::from)
-            .collect::<Vec<_>>();
+            .map(|(val,)| ColumnResolverArgs::new(&table.primary_key_columns(), &val))
+            .map(|args| args.map(async_graphql::Value::from))
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         Ok(Some(Value::List(result)))
     })
 }
 
-pub fn column_resolver(column: ColDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+/// Counts the rows matching the `where` argument of a list query, which returns a plain
+/// list without room for a `totalCount` of its own.
+pub fn total_count_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
     FieldFuture::new(async move {
-        let loader = ctx.data::<DataLoader<ColumnRowLoader>>()?;
+        track_read(&ctx, &table.name);
 
-        let parent_value = ctx.parent_value.try_to_value()?;
+        let mut db = read_connection(&ctx).await?;
 
-        let parent_value = parent_value.clone().into_json()?;
+        let filter = all_conditions([
+            ctx.args
+                .get("where")
+                .map(|filter| filter.to_condition(&table))
+                .transpose()?,
+            policy_condition(&ctx, &table.name, PolicyCommand::Select),
+        ]);
 
-        let name = parent_value
-            .get("name")
-            .ok_or(anyhow!("Unable to get column name"))?
-            .as_str()
-            .ok_or(anyhow!("Unable to convert column to string"))?;
+        let count = count_rows(&mut db, &table, filter).await?;
 
-        let id_val = parent_value
-            .get("id")
-            .ok_or(anyhow!("Unable to get column id value"))?;
+        Ok(Some(Value::from(count)))
+    })
+}
 
-        let result = loader
-            .load_one(ColumnRowDef {
-                table: Alias::new(column.table_name),
-                column: Alias::new(column.name),
-                value: id_val.clone(),
-                primary_column: Alias::new(name),
-            })
-            .await?
-            .ok_or(anyhow!("Unable to get row"))?;
+/// Number of rows of a table matching a condition.
+async fn count_rows(
+    conn: &mut SqliteConnection,
+    table: &TableDef,
+    filter: Option<Condition>,
+) -> async_graphql::Result<i64> {
+    let mut query = Query::select();
 
-        Ok(Some(Value::from_json(result)?))
-    })
+    query
+        .from(Alias::new(table.name.clone()))
+        .expr(Expr::cust("COUNT(*)"));
+
+    if let Some(filter) = filter {
+        query.cond_where(filter);
+    }
+
+    let query = query.to_string(SqliteQueryBuilder);
+
+    debug!("Generated SQL query: {}", query);
+
+    let (count,) = sqlx::query_as::<_, (i64,)>(&query).fetch_one(conn).await?;
+
+    Ok(count)
 }
 
-pub fn view_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+pub fn connection_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
     FieldFuture::new(async move {
-        debug!("Executing view resolver for table: {:?}", table.name);
+        debug!("Executing connection resolver for table: {:?}", table.name);
+
+        track_read(&ctx, &table.name);
+
+        let mut db = read_connection(&ctx).await?;
+
+        let pk_expr = primary_key_expr(&table)?;
+
+        let first = ctx.args.get("first").map(|val| val.u64()).transpose()?;
+        let last = ctx.args.get("last").map(|val| val.u64()).transpose()?;
 
-        let db = ctx.data::<SqlitePool>()?;
+        let after = ctx
+            .args
+            .get("after")
+            .map(|val| val.string().map(decode_cursor))
+            .transpose()?
+            .transpose()?;
 
-        let id = ctx
+        let before = ctx
             .args
-            .get("id")
-            .ok_or(anyhow!("Unable to get id"))?
-            .i64()?;
+            .get("before")
+            .map(|val| val.string().map(decode_cursor))
+            .transpose()?
+            .transpose()?;
+
+        let (limit, backward) = match (first, last) {
+            (Some(first), None) => (first, false),
+            (None, Some(last)) => (last, true),
+            _ => return Err(anyhow!("Exactly one of `first` or `last` is required").into()),
+        };
+
+        let sorts = DynamicSortCondition::parse_list(&table, ctx.args.get("orderBy"))?;
+
+        let filter = all_conditions([
+            ctx.args
+                .get("where")
+                .map(|filter| filter.to_condition(&table))
+                .transpose()?,
+            policy_condition(&ctx, &table.name, PolicyCommand::Select),
+        ]);
+
+        // only count the rows when the client asked for them
+        let total_count = if ctx.look_ahead().field("totalCount").exists() {
+            Some(count_rows(&mut db, &table, filter.clone()).await?)
+        } else {
+            None
+        };
+
+        let mut query = Query::select();
+
+        query
+            .from(Alias::new(table.name.clone()))
+            .expr(Expr::cust(format!(
+                "json_object('id', {}, 'cursor', json_array({}))",
+                pk_expr,
+                sorts
+                    .iter()
+                    .map(|sort| {
+                        // blobs can't be stored in the cursor, their hex encoding is used instead
+                        match table.columns.iter().find(|col| col.name == sort.field) {
+                            Some(col) if col.data_type == ColDataType::Bytes => {
+                                json_value_expr(&col.name, &col.data_type)
+                            }
+                            _ => format!("\"{}\"", sort.field),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+
+        if let Some(filter) = filter {
+            query.cond_where(filter);
+        }
 
-        debug!("View query for ID: {}", id);
+        if let Some(after) = after.clone() {
+            query.cond_where(DynamicSortCondition::keyset_condition(
+                &table, &sorts, after,
+            )?);
+        }
 
-        let table_name = table.name;
+        if let Some(before) = before.clone() {
+            let reversed = sorts.iter().map(|sort| sort.reversed()).collect::<Vec<_>>();
 
-        let pk_col = table
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .ok_or(anyhow!("Unable to find primary key"))?;
+            query.cond_where(DynamicSortCondition::keyset_condition(
+                &table, &reversed, before,
+            )?);
+        }
 
-        let query = Query::select()
-            .from(Alias::new(table_name.to_string()))
-            .expr(Expr::cust(format!("json_object('id',{})", pk_col.name)))
-            .and_where(Expr::col(Alias::new(pk_col.name.to_string())).eq(id))
-            .to_string(SqliteQueryBuilder);
+        for sort in sorts.iter() {
+            if backward {
+                sort.reversed().apply(&mut query);
+            } else {
+                sort.apply(&mut query);
+            }
+        }
+
+        // fetch one extra row to know whether another page exists
+        query.limit(limit + 1);
+
+        let query = query.to_string(SqliteQueryBuilder);
 
         debug!("Generated SQL query: {}", query);
 
-        let result = sqlx::query_as::<_, (serde_json::Value,)>(&query)
-            .fetch_one(db)
+        let mut rows = sqlx::query_as::<_, (serde_json::Value,)>(&query)
+            .fetch_all(&mut *db)
             .await
             .map_err(|e| {
                 debug!("Database query failed: {}", e);
                 e
-            })
-            .map(|(val,)| ColumnResolverArgs {
-                name: pk_col.name.clone(),
-                id: val.get("id").unwrap().clone(),
-            })
-            .map(async_graphql::Value::from)?;
+            })?
+            .into_iter()
+            .map(|(val,)| val)
+            .collect::<Vec<_>>();
 
-        debug!("View resolver found record with ID: {}", id);
-        Ok(Some(result))
+        let has_more = rows.len() as u64 > limit;
+
+        rows.truncate(limit as usize);
+
+        if backward {
+            rows.reverse();
+        }
+
+        let mut edges = vec![];
+
+        for row in rows {
+            let key = ColumnResolverArgs::new(
+                &table.primary_key_columns(),
+                row.get("id").ok_or(anyhow!("Unable to get row id"))?,
+            )?;
+
+            let values = row
+                .get("cursor")
+                .and_then(|val| val.as_array())
+                .ok_or(anyhow!("Unable to get cursor values"))?;
+
+            let cursor = encode_cursor(
+                &sorts
+                    .iter()
+                    .map(|sort| sort.field.clone())
+                    .zip(values.iter().cloned())
+                    .collect::<Vec<_>>(),
+            );
+
+            edges.push(serde_json::json!({
+                "cursor": cursor,
+                "node": key.to_json(),
+            }));
+        }
+
+        let start_cursor = edges.first().map(|edge| edge["cursor"].clone());
+        let end_cursor = edges.last().map(|edge| edge["cursor"].clone());
+
+        let (has_next_page, has_previous_page) = if backward {
+            (before.is_some(), has_more)
+        } else {
+            (has_more, after.is_some())
+        };
+
+        let result = serde_json::json!({
+            "totalCount": total_count,
+            "edges": edges,
+            "pageInfo": {
+                "hasNextPage": has_next_page,
+                "hasPreviousPage": has_previous_page,
+                "startCursor": start_cursor,
+                "endCursor": end_cursor,
+            },
+        });
+
+        Ok(Some(Value::from_json(result)?))
     })
 }
 
-pub fn foreign_key_resolver(
-    foreign_info: ForeignColDef,
-    ctx: ResolverContext<'_>,
-) -> FieldFuture<'_> {
-    FieldFuture::new(async move {
-        let db = ctx.data::<SqlitePool>()?;
-
-        let parent_value = ctx
-            .parent_value
-            .as_value()
-            .ok_or(anyhow::anyhow!("Unable to get parent value"))?
-            .clone();
-
-        let parent_value = parent_value.into_json()?;
-
-        let json_object = parent_value
-            .as_object()
-            .ok_or(anyhow::anyhow!("Unable to get json object"))?;
-
-        let pk_name = json_object
-            .get("name")
-            .map(|val| val.as_str())
-            .ok_or(anyhow::anyhow!("Unable to get primary key column name"))?
-            .ok_or(anyhow::anyhow!("Unable to cast column name as str"))?;
-
-        let pk_id = json_object
-            .get("id")
-            .map(|v| v.as_i64())
-            .ok_or(anyhow::anyhow!("Unable to get primary key id"))?
-            .ok_or(anyhow::anyhow!("Unable to cast id into i64"))?;
-
-        let query = Query::select()
-            .from_as(Alias::new(foreign_info.table.clone()), Alias::new("parent"))
-            .expr(Expr::cust_with_values(
-                format!("json_object(?,parent.{})", foreign_info.to),
-                [foreign_info.to.clone()],
-            ))
-            .inner_join(
-                Alias::new(foreign_info.main_table.clone()),
-                Expr::col((Alias::new("parent"), Alias::new(foreign_info.to.clone()))).equals((
-                    Alias::new(foreign_info.main_table.clone()),
-                    Alias::new(foreign_info.from.clone()),
-                )),
-            )
-            .and_where(
-                Expr::col((
-                    Alias::new(foreign_info.main_table.clone()),
-                    Alias::new(pk_name),
+/// Builds the `json_object` arguments computing `count` and the numeric
+/// `sum`/`avg`/`min`/`max` aggregates of a table.
+fn aggregate_json_fields(table: &TableDef) -> Vec<String> {
+    let mut fields = vec!["'count', COUNT(*)".to_string()];
+
+    let numeric_cols = table.numeric_columns();
+
+    if numeric_cols.is_empty() {
+        return fields;
+    }
+
+    for func in ["sum", "avg", "min", "max"] {
+        fields.push(format!(
+            "'{}', json_object({})",
+            func,
+            numeric_cols
+                .iter()
+                .map(|col| format!(
+                    "'{}', {}(\"{}\")",
+                    col.name.to_camel_case(),
+                    func.to_uppercase(),
+                    col.name
                 ))
-                .eq(pk_id),
-            )
-            .to_string(SqliteQueryBuilder);
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
 
-        println!("{:#?}", query);
+    fields
+}
 
-        let result = sqlx::query_as::<_, (serde_json::Value,)>(&query)
-            .fetch_one(db)
-            .await
-            .map(|(map_val,)| map_val.as_object().unwrap().clone())
-            .map(|val| ColumnResolverArgs {
-                name: foreign_info.to.clone(),
-                id: val.get(&foreign_info.to).unwrap().clone(),
-            })
-            .map(async_graphql::Value::from)?;
+/// Converts the `having` argument of a group by query into a condition on aggregates.
+fn having_condition(
+    table: &TableDef,
+    having: ValueAccessor<'_>,
+) -> async_graphql::Result<Condition> {
+    let mut condition = Condition::all();
 
-        Ok(Some(result))
-    })
+    for (key, val) in having.object()?.iter() {
+        if val.is_null() {
+            continue;
+        }
+
+        match key.as_str() {
+            "and" => {
+                for item in val.list()?.iter() {
+                    condition = condition.add(having_condition(table, item)?);
+                }
+            }
+            "or" => {
+                let mut any = Condition::any();
+
+                for item in val.list()?.iter() {
+                    any = any.add(having_condition(table, item)?);
+                }
+
+                condition = condition.add(any);
+            }
+            "not" => {
+                condition = condition.add(having_condition(table, val)?.not());
+            }
+            "count" => {
+                for (op_name, op_val) in val.object()?.iter() {
+                    if op_val.is_null() {
+                        continue;
+                    }
+
+                    let op =
+                        FilterOperator::parse(op_name.as_str(), op_val, &ColDataType::Integer)?;
+
+                    condition = condition.add(SimpleExpr::from(DynamicFilterCondition::with_expr(
+                        Expr::cust("COUNT(*)"),
+                        op,
+                    )));
+                }
+            }
+            func @ ("sum" | "avg" | "min" | "max") => {
+                for (field, filter) in val.object()?.iter() {
+                    if filter.is_null() {
+                        continue;
+                    }
+
+                    let col = table
+                        .columns
+                        .iter()
+                        .find(|col| col.name.to_camel_case() == field.as_str())
+                        .ok_or(anyhow!("Unknown having field '{}'", field))?;
+
+                    for (op_name, op_val) in filter.object()?.iter() {
+                        if op_val.is_null() {
+                            continue;
+                        }
+
+                        let op =
+                            FilterOperator::parse(op_name.as_str(), op_val, &ColDataType::Float)?;
+
+                        condition =
+                            condition.add(SimpleExpr::from(DynamicFilterCondition::with_expr(
+                                Expr::cust(format!("{}(\"{}\")", func.to_uppercase(), col.name)),
+                                op,
+                            )));
+                    }
+                }
+            }
+            _ => return Err(anyhow!("Unknown having field '{}'", key).into()),
+        }
+    }
+
+    Ok(condition)
 }
 
-pub fn insert_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+pub fn group_by_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
     FieldFuture::new(async move {
-        debug!("Executing insert resolver for table: {:?}", table.name);
+        debug!("Executing group by resolver for table: {:?}", table.name);
 
-        let db = ctx.data::<SqlitePool>()?;
-        let table_name = table.name.to_string();
+        track_read(&ctx, &table.name);
 
-        let input = ctx.args.try_get("value")?;
+        let mut db = read_connection(&ctx).await?;
 
-        let input = input.object()?;
+        let mut group_cols = vec![];
 
-        debug!("Insert data: {} fields", input.len());
+        for item in ctx.args.try_get("by")?.list()?.iter() {
+            let name = item.enum_name()?;
 
-        let mut binding = Query::insert();
+            let col = table
+                .column_by_enum_item(name)
+                .ok_or(anyhow!("Unknown group by field '{}'", name))?;
 
-        let pk_col = table
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .ok_or(anyhow!("Unable to find primary key"))?;
+            group_cols.push(col);
+        }
+
+        if group_cols.is_empty() {
+            return Err(anyhow!("At least one group by field is required").into());
+        }
 
-        let query = binding
-            .into_table(Alias::new(table_name))
-            .columns(input.iter().map(|(name, _)| Alias::new(name.to_string())));
+        let key = format!(
+            "'key', json_object({})",
+            group_cols
+                .iter()
+                .map(|col| format!(
+                    "'{}', {}",
+                    col.name.to_camel_case(),
+                    json_value_expr(&col.name, &col.data_type)
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
 
-        let mut values = vec![];
+        let mut query = Query::select();
 
-        for (key, val) in input.iter() {
-            debug!("Processing field: {}", key);
+        query
+            .from(Alias::new(table.name.clone()))
+            .expr(Expr::cust(format!(
+                "json_object({}, {})",
+                key,
+                aggregate_json_fields(&table).join(", ")
+            )));
 
-            let col_type = &table
-                .columns
-                .iter()
-                .find(|col| col.name.to_string() == *key)
-                .ok_or(anyhow::anyhow!("Unable to get column"))?
-                .data_type;
+        for col in group_cols.iter() {
+            query
+                .group_by_col(Alias::new(col.name.clone()))
+                .order_by(Alias::new(col.name.clone()), Order::Asc);
+        }
+
+        if let Some(filter) = ctx.args.get("where") {
+            query.cond_where(filter.to_condition(&table)?);
+        }
 
-            values.push(val.to_simple_expr(col_type)?);
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Select) {
+            query.cond_where(policy);
         }
 
-        let query = query.returning(Query::returning().column(Alias::new(pk_col.name.to_string())));
+        if let Some(having) = ctx.args.get("having") {
+            query.cond_having(having_condition(&table, having)?);
+        }
 
-        let query = query.values(values)?.to_string(SqliteQueryBuilder);
+        let query = query.to_string(SqliteQueryBuilder);
 
         debug!("Generated SQL query: {}", query);
 
-        let result = sqlx::query_as::<_, (i64,)>(&query)
-            .fetch_one(db)
+        let result = sqlx::query_as::<_, (serde_json::Value,)>(&query)
+            .fetch_all(&mut *db)
             .await
             .map_err(|e| {
-                debug!("Insert query failed: {}", e);
-                anyhow::anyhow!("Insert operation failed: {}", e)
-            })
-            .map(|(val,)| {
-                serde_json::json!({
-                    "name": pk_col.name.to_string(),
-                    "id": val
-                })
-            })?;
-
-        debug!("Insert completed, new ID: {:?}", result);
+                debug!("Database query failed: {}", e);
+                e
+            })?
+            .into_iter()
+            .map(|(val,)| Value::from_json(val))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Some(Value::from_json(result)?))
+        Ok(Some(Value::List(result)))
     })
 }
 
-pub fn update_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+pub fn aggregate_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
     FieldFuture::new(async move {
-        debug!("Executing update resolver for table: {:?}", table.name);
-
-        let table_name = table.name.to_string();
-
-        let pk_col = table
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .ok_or(anyhow!("Unable to find primary key"))?;
-
-        let db = ctx.data::<SqlitePool>()?;
-
-        let id = ctx.args.try_get("id")?;
-
-        let input = ctx.args.try_get("value")?.object()?;
-
-        debug!("Update data: {} fields", input.len());
-
-        let mut binding = Query::update();
+        debug!("Executing aggregate resolver for table: {:?}", table.name);
 
-        // Build the update query
-        let mut query = binding.table(Alias::new(table_name));
+        track_read(&ctx, &table.name);
 
-        // Collect columns and values to update
-        let mut values = vec![];
+        let mut db = read_connection(&ctx).await?;
 
-        for (key, val) in input.iter() {
-            debug!("Processing field: {}", key);
+        let mut query = Query::select();
 
-            let col_type = &table
-                .columns
-                .iter()
-                .find(|col| col.name.to_string() == *key)
-                .ok_or(anyhow::anyhow!("Unable to get column"))?
-                .data_type;
+        query
+            .from(Alias::new(table.name.clone()))
+            .expr(Expr::cust(format!(
+                "json_object({})",
+                aggregate_json_fields(&table).join(", ")
+            )));
 
-            values.push((Alias::new(key.to_string()), val.to_simple_expr(col_type)?));
+        if let Some(filter) = ctx.args.get("where") {
+            query.cond_where(filter.to_condition(&table)?);
         }
 
-        // Set values to update
-        query = query.values(values);
-
-        // Add WHERE clause for primary key
-        query = query.and_where(
-            Expr::col(Alias::new(pk_col.name.to_string()))
-                .eq(id.to_simple_expr(&pk_col.data_type)?),
-        );
-
-        let query = query.returning(Query::returning().column(Alias::new(pk_col.name.to_string())));
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Select) {
+            query.cond_where(policy);
+        }
 
         let query = query.to_string(SqliteQueryBuilder);
 
         debug!("Generated SQL query: {}", query);
 
-        let result = sqlx::query_as::<_, (i64,)>(&query)
-            .fetch_one(db)
+        let (result,) = sqlx::query_as::<_, (serde_json::Value,)>(&query)
+            .fetch_one(&mut *db)
             .await
-            .map(|(val,)| {
-                serde_json::json!({
-                    "name":pk_col.name.to_string(),
-                    "id":val
-                })
+            .map_err(|e| {
+                debug!("Database query failed: {}", e);
+                e
             })?;
 
         Ok(Some(Value::from_json(result)?))
     })
 }
 
-pub fn delete_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+/// Resolves a field by reading the entry with the same key from the parent object.
+///
+/// Used by wrapper types such as connections and edges whose values are produced
+/// entirely by the parent resolver. Nulls resolve to `None`, since custom scalars
+/// reject explicit null values.
+pub fn value_resolver<'a>(key: impl Into<String>, ctx: ResolverContext<'a>) -> FieldFuture<'a> {
+    let key = key.into();
+
     FieldFuture::new(async move {
-        debug!("Executing delete resolver for table: {:?}", table.name);
+        let parent_value = ctx.parent_value.try_to_value()?;
 
-        let table_name = table.name.to_string();
+        let Value::Object(map) = parent_value else {
+            return Err(anyhow!("Unable to get parent object").into());
+        };
 
-        let pk_col = table
-            .columns
-            .iter()
-            .find(|col| col.is_primary)
-            .ok_or(anyhow!("Unable to find primary key"))?;
+        Ok(map
+            .get(key.as_str())
+            .filter(|value| **value != Value::Null)
+            .cloned())
+    })
+}
 
-        let db = ctx.data::<SqlitePool>()?;
+/// Like [`value_resolver`], converting the value of a column of the given type with
+/// [`ColDataType::to_graphql_value`].
+pub fn typed_value_resolver<'a>(
+    key: impl Into<String>,
+    data_type: ColDataType,
+    ctx: ResolverContext<'a>,
+) -> FieldFuture<'a> {
+    let key = key.into();
 
-        let id = ctx.args.try_get("id")?;
+    FieldFuture::new(async move {
+        let parent_value = ctx.parent_value.try_to_value()?;
 
-        let query = Query::delete()
-            .from_table(Alias::new(table_name))
-            .and_where(
-                Expr::col(Alias::new(pk_col.name.to_string()))
-                    .eq(id.to_simple_expr(&pk_col.data_type)?),
-            )
-            .to_string(SqliteQueryBuilder);
+        let Value::Object(map) = parent_value else {
+            return Err(anyhow!("Unable to get parent object").into());
+        };
 
-        debug!("Generated SQL query: {}", query);
+        match map.get(key.as_str()) {
+            Some(value) => Ok(data_type.to_graphql_value(value.clone().into_json()?)?),
+            None => Ok(None),
+        }
+    })
+}
 
-        let result = sqlx::query(&query).execute(db).await?;
+pub fn column_resolver(column: ColDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        track_read(&ctx, &column.table_name);
 
-        debug!(
-            "Delete completed, rows affected: {}",
-            result.rows_affected()
+        let loader = ctx.data::<DataLoader<ColumnRowLoader>>()?;
+
+        let key = ColumnResolverArgs::from_parent(&ctx)?;
+
+        if let Some(value) = key.row_value(&column.name) {
+            return column.data_type.to_graphql_value(value.clone());
+        }
+
+        let result = loader
+            .load_one(ColumnRowDef {
+                table: Alias::new(column.table_name),
+                column: Alias::new(column.name),
+                data_type: Some(column.data_type.clone()),
+                value: key.values(),
+                primary_columns: key.columns(),
+                filter: None,
+            })
+            .await?
+            .ok_or(anyhow!("Unable to get row"))?;
+
+        column.data_type.to_graphql_value(result)
+    })
+}
+
+pub fn view_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing view resolver for table: {:?}", table.name);
+
+        track_read(&ctx, &table.name);
+
+        let mut db = read_connection(&ctx).await?;
+
+        let mut query = Query::select();
+
+        query
+            .from(Alias::new(table.name.clone()))
+            .expr(Expr::cust(primary_key_expr(&table)?))
+            .cond_where(primary_key_condition(&table, &ctx)?);
+
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Select) {
+            query.cond_where(policy);
+        }
+
+        let query = query.to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
+
+        let result = sqlx::query_as::<_, (serde_json::Value,)>(&query)
+            .fetch_optional(&mut *db)
+            .await
+            .map_err(|e| {
+                debug!("Database query failed: {}", e);
+                e
+            })?
+            .map(|(val,)| ColumnResolverArgs::new(&table.primary_key_columns(), &val))
+            .transpose()?
+            .map(async_graphql::Value::from);
+
+        debug!("View resolver found record: {}", result.is_some());
+        Ok(result)
+    })
+}
+
+pub fn foreign_key_resolver(
+    foreign_info: ForeignColDef,
+    not_null: bool,
+    ctx: ResolverContext<'_>,
+) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        track_read(&ctx, &foreign_info.main_table);
+
+        let loader = ctx.data::<DataLoader<ColumnRowLoader>>()?;
+
+        let key = ColumnResolverArgs::from_parent(&ctx)?;
+
+        // the referenced row is identified by the column the foreign key points to
+        let value = match key.row_value(&foreign_info.from) {
+            Some(value) => value.clone(),
+            None => loader
+                .load_one(ColumnRowDef {
+                    table: Alias::new(foreign_info.main_table.clone()),
+                    column: Alias::new(foreign_info.from.clone()),
+                    data_type: None,
+                    value: key.values(),
+                    primary_columns: key.columns(),
+                    filter: None,
+                })
+                .await?
+                .ok_or(anyhow!("Unable to get row"))?,
+        };
+
+        if value.is_null() {
+            return Ok(None);
+        }
+
+        // a referenced row hidden by the policies of its table resolves to null, which fails
+        // non-null fields
+        if let Some(policy) = policy_condition(&ctx, &foreign_info.table, PolicyCommand::Select) {
+            track_read(&ctx, &foreign_info.table);
+
+            let visible = loader
+                .load_one(ColumnRowDef {
+                    table: Alias::new(foreign_info.table.clone()),
+                    column: Alias::new(foreign_info.to.clone()),
+                    data_type: None,
+                    value: vec![value.clone()],
+                    primary_columns: vec![Alias::new(foreign_info.to.clone())],
+                    filter: Some(condition_sql(policy)),
+                })
+                .await?;
+
+            match (visible, not_null) {
+                (Some(_), _) => {}
+                (None, false) => return Ok(None),
+                (None, true) => {
+                    return Err(anyhow!(
+                        "Row of '{}' referenced by '{}' is not accessible",
+                        foreign_info.table,
+                        foreign_info.from
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(Some(Value::from(ColumnResolverArgs {
+            key: vec![(foreign_info.to.clone(), value)],
+            row: None,
+        })))
+    })
+}
+
+pub fn relation_resolver(relation: RelationDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        related_rows_resolver(
+            &ctx,
+            &relation.table,
+            &relation.foreign,
+            &relation.foreign.from,
+            None,
+        )
+        .await
+    })
+}
+
+pub fn junction_resolver(junction: JunctionDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        track_read(&ctx, &junction.junction);
+
+        let junction_row = JunctionRowDef {
+            table: Alias::new(junction.junction.clone()),
+            local_column: Alias::new(junction.local.from.clone()),
+            remote_column: Alias::new(junction.remote.from.clone()),
+            foreign_column: Alias::new(junction.remote.to.clone()),
+            filter: policy_condition(&ctx, &junction.junction, PolicyCommand::Select)
+                .map(condition_sql),
+        };
+
+        related_rows_resolver(
+            &ctx,
+            &junction.table,
+            &junction.local,
+            "__parent",
+            Some(junction_row),
+        )
+        .await
+    })
+}
+
+/// Loads the rows of `table` whose `foreign_column` matches the parent row, applying the
+/// `where`, `orderBy`, `page` and `perPage` arguments of the field.
+async fn related_rows_resolver(
+    ctx: &ResolverContext<'_>,
+    table: &TableDef,
+    parent: &ForeignColDef,
+    foreign_column: &str,
+    junction: Option<JunctionRowDef>,
+) -> async_graphql::Result<Option<Value>> {
+    track_read(ctx, &table.name);
+
+    let loader = ctx.data::<DataLoader<RelationLoader>>()?;
+
+    let pk_cols = table.primary_key_columns();
+
+    if pk_cols.is_empty() {
+        return Err(anyhow!("Unable to find primary key").into());
+    }
+
+    let key = ColumnResolverArgs::from_parent(ctx)?;
+
+    // the foreign key usually references the parent's primary key, otherwise load the
+    // referenced column first
+    let parent_key = match &key.key[..] {
+        [(name, value)] if *name == parent.to => value.clone(),
+        _ => ctx
+            .data::<DataLoader<ColumnRowLoader>>()?
+            .load_one(ColumnRowDef {
+                table: Alias::new(parent.table.clone()),
+                column: Alias::new(parent.to.clone()),
+                data_type: None,
+                value: key.values(),
+                primary_columns: key.columns(),
+                filter: None,
+            })
+            .await?
+            .ok_or(anyhow!("Unable to get row"))?,
+    };
+
+    if parent_key.is_null() {
+        return Ok(Some(Value::List(vec![])));
+    }
+
+    let filter = all_conditions([
+        ctx.args
+            .get("where")
+            .map(|filter| filter.to_condition(table))
+            .transpose()?,
+        policy_condition(ctx, &table.name, PolicyCommand::Select),
+    ])
+    .map(condition_sql);
+
+    let per_page = ctx.args.get("perPage").map(|val| val.u64()).transpose()?;
+    let page = ctx.args.get("page").map(|val| val.u64()).transpose()?;
+
+    let offset = match (page, per_page) {
+        (Some(page), Some(per_page)) => Some(page.saturating_sub(1) * per_page),
+        _ => None,
+    };
+
+    let ids = loader
+        .load_one(RelationRowDef {
+            table: Alias::new(table.name.clone()),
+            foreign_column: Alias::new(foreign_column),
+            primary_columns: pk_cols
+                .iter()
+                .map(|col| Alias::new(col.name.clone()))
+                .collect(),
+            filter,
+            sorts: DynamicSortCondition::parse_list(table, ctx.args.get("orderBy"))?,
+            offset,
+            limit: per_page,
+            junction,
+            value: parent_key,
+        })
+        .await?
+        .unwrap_or_default();
+
+    let result = ids
+        .iter()
+        .map(|id| ColumnResolverArgs::new(&pk_cols, id))
+        .map(|args| args.map(async_graphql::Value::from))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Some(Value::List(result)))
+}
+
+/// Condition of the policies restricting a command on `table` for the request.
+fn policy_condition(
+    ctx: &ResolverContext<'_>,
+    table: &str,
+    command: PolicyCommand,
+) -> Option<Condition> {
+    PolicyScope::from_context(ctx).condition(table, command)
+}
+
+/// Combines conditions with `AND`, `None` when there are none.
+fn all_conditions(conditions: impl IntoIterator<Item = Option<Condition>>) -> Option<Condition> {
+    conditions
+        .into_iter()
+        .flatten()
+        .reduce(|all, condition| Condition::all().add(all).add(condition))
+}
+
+/// Renders a condition as SQL, for the filters of the loaders.
+fn condition_sql(condition: Condition) -> String {
+    let mut sql = String::new();
+
+    SqliteQueryBuilder.prepare_simple_expr(&SimpleExpr::from(condition), &mut sql);
+
+    sql
+}
+
+/// Records a table read by a live query, whose result changes with the table.
+fn track_read(ctx: &ResolverContext<'_>, table: &str) {
+    if let Some(tables) = ctx.data_opt::<LiveTables>() {
+        tables.insert(table);
+    }
+}
+
+/// Acquires a connection running its statements in the scope of the request, so the SQL
+/// functions see who is reading or writing.
+async fn read_connection(ctx: &ResolverContext<'_>) -> async_graphql::Result<DbConnection> {
+    let mut db = Database::from_context(ctx)?.acquire().await?;
+
+    db.enter_request(ctx.data_opt::<RequestInfo>()).await?;
+
+    Ok(db)
+}
+
+/// Acquires the connection of a mutation, whose committed writes are published to the
+/// change subscriptions.
+async fn write_connection(ctx: &ResolverContext<'_>) -> async_graphql::Result<DbConnection> {
+    let mut db = read_connection(ctx).await?;
+
+    if let Some(changes) = ctx.data_opt::<ChangeBus>() {
+        db.watch(changes).await?;
+    }
+
+    Ok(db)
+}
+
+/// Expressions returning the values and the rowid of the rows deleted by a statement,
+/// after its own returned expressions, see [`capture_deleted`].
+fn deleted_row_exprs(table: &TableDef) -> Vec<SimpleExpr> {
+    // changes to tables without rowid are not published
+    if table.without_rowid {
+        return vec![];
+    }
+
+    vec![Expr::cust("*"), Expr::cust("rowid")]
+}
+
+/// Attaches the values of deleted rows, returned after the first `skip` columns by the
+/// expressions of [`deleted_row_exprs`], to the changes published to the subscriptions.
+async fn capture_deleted(
+    ctx: &ResolverContext<'_>,
+    table: &TableDef,
+    conn: &mut SqliteConnection,
+    rows: &[SqliteRow],
+    skip: usize,
+) -> async_graphql::Result<()> {
+    let Some(changes) = ctx.data_opt::<ChangeBus>() else {
+        return Ok(());
+    };
+
+    if table.without_rowid {
+        return Ok(());
+    }
+
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let rowid = row.len() - 1;
+
+            let values = (skip..rowid)
+                .map(|index| {
+                    Ok((
+                        row.column(index).name().to_string(),
+                        stored_value(row, index)?,
+                    ))
+                })
+                .collect::<sqlx::Result<RowValues>>()?;
+
+            Ok((row.try_get::<i64, _>(rowid)?, values))
+        })
+        .collect::<sqlx::Result<Vec<_>>>()?;
+
+    changes.capture_deleted(conn, &table.name, rows).await?;
+
+    Ok(())
+}
+
+/// Condition matching the junction row linking the pair of keys passed as arguments.
+fn junction_pair_condition(
+    table: &TableDef,
+    ctx: &ResolverContext<'_>,
+) -> async_graphql::Result<Condition> {
+    let (local, remote) = table
+        .junction_columns()
+        .ok_or(anyhow!("{} is not a junction table", table.name))?;
+
+    let mut condition = Condition::all();
+
+    for col in [local, remote] {
+        let value = ctx.args.try_get(&col.name.to_camel_case())?;
+
+        condition = condition
+            .add(Expr::col(Alias::new(col.name.clone())).eq(value.to_simple_expr(&col.data_type)?));
+    }
+
+    Ok(condition)
+}
+
+pub fn connect_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing connect resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+
+        let pk_expr = primary_key_expr(&table)?;
+
+        let (local, remote) = table
+            .junction_columns()
+            .ok_or(anyhow!("{} is not a junction table", table.name))?;
+
+        let condition = Condition::all()
+            .add(junction_pair_condition(&table, &ctx)?)
+            .add_option(policy_condition(&ctx, &table.name, PolicyCommand::Select));
+
+        // connecting an already connected pair returns the existing row
+        let existing_query = Query::select()
+            .from(Alias::new(table.name.clone()))
+            .expr(Expr::cust(pk_expr.clone()))
+            .cond_where(condition)
+            .limit(1)
+            .to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", existing_query);
+
+        // a row violating the insert policy is rolled back
+        let mut tx = db.begin().await?;
+
+        let existing = sqlx::query_as::<_, (serde_json::Value,)>(&existing_query)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let id = match existing {
+            Some((id,)) => id,
+            None => {
+                let mut values = vec![];
+
+                for col in [local, remote] {
+                    values.push(
+                        ctx.args
+                            .try_get(&col.name.to_camel_case())?
+                            .to_simple_expr(&col.data_type)?,
+                    );
+                }
+
+                let insert_query = Query::insert()
+                    .into_table(Alias::new(table.name.clone()))
+                    .columns([
+                        Alias::new(local.name.clone()),
+                        Alias::new(remote.name.clone()),
+                    ])
+                    .values(values)?
+                    .returning(Query::returning().exprs([
+                        Expr::cust(pk_expr),
+                        policy_check_expr(policy_condition(
+                            &ctx,
+                            &table.name,
+                            PolicyCommand::Insert,
+                        )),
+                    ]))
+                    .to_string(SqliteQueryBuilder);
+
+                debug!("Generated SQL query: {}", insert_query);
+
+                let (id, check) =
+                    sqlx::query_as::<_, (serde_json::Value, Option<bool>)>(&insert_query)
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(|e| anyhow!("Connect operation failed: {}", e))?;
+
+                ensure_policy_check(&table, check)?;
+
+                id
+            }
+        };
+
+        tx.commit().await?;
+
+        Ok(Some(Value::from(ColumnResolverArgs::new(
+            &table.primary_key_columns(),
+            &id,
+        )?)))
+    })
+}
+
+pub fn disconnect_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing disconnect resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+
+        let mut query = Query::delete();
+
+        query
+            .from_table(Alias::new(table.name.clone()))
+            .cond_where(junction_pair_condition(&table, &ctx)?)
+            .returning(
+                Query::returning().exprs(
+                    [Expr::cust(primary_key_expr(&table)?)]
+                        .into_iter()
+                        .chain(deleted_row_exprs(&table)),
+                ),
+            );
+
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Delete) {
+            query.cond_where(policy);
+        }
+
+        let query = query.to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
+
+        let rows = sqlx::query(&query).fetch_all(&mut *db).await?;
+
+        capture_deleted(&ctx, &table, &mut db, &rows, 1).await?;
+
+        debug!("Disconnect completed, rows affected: {}", rows.len());
+
+        Ok(Some(Value::from(rows.len())))
+    })
+}
+
+/// Converts the column fields of an insert or update input to column values.
+///
+/// Input fields are camelCased column names, explicit nulls set the column to `NULL`.
+/// Relationship fields are written by [`insert_row`] and [`update_row`].
+fn input_values(
+    table: &TableDef,
+    input: &ObjectAccessor<'_>,
+) -> async_graphql::Result<Vec<(Alias, SimpleExpr)>> {
+    let mut values = vec![];
+
+    for col in table.columns.iter() {
+        let Some(val) = input.get(&col.name.to_camel_case()) else {
+            continue;
+        };
+
+        debug!("Processing field: {}", col.name);
+
+        let value = if val.is_null() {
+            SimpleExpr::Keyword(Keyword::Null)
+        } else {
+            val.to_simple_expr(&col.data_type)?
+        };
+
+        values.push((Alias::new(col.name.clone()), value));
+    }
+
+    Ok(values)
+}
+
+/// Fails when an input sets relationship fields, which only inserts and single-row updates
+/// support.
+fn reject_relation_inputs(
+    table: &TableDef,
+    input: &ObjectAccessor<'_>,
+) -> async_graphql::Result<()> {
+    let parents = table
+        .columns
+        .iter()
+        .filter_map(|col| col.relationship.as_ref()?.nested_field_name());
+
+    let children = table
+        .writable_relations()
+        .into_iter()
+        .map(|relation| table.relation_field_name(relation));
+
+    match parents
+        .chain(children)
+        .find(|name| input.get(name).is_some())
+    {
+        Some(name) => Err(anyhow!("Relationship field '{}' is not supported here", name).into()),
+        None => Ok(()),
+    }
+}
+
+/// Builds the statement inserting a single row, returning its primary key.
+fn insert_statement(
+    table: &TableDef,
+    values: Vec<(Alias, SimpleExpr)>,
+) -> async_graphql::Result<InsertStatement> {
+    let mut query = Query::insert();
+
+    let pk_expr = primary_key_expr(table)?;
+
+    query
+        .into_table(Alias::new(table.name.clone()))
+        .columns(values.iter().map(|(col, _)| col.clone()))
+        .returning(Query::returning().expr(Expr::cust(pk_expr)));
+
+    // omitted columns take their defaults
+    if values.is_empty() {
+        query.or_default_values();
+    } else {
+        query.values(values.into_iter().map(|(_, val)| val))?;
+    }
+
+    Ok(query)
+}
+
+/// A row written by [`insert_row`] or [`update_row`].
+struct WrittenRow {
+    key: ColumnResolverArgs,
+    referenced: serde_json::Value, // values of the columns referenced by foreign keys
+}
+
+impl WrittenRow {
+    /// Value of a referenced column, to store in the foreign key of a related row.
+    fn referenced_value(&self, column: &str) -> async_graphql::Result<SimpleExpr> {
+        let value = self
+            .referenced
+            .get(column)
+            .ok_or(anyhow!("Unable to get the value of '{}'", column))?;
+
+        Ok(SimpleExpr::Value(json_to_sql_value(value)))
+    }
+}
+
+type WriteFuture<'a> = Pin<Box<dyn Future<Output = async_graphql::Result<WrittenRow>> + Send + 'a>>;
+
+/// Evaluates the policy condition a written row must match, `TRUE` without policies.
+fn policy_check_expr(check: Option<Condition>) -> SimpleExpr {
+    check
+        .map(SimpleExpr::from)
+        .unwrap_or(Expr::val(true).into())
+}
+
+/// Fails when a written row does not match the policy condition of its table, which rolls
+/// back the transaction of the write.
+fn ensure_policy_check(table: &TableDef, check: Option<bool>) -> async_graphql::Result<()> {
+    match check {
+        Some(true) => Ok(()),
+        _ => Err(anyhow!("Row written to '{}' violates its policies", table.name).into()),
+    }
+}
+
+/// Selects the primary key of a written row, the columns other tables reference and whether
+/// it matches the policy condition `check`.
+fn written_row_exprs(
+    table: &TableDef,
+    check: Option<Condition>,
+) -> async_graphql::Result<[SimpleExpr; 3]> {
+    let mut referenced = table
+        .relations
+        .iter()
+        .map(|relation| relation.foreign.to.as_str())
+        .collect::<Vec<_>>();
+
+    referenced.sort();
+    referenced.dedup();
+
+    let referenced_expr = format!(
+        "json_object({})",
+        referenced
+            .iter()
+            .map(|col| format!("'{}', \"{}\"", col, col))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    Ok([
+        Expr::cust(primary_key_expr(table)?),
+        Expr::cust(referenced_expr),
+        policy_check_expr(check),
+    ])
+}
+
+async fn fetch_written_row(
+    conn: &mut SqliteConnection,
+    table: &TableDef,
+    query: &str,
+) -> async_graphql::Result<WrittenRow> {
+    debug!("Generated SQL query: {}", query);
+
+    let (key, referenced, check) =
+        sqlx::query_as::<_, (serde_json::Value, serde_json::Value, Option<bool>)>(query)
+            .fetch_one(conn)
+            .await
+            .map_err(|e| {
+                debug!("Write query failed: {}", e);
+                anyhow!("Writing to '{}' failed: {}", table.name, e)
+            })?;
+
+    ensure_policy_check(table, check)?;
+
+    Ok(WrittenRow {
+        key: ColumnResolverArgs::new(&table.primary_key_columns(), &key)?,
+        referenced,
+    })
+}
+
+/// Connects or creates the rows referenced by the relationship fields of an input, returning
+/// the values of the foreign keys pointing to them.
+async fn parent_values(
+    conn: &mut SqliteConnection,
+    tables: &TableRegistry,
+    policies: PolicyScope<'_>,
+    table: &TableDef,
+    input: &ObjectAccessor<'_>,
+) -> async_graphql::Result<Vec<(Alias, SimpleExpr)>> {
+    let mut values = vec![];
+
+    for foreign in table
+        .columns
+        .iter()
+        .filter_map(|col| col.relationship.as_ref())
+    {
+        let Some(field) = foreign
+            .nested_field_name()
+            .and_then(|name| input.get(&name))
+            .filter(|field| !field.is_null())
+        else {
+            continue;
+        };
+
+        if input.get(&foreign.from.to_camel_case()).is_some() {
+            return Err(anyhow!(
+                "'{}' and '{}' can't both be set",
+                foreign.from.to_camel_case(),
+                foreign.field_name()
+            )
+            .into());
+        }
+
+        let parent = tables.get(&foreign.table)?;
+        let field = field.object()?;
+
+        let value = if let Some(connect) = field.get("connect") {
+            let col = parent
+                .columns
+                .iter()
+                .find(|col| col.name == foreign.to)
+                .ok_or(anyhow!("Unknown column '{}'", foreign.to))?;
+
+            connect
+                .object()?
+                .try_get(&foreign.to.to_camel_case())?
+                .to_simple_expr(&col.data_type)?
+        } else {
+            let create = field.try_get("create")?.object()?;
+
+            insert_row(&mut *conn, tables, policies, parent, create, vec![])
+                .await?
+                .referenced_value(&foreign.to)?
+        };
+
+        values.push((Alias::new(foreign.from.clone()), value));
+    }
+
+    Ok(values)
+}
+
+/// Creates the children listed in the reverse relationship fields of an input.
+async fn insert_children(
+    conn: &mut SqliteConnection,
+    tables: &TableRegistry,
+    policies: PolicyScope<'_>,
+    table: &TableDef,
+    input: &ObjectAccessor<'_>,
+    row: &WrittenRow,
+) -> async_graphql::Result<()> {
+    for relation in table.writable_relations() {
+        let Some(field) = input
+            .get(&table.relation_field_name(relation))
+            .filter(|field| !field.is_null())
+        else {
+            continue;
+        };
+
+        let child = tables.get(&relation.table.name)?;
+        let parent_value = row.referenced_value(&relation.foreign.to)?;
+
+        for item in field.object()?.try_get("create")?.list()?.iter() {
+            let link = (
+                Alias::new(relation.foreign.from.clone()),
+                parent_value.clone(),
+            );
+
+            insert_row(
+                &mut *conn,
+                tables,
+                policies,
+                child,
+                item.object()?,
+                vec![link],
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Inserts a row along with the rows its relationship fields connect or create, in dependency
+/// order: referenced rows first, then the row, then its children.
+///
+/// `links` holds the foreign keys to the parent of a row created as a child. Every inserted
+/// row must match the insert policy of its table, whose permissions must allow inserts.
+fn insert_row<'a>(
+    conn: &'a mut SqliteConnection,
+    tables: &'a TableRegistry,
+    policies: PolicyScope<'a>,
+    table: &'a TableDef,
+    input: ObjectAccessor<'a>,
+    links: Vec<(Alias, SimpleExpr)>,
+) -> WriteFuture<'a> {
+    Box::pin(async move {
+        // nested creates reach tables whose insert mutations are left out of the schema
+        if !table.allows(TableOperation::Insert) {
+            return Err(anyhow!("Inserting into '{}' is not allowed", table.name).into());
+        }
+
+        let mut values = input_values(table, &input)?;
+
+        values.extend(parent_values(&mut *conn, tables, policies, table, &input).await?);
+
+        // the link to the parent a child is created for wins over the input
+        values.retain(|(col, _)| {
+            !links
+                .iter()
+                .any(|(link, _)| link.to_string() == col.to_string())
+        });
+        values.extend(links);
+
+        let check = policies.condition(&table.name, PolicyCommand::Insert);
+
+        let query = insert_statement(table, values)?
+            .returning(Query::returning().exprs(written_row_exprs(table, check)?))
+            .to_string(SqliteQueryBuilder);
+
+        let row = fetch_written_row(&mut *conn, table, &query).await?;
+
+        insert_children(&mut *conn, tables, policies, table, &input, &row).await?;
+
+        Ok(row)
+    })
+}
+
+/// Updates the row matching `condition` along with the rows its relationship fields connect
+/// or create, see [`insert_row`].
+///
+/// Only a row matching the update policy of the table can be updated, and it must still
+/// match it afterwards.
+fn update_row<'a>(
+    conn: &'a mut SqliteConnection,
+    tables: &'a TableRegistry,
+    policies: PolicyScope<'a>,
+    table: &'a TableDef,
+    input: ObjectAccessor<'a>,
+    condition: Condition,
+) -> WriteFuture<'a> {
+    Box::pin(async move {
+        let mut values = input_values(table, &input)?;
+
+        values.extend(parent_values(&mut *conn, tables, policies, table, &input).await?);
+
+        let policy = policies.condition(&table.name, PolicyCommand::Update);
+
+        let condition = Condition::all().add(condition).add_option(policy.clone());
+
+        // an input that only creates children leaves the row itself untouched
+        let query = if values.is_empty() {
+            Query::select()
+                .from(Alias::new(table.name.clone()))
+                .exprs(written_row_exprs(table, policy)?)
+                .cond_where(condition)
+                .to_string(SqliteQueryBuilder)
+        } else {
+            Query::update()
+                .table(Alias::new(table.name.clone()))
+                .values(values)
+                .cond_where(condition)
+                .returning(Query::returning().exprs(written_row_exprs(table, policy)?))
+                .to_string(SqliteQueryBuilder)
+        };
+
+        let row = fetch_written_row(&mut *conn, table, &query).await?;
+
+        insert_children(&mut *conn, tables, policies, table, &input, &row).await?;
+
+        Ok(row)
+    })
+}
+
+/// Result of a bulk mutation: the number of affected rows and the affected nodes.
+fn mutation_response(nodes: Vec<ColumnResolverArgs>) -> async_graphql::Result<Value> {
+    Ok(Value::from_json(serde_json::json!({
+        "affectedRows": nodes.len(),
+        "nodes": nodes.iter().map(ColumnResolverArgs::to_json).collect::<Vec<_>>(),
+    }))?)
+}
+
+pub fn insert_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing insert resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+        let tables = ctx.data::<TableRegistry>()?;
+
+        let input = ctx.args.try_get("value")?.object()?;
+
+        debug!("Insert data: {} fields", input.len());
+
+        // related rows are written in the same transaction
+        let mut tx = db.begin().await?;
+
+        let row = insert_row(
+            &mut tx,
+            tables,
+            PolicyScope::from_context(&ctx),
+            &table,
+            input,
+            vec![],
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        debug!("Insert completed, new key: {:?}", row.key);
+
+        Ok(Some(Value::from(row.key)))
+    })
+}
+
+pub fn insert_many_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing insert many resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+        let tables = ctx.data::<TableRegistry>()?;
+
+        let inputs = ctx.args.try_get("values")?.list()?;
+
+        debug!("Inserting {} rows", inputs.len());
+
+        // either all rows are inserted or none
+        let mut tx = db.begin().await?;
+        let mut nodes = Vec::with_capacity(inputs.len());
+
+        let policies = PolicyScope::from_context(&ctx);
+
+        for input in inputs.iter() {
+            let row =
+                insert_row(&mut tx, tables, policies, &table, input.object()?, vec![]).await?;
+
+            nodes.push(row.key);
+        }
+
+        tx.commit().await?;
+
+        debug!("Insert completed, {} rows inserted", nodes.len());
+
+        Ok(Some(mutation_response(nodes)?))
+    })
+}
+
+/// Whether a row has the values an upsert inserts in the columns of its conflict target,
+/// so that the upsert updates it.
+async fn upsert_conflicts(
+    conn: &mut SqliteConnection,
+    table: &TableDef,
+    target: &[String],
+    values: &[(Alias, SimpleExpr)],
+) -> async_graphql::Result<bool> {
+    let mut condition = Condition::all();
+
+    for name in target {
+        let value = match values.iter().find(|(col, _)| col.to_string() == *name) {
+            Some((_, value)) => value.clone(),
+            // omitted columns take their defaults, and NULLs never conflict
+            None => match table
+                .columns
+                .iter()
+                .find(|col| col.name == *name)
+                .and_then(|col| col.default_value.as_ref())
+            {
+                Some(default) => Expr::cust(default),
+                None => return Ok(false),
+            },
+        };
+
+        condition = condition.add(Expr::col(Alias::new(name.clone())).eq(value));
+    }
+
+    let query = Query::select()
+        .expr(Expr::val(1))
+        .from(Alias::new(table.name.clone()))
+        .cond_where(condition)
+        .to_string(SqliteQueryBuilder);
+
+    debug!("Generated SQL query: {}", query);
+
+    Ok(sqlx::query(&query).fetch_optional(conn).await?.is_some())
+}
+
+pub fn upsert_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing upsert resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+
+        let input = ctx.args.try_get("value")?.object()?;
+        let on_conflict = ctx.args.try_get("onConflict")?.object()?;
+
+        let constraint = on_conflict.try_get("constraint")?.enum_name()?.to_string();
+
+        let (_, target) = table
+            .conflict_targets()
+            .into_iter()
+            .find(|(item, _)| *item == constraint)
+            .ok_or(anyhow!("Unknown constraint '{}'", constraint))?;
+
+        let update = on_conflict
+            .try_get("update")?
+            .list()?
+            .iter()
+            .map(|item| {
+                let item = item.enum_name()?;
+
+                let col = table
+                    .column_by_enum_item(item)
+                    .ok_or(anyhow!("Unknown column '{}'", item))?;
+
+                // the columns enum also lists the ones only the filters and sorting see
+                if !col.writable {
+                    return Err(anyhow!("Column '{}' can't be written", col.name).into());
+                }
+
+                Ok(Alias::new(col.name.clone()))
+            })
+            .collect::<async_graphql::Result<Vec<_>>>()?;
+
+        let mut clause = OnConflict::columns(target.iter().cloned().map(Alias::new));
+
+        // without columns to update the existing row is kept and nothing is returned
+        if update.is_empty() {
+            clause.do_nothing();
+        } else {
+            clause.update_columns(update);
+
+            // so is an existing row outside of the update policy
+            if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Update) {
+                clause.action_and_where(policy.into());
+            }
+        }
+
+        reject_relation_inputs(&table, &input)?;
+
+        let values = input_values(&table, &input)?;
+
+        // a row violating the policy of the path it took is rolled back
+        let mut tx = db.begin().await?;
+
+        // an updated row must match the update policy, an inserted one the insert policy
+        let command = match upsert_conflicts(&mut tx, &table, &target, &values).await? {
+            true => PolicyCommand::Update,
+            false => PolicyCommand::Insert,
+        };
+
+        let query = insert_statement(&table, values)?
+            .on_conflict(clause)
+            .returning(Query::returning().exprs([
+                Expr::cust(primary_key_expr(&table)?),
+                policy_check_expr(policy_condition(&ctx, &table.name, command)),
+            ]))
+            .to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
+
+        let result = sqlx::query_as::<_, (serde_json::Value, Option<bool>)>(&query)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| {
+                debug!("Upsert query failed: {}", e);
+                anyhow::anyhow!("Upsert operation failed: {}", e)
+            })?
+            .map(|(val, check)| {
+                ensure_policy_check(&table, check)?;
+
+                Ok::<_, async_graphql::Error>(ColumnResolverArgs::new(
+                    &table.primary_key_columns(),
+                    &val,
+                )?)
+            })
+            .transpose()?;
+
+        tx.commit().await?;
+
+        Ok(result.map(Value::from))
+    })
+}
+
+pub fn update_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing update resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+        let tables = ctx.data::<TableRegistry>()?;
+
+        let input = ctx.args.try_get("value")?.object()?;
+
+        debug!("Update data: {} fields", input.len());
+
+        let condition = primary_key_condition(&table, &ctx)?;
+
+        // related rows are written in the same transaction
+        let mut tx = db.begin().await?;
+
+        let row = update_row(
+            &mut tx,
+            tables,
+            PolicyScope::from_context(&ctx),
+            &table,
+            input,
+            condition,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(Value::from(row.key)))
+    })
+}
+
+pub fn delete_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing delete resolver for table: {:?}", table.name);
+
+        let table_name = table.name.to_string();
+
+        let mut db = write_connection(&ctx).await?;
+
+        let mut query = Query::delete();
+
+        query
+            .from_table(Alias::new(table_name))
+            .cond_where(primary_key_condition(&table, &ctx)?)
+            .returning(
+                Query::returning().exprs(
+                    [Expr::cust(primary_key_expr(&table)?)]
+                        .into_iter()
+                        .chain(deleted_row_exprs(&table)),
+                ),
+            );
+
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Delete) {
+            query.cond_where(policy);
+        }
+
+        let query = query.to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
+
+        let rows = sqlx::query(&query).fetch_all(&mut *db).await?;
+
+        capture_deleted(&ctx, &table, &mut db, &rows, 1).await?;
+
+        debug!("Delete completed, rows affected: {}", rows.len());
+
+        Ok(Some(Value::from(rows.len())))
+    })
+}
+
+pub fn update_many_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing update many resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+
+        let set = ctx.args.try_get("set")?.object()?;
+
+        reject_relation_inputs(&table, &set)?;
+
+        let values = input_values(&table, &set)?;
+
+        if values.is_empty() {
+            return Err(anyhow!("No columns to update").into());
+        }
+
+        let policy = policy_condition(&ctx, &table.name, PolicyCommand::Update);
+
+        // a single statement, so the update is atomic
+        let mut query = Query::update();
+
+        query
+            .table(Alias::new(table.name.clone()))
+            .values(values)
+            .cond_where(ctx.args.try_get("where")?.to_condition(&table)?)
+            .returning(Query::returning().exprs([
+                Expr::cust(primary_key_expr(&table)?),
+                policy_check_expr(policy.clone()),
+            ]));
+
+        if let Some(policy) = policy {
+            query.cond_where(policy);
+        }
+
+        let query = query.to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
+
+        // a row no longer matching the update policy rolls back the whole update
+        let mut tx = db.begin().await?;
+
+        let nodes = sqlx::query_as::<_, (serde_json::Value, Option<bool>)>(&query)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|(val, check)| {
+                ensure_policy_check(&table, check)?;
+
+                Ok(ColumnResolverArgs::new(&table.primary_key_columns(), &val)?)
+            })
+            .collect::<async_graphql::Result<Vec<_>>>()?;
+
+        tx.commit().await?;
+
+        debug!("Update completed, rows affected: {}", nodes.len());
+
+        Ok(Some(mutation_response(nodes)?))
+    })
+}
+
+pub fn delete_many_resolver(table: TableDef, ctx: ResolverContext<'_>) -> FieldFuture<'_> {
+    FieldFuture::new(async move {
+        debug!("Executing delete many resolver for table: {:?}", table.name);
+
+        let mut db = write_connection(&ctx).await?;
+
+        // deleted rows can't be loaded anymore, so their values are returned with their keys
+        let row_expr = json_row_expr(
+            &table
+                .columns
+                .iter()
+                .map(|col| (col.name.as_str(), &col.data_type))
+                .collect::<Vec<_>>(),
         );
 
-        Ok(Some(Value::from_json(
-            serde_json::json!({"rows_affected":result.rows_affected()}),
-        )?))
+        let mut query = Query::delete();
+
+        query
+            .from_table(Alias::new(table.name.clone()))
+            .cond_where(ctx.args.try_get("where")?.to_condition(&table)?)
+            .returning(
+                Query::returning().exprs(
+                    [Expr::cust(primary_key_expr(&table)?), Expr::cust(row_expr)]
+                        .into_iter()
+                        .chain(deleted_row_exprs(&table)),
+                ),
+            );
+
+        if let Some(policy) = policy_condition(&ctx, &table.name, PolicyCommand::Delete) {
+            query.cond_where(policy);
+        }
+
+        let query = query.to_string(SqliteQueryBuilder);
+
+        debug!("Generated SQL query: {}", query);
+
+        let rows = sqlx::query(&query).fetch_all(&mut *db).await?;
+
+        capture_deleted(&ctx, &table, &mut db, &rows, 2).await?;
+
+        let nodes = rows
+            .iter()
+            .map(|row| {
+                let key = row.try_get::<serde_json::Value, _>(0)?;
+
+                ColumnResolverArgs::new(&table.primary_key_columns(), &key)?
+                    .with_row(row.try_get(1)?)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        debug!("Delete completed, rows affected: {}", nodes.len());
+
+        Ok(Some(mutation_response(nodes)?))
     })
 }
+
+pub fn changed_subscription_resolver(
+    table: TableDef,
+    ctx: ResolverContext<'_>,
+) -> SubscriptionFieldFuture<'_> {
+    SubscriptionFieldFuture::new(async move {
+        debug!("Subscribing to changes of table: {:?}", table.name);
+
+        let changes = ctx.data::<ChangeBus>()?.subscribe();
+        let db = Database::from_context(&ctx)?;
+        let request = ctx.data_opt::<RequestInfo>().cloned();
+
+        // the claims of a subscription don't change, neither does its policy condition
+        let condition = all_conditions([
+            ctx.args
+                .get("where")
+                .map(|filter| filter.to_condition(&table))
+                .transpose()?,
+            policy_condition(&ctx, &table.name, PolicyCommand::Select),
+        ]);
+
+        // deleted rows are matched against their values, with the affinity of their columns
+        let types =
+            sqlx::query_as::<_, (String, String)>("SELECT name, type FROM pragma_table_xinfo(?)")
+                .bind(&table.name)
+                .fetch_all(&mut *read_connection(&ctx).await?)
+                .await?
+                .into_iter()
+                .collect::<HashMap<_, _>>();
+
+        let state = (changes, table, db, request, condition, types);
+
+        Ok(stream::unfold(
+            state,
+            |(mut changes, table, db, request, condition, types)| async move {
+                loop {
+                    let change = match changes.recv().await {
+                        Ok(change) => change,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!(
+                                "Subscription to '{}' skipped {} changes",
+                                table.name, skipped
+                            );
+                            continue;
+                        }
+                        Err(RecvError::Closed) => return None,
+                    };
+
+                    if change.table != table.name {
+                        continue;
+                    }
+
+                    let event = match change_event(
+                        &table,
+                        &db,
+                        request.as_ref(),
+                        condition.as_ref(),
+                        &types,
+                        &change,
+                    )
+                    .await
+                    {
+                        Ok(Some(event)) => Ok(event),
+                        Ok(None) => continue,
+                        Err(e) => Err(e),
+                    };
+
+                    return Some((event, (changes, table, db, request, condition, types)));
+                }
+            },
+        ))
+    })
+}
+
+/// Builds the event notifying a change, `None` when the written row doesn't match the
+/// filter of the subscription or no longer exists.
+///
+/// Deleted rows can't be loaded anymore, they are matched against the values captured
+/// by the delete instead. Rows deleted by triggers or foreign key actions have none, so
+/// they are only notified to subscriptions without filter.
+async fn change_event(
+    table: &TableDef,
+    db: &Database,
+    request: Option<&RequestInfo>,
+    condition: Option<&Condition>,
+    types: &HashMap<String, String>,
+    change: &TableChange,
+) -> async_graphql::Result<Option<Value>> {
+    if change.operation == ChangeOperation::Delete {
+        let matches = match (condition, &change.row) {
+            (None, _) => true,
+            (Some(condition), Some(row)) => {
+                deleted_row_matches(table, db, request, condition, types, row).await?
+            }
+            (Some(_), None) => false,
+        };
+
+        if !matches {
+            return Ok(None);
+        }
+
+        return Ok(Some(Value::from_json(serde_json::json!({
+            "operation": change.operation.as_str(),
+            "node": null,
+        }))?));
+    }
+
+    let mut query = Query::select();
+
+    query
+        .from(Alias::new(table.name.clone()))
+        .expr(Expr::cust(primary_key_expr(table)?))
+        .and_where(Expr::cust_with_values("rowid = ?", [change.rowid]));
+
+    if let Some(condition) = condition {
+        query.cond_where(condition.clone());
+    }
+
+    let query = query.to_string(SqliteQueryBuilder);
+
+    debug!("Generated SQL query: {}", query);
+
+    let mut conn = db.acquire().await?;
+
+    // policies of the subscription may read its claims
+    conn.enter_request(request).await?;
+
+    let Some((key,)) = sqlx::query_as::<_, (serde_json::Value,)>(&query)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let node = ColumnResolverArgs::new(&table.primary_key_columns(), &key)?;
+
+    Ok(Some(Value::from_json(serde_json::json!({
+        "operation": change.operation.as_str(),
+        "node": node.to_json(),
+    }))?))
+}
+
+/// Evaluates a condition on the values of a deleted row, selected in place of its table.
+async fn deleted_row_matches(
+    table: &TableDef,
+    db: &Database,
+    request: Option<&RequestInfo>,
+    condition: &Condition,
+    types: &HashMap<String, String>,
+    row: &RowValues,
+) -> async_graphql::Result<bool> {
+    let mut values = Query::select();
+
+    for (name, value) in row.iter() {
+        let declared_type = types.get(name).map(String::as_str).unwrap_or_default();
+
+        values.expr_as(
+            stored_value_expr(value.clone(), declared_type),
+            Alias::new(name),
+        );
+    }
+
+    let query = Query::select()
+        .expr(Expr::val(1))
+        .from(Alias::new(table.name.clone()))
+        .cond_where(condition.clone())
+        .to_owned()
+        .with(
+            WithClause::new()
+                .cte(
+                    CommonTableExpression::new()
+                        .query(values)
+                        .table_name(Alias::new(table.name.clone()))
+                        .to_owned(),
+                )
+                .to_owned(),
+        )
+        .to_string(SqliteQueryBuilder);
+
+    debug!("Generated SQL query: {}", query);
+
+    let mut conn = db.acquire().await?;
+
+    conn.enter_request(request).await?;
+
+    Ok(sqlx::query(&query)
+        .fetch_optional(&mut *conn)
+        .await?
+        .is_some())
+}
@@ -31,21 +31,25 @@
 //! - **Complete CRUD**: Full Create, Read, Update, Delete operation generation
 //! - **Dynamic schema**: All types are generated at runtime using async-graphql's dynamic API
 
-use async_graphql::dynamic::{Enum, Field, InputObject, InputValue, Object, Scalar, TypeRef};
-use sea_query::SimpleExpr;
+use async_graphql::dynamic::{
+    Enum, Field, InputObject, InputValue, Object, Scalar, SubscriptionField, TypeRef,
+};
+use sea_query::{Condition, SimpleExpr};
 
-use crate::parser::ColDataType;
+use crate::parser::{ColDataType, TableDef};
 
 /// Converts SQLite column definitions to GraphQL scalar types.
 ///
 /// This trait provides functionality to map SQLite column types to their corresponding
 /// GraphQL scalar types according to the following mapping:
 /// - `TEXT` → `String`
-/// - `INTEGER` → `Int`
-/// - `REAL`/`FLOAT` → `Float`
+/// - `INTEGER` → `Int`, `BIGINT` → `BigInt`
+/// - `REAL`/`FLOAT`/`NUMERIC` → `Float`
 /// - `BOOLEAN` → `Boolean`
-/// - `BLOB` → `String` (as base64 encoded string)
-/// - Custom types → `String` (fallback)
+/// - `BLOB` → `Base64`
+/// - `JSON` → `JSON`
+/// - `DATETIME`/`DATE` → `DateTime`/`Date`
+/// - Other types → by SQLite type affinity (see [`ColDataType::from_declared_type`])
 pub trait ToGraphqlScalarExt {
     /// Converts the implementor to a GraphQL scalar type.
     ///
@@ -276,14 +280,17 @@ pub trait ToGraphqlNode {
 ///
 /// Contains all components needed to register the table's schema:
 /// - `table`: The main node object type representing table records
+/// - `objects`: Supporting object types such as connections and edges
 /// - `queries`: Query objects containing list and view operations
 /// - `mutations`: Mutation field definitions for insert, update, delete
 /// - `inputs`: Input object type definitions for queries and mutations
 /// - `enums`: Enum type definitions for CHECK constraint columns
 pub struct GraphQLObjectOutput {
     pub table: Object,
+    pub objects: Vec<Object>,
     pub queries: Vec<Field>,
     pub mutations: Vec<Field>,
+    pub subscriptions: Vec<SubscriptionField>,
     pub inputs: Vec<InputObject>,
     pub enums: Vec<Enum>,
 }
@@ -323,6 +330,7 @@ pub trait ToGraphqlObject {
     /// - `table`: The main table node object type
     /// - `queries`: Query objects containing list and view operations to be added to Query type
     /// - `mutations`: All mutation field definitions to be added to the Mutation type
+    /// - `subscriptions`: Change subscription fields to be added to the Subscription type
     /// - `inputs`: All input object type definitions to be registered with the schema
     /// - `enums`: All enum type definitions to be registered with the schema
     fn to_object(&self) -> async_graphql::Result<GraphQLObjectOutput>;
@@ -347,3 +355,30 @@ pub trait ToSimpleExpr {
     /// if the conversion fails due to type mismatch or invalid value format.
     fn to_simple_expr(self, data_type: &ColDataType) -> async_graphql::Result<SimpleExpr>;
 }
+
+/// Converts `where` filter arguments to sea-query conditions.
+///
+/// The filter object maps camelCased column names to per-column operator inputs
+/// (`eq`, `in`, `contains`, ...) and supports nesting through the `and`, `or` and
+/// `not` combinators. All entries at the same level are combined with `AND`.
+pub trait ToCondition {
+    /// Converts the implementor to a sea-query `Condition`.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - The table the filter applies to, used to resolve column names and types
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the generated `Condition` on success, or an `async_graphql::Error`
+    /// if the filter references an unknown column or operator.
+    fn to_condition(self, table: &TableDef) -> async_graphql::Result<Condition>;
+}
+
+/// Sets an optional description on a GraphQL type or field.
+///
+/// An empty description is still rendered in the SDL, so nothing is set for `None`.
+pub trait WithDescription: Sized {
+    /// Returns the implementor with the description set, if there is one.
+    fn with_description(self, description: Option<String>) -> Self;
+}
@@ -0,0 +1,356 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use async_graphql::{
+    Request, Response, ServerError, ServerResult, Value,
+    dataloader::DataLoader,
+    dynamic::ResolverContext,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextPrepareRequest},
+    parser::types::{DocumentOperations, OperationDefinition, OperationType},
+};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction, pool::PoolConnection};
+use tokio::sync::{Mutex, OwnedMappedMutexGuard, OwnedMutexGuard};
+use tracing::{debug, warn};
+
+use crate::{
+    auth::Claims,
+    changes::{ChangeBus, ChangeWatch},
+    functions::{RequestInfo, RequestScope},
+    loader::{ColumnRowLoader, RelationLoader},
+    utils::selected_operation,
+};
+
+/// Operation directive running every root field of a mutation in one transaction.
+pub const TRANSACTION_DIRECTIVE: &str = "transaction";
+
+/// Operation directive running a mutation in a transaction that is always rolled back.
+pub const DRY_RUN_DIRECTIVE: &str = "dryRun";
+
+type SharedTransaction = Arc<Mutex<Option<Transaction<'static, Sqlite>>>>;
+
+/// Where resolvers and loaders run their statements.
+///
+/// Outside of transactional mutations every statement gets a connection of the pool.
+/// Transactional mutations share a single transaction between all of their root
+/// fields and the loaders resolving the returned nodes.
+#[derive(Clone)]
+pub enum Database {
+    Pool(SqlitePool),
+    Transaction(SharedTransaction),
+}
+
+impl Database {
+    /// Returns the database of the current request, falling back to the pool of the schema.
+    pub fn from_context<'a>(ctx: &ResolverContext<'a>) -> async_graphql::Result<Self> {
+        match ctx.data_opt::<Database>() {
+            Some(db) => Ok(db.clone()),
+            None => Ok(Database::Pool(ctx.data::<SqlitePool>()?.clone())),
+        }
+    }
+
+    /// Acquires a connection, waiting for the shared transaction to be released by
+    /// other resolvers.
+    pub async fn acquire(&self) -> sqlx::Result<DbConnection> {
+        match self {
+            Database::Pool(pool) => Ok(DbConnection::Pool(pool.acquire().await?, None, None)),
+            Database::Transaction(transaction) => {
+                let guard = transaction.clone().lock_owned().await;
+
+                OwnedMutexGuard::try_map(guard, |transaction| transaction.as_deref_mut())
+                    .map(|conn| DbConnection::Transaction(conn, None))
+                    .map_err(|_| sqlx::Error::PoolClosed)
+            }
+        }
+    }
+}
+
+impl From<SqlitePool> for Database {
+    fn from(value: SqlitePool) -> Self {
+        Database::Pool(value)
+    }
+}
+
+/// A connection of the pool, or exclusive access to the transaction of the request.
+pub enum DbConnection {
+    Pool(
+        PoolConnection<Sqlite>,
+        Option<ChangeWatch>,
+        Option<RequestScope>,
+    ),
+    Transaction(
+        OwnedMappedMutexGuard<Option<Transaction<'static, Sqlite>>, SqliteConnection>,
+        Option<RequestScope>,
+    ),
+}
+
+impl DbConnection {
+    /// Publishes the changes committed through this connection once it is released.
+    /// Changes of the shared transaction are published when it commits instead.
+    pub async fn watch(&mut self, changes: &ChangeBus) -> sqlx::Result<()> {
+        if let DbConnection::Pool(conn, watch, _) = self {
+            *watch = Some(changes.watch(conn).await?);
+        }
+
+        Ok(())
+    }
+
+    /// Exposes the request to the SQL functions until the connection is released, see
+    /// [`RequestScope`].
+    pub async fn enter_request(&mut self, request: Option<&RequestInfo>) -> sqlx::Result<()> {
+        let (conn, scope): (&mut SqliteConnection, _) = match self {
+            DbConnection::Pool(conn, _, scope) => (conn, scope),
+            DbConnection::Transaction(conn, scope) => (conn, scope),
+        };
+
+        *scope = Some(RequestScope::enter(conn, request).await?);
+
+        Ok(())
+    }
+}
+
+impl Drop for DbConnection {
+    fn drop(&mut self) {
+        // leave the request before the connection returns to the pool, where another
+        // request may acquire it
+        match self {
+            DbConnection::Pool(_, watch, scope) => {
+                scope.take();
+                watch.take();
+            }
+            DbConnection::Transaction(_, scope) => {
+                scope.take();
+            }
+        }
+    }
+}
+
+impl Deref for DbConnection {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            DbConnection::Pool(conn, ..) => conn,
+            DbConnection::Transaction(conn, _) => conn,
+        }
+    }
+}
+
+impl DerefMut for DbConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            DbConnection::Pool(conn, ..) => conn,
+            DbConnection::Transaction(conn, _) => conn,
+        }
+    }
+}
+
+/// How the root fields of a mutation operation are executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionMode {
+    /// Committed when every field succeeds, rolled back otherwise
+    Commit,
+    /// Always rolled back, the response previews the results
+    DryRun,
+}
+
+impl TransactionMode {
+    /// Mode requested by the directives of an operation, removing them since they
+    /// are not part of the schema.
+    fn take_from(operation: &mut OperationDefinition, always: bool) -> Option<Self> {
+        if operation.ty != OperationType::Mutation {
+            return None;
+        }
+
+        let mut mode = always.then_some(TransactionMode::Commit);
+
+        operation.directives.retain(|directive| {
+            match directive.node.name.node.as_str() {
+                DRY_RUN_DIRECTIVE => mode = Some(TransactionMode::DryRun),
+                TRANSACTION_DIRECTIVE => {
+                    mode = mode.or(Some(TransactionMode::Commit));
+                }
+                _ => return true,
+            }
+
+            false
+        });
+
+        mode
+    }
+}
+
+/// Extension running mutation operations in a single transaction.
+///
+/// Operations opt in with the `@transaction` directive, or `@dryRun` to always roll
+/// back, e.g. `mutation @dryRun { ... }`. With `always` every mutation operation
+/// is transactional.
+///
+/// Every request also gets its [`RequestInfo`] and the loaders reading in its scope.
+pub struct Transactions {
+    pool: SqlitePool,
+    always: bool,
+}
+
+impl Transactions {
+    pub fn new(pool: SqlitePool, always: bool) -> Self {
+        Self { pool, always }
+    }
+}
+
+impl ExtensionFactory for Transactions {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(TransactionsExtension {
+            pool: self.pool.clone(),
+            always: self.always,
+            state: Default::default(),
+        })
+    }
+}
+
+struct TransactionsExtension {
+    pool: SqlitePool,
+    always: bool,
+    state: std::sync::Mutex<Option<(TransactionMode, SharedTransaction, Option<ChangeWatch>)>>,
+}
+
+/// Adds the loaders of a request, reading through `db` in the scope of the request.
+fn with_loaders(request: Request, db: Database, info: RequestInfo) -> Request {
+    request
+        .data(DataLoader::new(
+            ColumnRowLoader {
+                db: db.clone(),
+                request: info.clone(),
+            },
+            tokio::spawn,
+        ))
+        .data(DataLoader::new(
+            RelationLoader {
+                db,
+                request: info.clone(),
+            },
+            tokio::spawn,
+        ))
+        .data(info)
+}
+
+#[async_graphql::async_trait::async_trait]
+impl Extension for TransactionsExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        // invalid documents are reported by the regular parsing
+        let Ok(document) = request.parsed_query() else {
+            return next.run(ctx, request).await;
+        };
+
+        let mut document = document.clone();
+
+        // requests with a single operation don't need to name it
+        let operation_name = match &document.operations {
+            DocumentOperations::Multiple(operations) if operations.len() == 1 => {
+                operations.keys().next().map(|name| name.to_string())
+            }
+            DocumentOperations::Multiple(_) => request.operation_name.clone(),
+            DocumentOperations::Single(_) => None,
+        };
+
+        let operation = selected_operation(&mut document, request.operation_name.as_deref());
+
+        // claims of subscriptions are part of the connection rather than the request
+        let info = RequestInfo {
+            claims: Claims::from_data(&request.data)
+                .or_else(|| ctx.data_opt::<Claims>())
+                .cloned()
+                .unwrap_or_default(),
+            operation_name,
+            operation_type: operation.as_ref().map(|operation| operation.ty),
+        };
+
+        let Some(mode) =
+            operation.and_then(|operation| TransactionMode::take_from(operation, self.always))
+        else {
+            // statements of every request run in its scope, see `RequestScope`
+            let db = Database::Pool(self.pool.clone());
+
+            return next.run(ctx, with_loaders(request, db, info)).await;
+        };
+
+        debug!("Running mutation in a transaction: {:?}", mode);
+
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| ServerError::new(e.to_string(), None))?;
+
+        // writes are published to subscriptions once the transaction commits
+        let watch = match ctx.data_opt::<ChangeBus>() {
+            Some(changes) => Some(
+                changes
+                    .watch(&mut transaction)
+                    .await
+                    .map_err(|e| ServerError::new(e.to_string(), None))?,
+            ),
+            None => None,
+        };
+
+        let transaction: SharedTransaction = Arc::new(Mutex::new(Some(transaction)));
+        let db = Database::Transaction(transaction.clone());
+
+        // loaders read through the transaction, to see the uncommitted rows
+        request = with_loaders(request, db.clone(), info).data(db);
+
+        request.set_parsed_query(document);
+
+        *self.state.lock().unwrap() = Some((mode, transaction, watch));
+
+        next.run(ctx, request).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let mut response = next.run(ctx, operation_name).await;
+
+        let Some((mode, transaction, watch)) = self.state.lock().unwrap().take() else {
+            return response;
+        };
+
+        let Some(transaction) = transaction.lock().await.take() else {
+            return response;
+        };
+
+        let result = if mode == TransactionMode::DryRun || response.is_err() {
+            debug!("Rolling back mutation transaction");
+
+            // nothing was written, so no partial results are returned either
+            if mode == TransactionMode::Commit {
+                response.data = Value::Null;
+            }
+
+            transaction.rollback().await
+        } else {
+            debug!("Committing mutation transaction");
+            transaction.commit().await
+        };
+
+        // publishes the committed writes
+        drop(watch);
+
+        if let Err(e) = result {
+            warn!("Failed to finish mutation transaction: {}", e);
+            response.data = Value::Null;
+            response.errors.push(ServerError::new(e.to_string(), None));
+        }
+
+        response
+    }
+}
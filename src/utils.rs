@@ -4,13 +4,31 @@
 //! for common operations like foreign key detection, string manipulation, and
 //! primary key identification.
 
+use std::collections::HashMap;
+
 use anyhow::anyhow;
-use async_graphql::dynamic::{InputObject, InputValue, TypeRef, ValueAccessor};
-use sea_query::SimpleExpr;
-use sqlparser::ast::{ColumnDef, ColumnOption, CreateTable, TableConstraint};
+use async_graphql::{
+    Name,
+    dynamic::{Field, InputValue, Object, ValueAccessor},
+    parser::types::{Directive, DocumentOperations, ExecutableDocument, OperationDefinition},
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use sea_query::{Condition, Expr, Keyword, SimpleExpr};
+use sqlparser::{
+    ast::{ColumnDef, ColumnOption, CreateTable, Statement, TableConstraint},
+    dialect::SQLiteDialect,
+    parser::Parser,
+    tokenizer::{Token, TokenWithSpan, Tokenizer, Whitespace},
+};
+use sqlx::{Row, TypeInfo, ValueRef, sqlite::SqliteRow};
+use stringcase::Caser;
 use tracing::{debug, instrument, warn};
 
-use crate::{parser::ColDataType, traits::ToSimpleExpr};
+use crate::{
+    parser::{ColDataType, TableDef},
+    resolvers::{DynamicFilterCondition, FilterOperator},
+    traits::{ToCondition, ToSimpleExpr, WithDescription},
+};
 
 /// Strips the "_id" suffix from a column name if present.
 ///
@@ -25,6 +43,7 @@ use crate::{parser::ColDataType, traits::ToSimpleExpr};
 ///
 /// # Examples
 /// ```
+/// # use graph_sql::utils::strip_id_suffix;
 /// assert_eq!(strip_id_suffix("user_id"), "user");
 /// assert_eq!(strip_id_suffix("email"), "email");
 /// assert_eq!(strip_id_suffix("category_id"), "category");
@@ -52,7 +71,7 @@ pub fn strip_id_suffix(name: &str) -> String {
 /// - Primary key constraint exists but references non-existent columns
 ///
 /// # Examples
-/// ```
+/// ```ignore
 /// match find_primary_key_column(&table_def) {
 ///     Ok(pk_col) => println!("Primary key: {}", pk_col.name),
 ///     Err(e) => eprintln!("No primary key found: {}", e),
@@ -139,6 +158,7 @@ pub fn find_primary_key_column(table: &CreateTable) -> anyhow::Result<&ColumnDef
 ///
 /// # Examples
 /// ```
+/// # use graph_sql::utils::is_valid_graphql_identifier;
 /// assert!(is_valid_graphql_identifier("user"));
 /// assert!(is_valid_graphql_identifier("user_id"));
 /// assert!(is_valid_graphql_identifier("_internal"));
@@ -163,6 +183,315 @@ pub fn is_valid_graphql_identifier(name: &str) -> bool {
     chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
+/// Extracts the allowed values of `CHECK (col IN ('a', 'b', ...))` constraints from a
+/// `CREATE TABLE` statement, keyed by column name.
+///
+/// Both column and table constraints are supported. Only lists of strings that are all
+/// valid GraphQL enum values are returned, other constraints are ignored.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::check_constraint_enums;
+/// let enums = check_constraint_enums(
+///     "CREATE TABLE address (type TEXT CHECK (type IN ('billing', 'shipping')))",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(enums["type"], vec!["billing", "shipping"]);
+/// ```
+pub fn check_constraint_enums(sql: &str) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let statements = Parser::parse_sql(&SQLiteDialect {}, sql)?;
+
+    let mut enums = HashMap::new();
+
+    for statement in statements {
+        let Statement::CreateTable(table) = statement else {
+            continue;
+        };
+
+        let column_checks = table.columns.iter().flat_map(|col| {
+            col.options.iter().filter_map(|opt| match &opt.option {
+                ColumnOption::Check(expr) => Some(expr),
+                _ => None,
+            })
+        });
+
+        let table_checks = table
+            .constraints
+            .iter()
+            .filter_map(|constraint| match constraint {
+                TableConstraint::Check { expr, .. } => Some(expr.as_ref()),
+                _ => None,
+            });
+
+        for expr in column_checks.chain(table_checks) {
+            if let Some((column, values)) = in_list_values(expr) {
+                enums.entry(column).or_insert(values);
+            }
+        }
+    }
+
+    Ok(enums)
+}
+
+/// Matches `col IN ('a', 'b', ...)`, returning the column and the listed values.
+fn in_list_values(expr: &sqlparser::ast::Expr) -> Option<(String, Vec<String>)> {
+    match expr {
+        sqlparser::ast::Expr::Nested(expr) => in_list_values(expr),
+        sqlparser::ast::Expr::InList {
+            expr,
+            list,
+            negated: false,
+        } => {
+            let sqlparser::ast::Expr::Identifier(column) = expr.as_ref() else {
+                return None;
+            };
+
+            let values = list
+                .iter()
+                .map(|item| match item {
+                    sqlparser::ast::Expr::Value(value) => match &value.value {
+                        sqlparser::ast::Value::SingleQuotedString(value) => Some(value.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            // enum values must be names, and can't be `true`, `false` or `null`
+            let valid = !values.is_empty()
+                && values.iter().all(|value| {
+                    is_valid_graphql_identifier(value)
+                        && !matches!(value.as_str(), "true" | "false" | "null")
+                });
+
+            if !valid {
+                debug!(
+                    "Skipping CHECK constraint on '{}', values are not valid enum values",
+                    column.value
+                );
+                return None;
+            }
+
+            Some((column.value.clone(), values))
+        }
+        _ => None,
+    }
+}
+
+/// Descriptions of a table and its columns taken from the comments of its SQL.
+#[derive(Debug, Default, PartialEq)]
+pub struct SqlComments {
+    /// Comments placed before the column list or on the line of its opening parenthesis
+    pub table: Option<String>,
+    /// Comments attached to each column, by column name
+    pub columns: HashMap<String, String>,
+}
+
+/// Extracts the `--` and `/* */` comments of a `CREATE TABLE` or `CREATE VIEW` statement.
+///
+/// A comment on the same line as a column definition describes that column, and
+/// comments on their own lines describe the column that follows them. Comments
+/// between the table name and its column list, or right after the opening
+/// parenthesis, describe the table. Only the header comments of a view are used.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::sql_comments;
+/// let comments = sql_comments(
+///     "CREATE TABLE post ( -- Blog posts
+///         id INTEGER PRIMARY KEY,
+///         -- Headline shown in listings
+///         title TEXT NOT NULL,
+///         body TEXT -- Markdown content
+///     )",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(comments.table.as_deref(), Some("Blog posts"));
+/// assert_eq!(comments.columns["title"], "Headline shown in listings");
+/// assert_eq!(comments.columns["body"], "Markdown content");
+/// ```
+pub fn sql_comments(sql: &str) -> anyhow::Result<SqlComments> {
+    // who the comments found at the current position belong to
+    enum Owner {
+        Table,
+        Column(String),
+        Constraint,
+    }
+
+    let tokens = Tokenizer::new(&SQLiteDialect {}, sql).tokenize_with_location()?;
+
+    let mut table = Vec::new();
+    let mut columns: HashMap<String, Vec<String>> = HashMap::new();
+    let mut pending = Vec::new();
+
+    let mut owner = Owner::Table;
+    let mut in_header = true;
+    let mut is_view = false;
+    let mut depth = 0;
+    let mut expect_item = false;
+    let mut last_line = 0;
+
+    for TokenWithSpan { token, span } in tokens {
+        let comment = match &token {
+            Token::Whitespace(Whitespace::SingleLineComment { comment, .. })
+            | Token::Whitespace(Whitespace::MultiLineComment(comment)) => comment.trim(),
+            Token::Whitespace(_) => continue,
+            _ => {
+                match &token {
+                    Token::Word(word)
+                        if in_header && word.keyword == sqlparser::keywords::Keyword::VIEW =>
+                    {
+                        is_view = true;
+                    }
+                    Token::Word(word)
+                        if in_header
+                            && is_view
+                            && word.keyword == sqlparser::keywords::Keyword::AS =>
+                    {
+                        in_header = false;
+                    }
+                    Token::LParen if in_header => {
+                        in_header = false;
+                        depth = 1;
+                        expect_item = true;
+                    }
+                    _ if in_header || is_view => {}
+                    Token::LParen => depth += 1,
+                    Token::RParen => {
+                        depth -= 1;
+
+                        if depth == 0 {
+                            // comments after the last column don't describe anything
+                            pending.clear();
+                            owner = Owner::Table;
+                        }
+                    }
+                    Token::Comma if depth == 1 => expect_item = true,
+                    _ if expect_item && depth == 1 => {
+                        expect_item = false;
+
+                        owner = match &token {
+                            Token::Word(word)
+                                if word.quote_style.is_some()
+                                    || !matches!(
+                                        word.keyword,
+                                        sqlparser::keywords::Keyword::CONSTRAINT
+                                            | sqlparser::keywords::Keyword::PRIMARY
+                                            | sqlparser::keywords::Keyword::FOREIGN
+                                            | sqlparser::keywords::Keyword::UNIQUE
+                                            | sqlparser::keywords::Keyword::CHECK
+                                    ) =>
+                            {
+                                Owner::Column(word.value.clone())
+                            }
+                            _ => Owner::Constraint,
+                        };
+
+                        // comments on their own lines describe the item after them
+                        if let Owner::Column(name) = &owner {
+                            columns
+                                .entry(name.clone())
+                                .or_default()
+                                .append(&mut pending);
+                        }
+
+                        pending.clear();
+                    }
+                    _ => {}
+                }
+
+                last_line = span.end.line;
+                continue;
+            }
+        };
+
+        if comment.is_empty() {
+            continue;
+        }
+
+        if in_header {
+            table.push(comment.to_string());
+        } else if is_view {
+            continue;
+        } else if span.start.line == last_line {
+            match &owner {
+                Owner::Table => table.push(comment.to_string()),
+                Owner::Column(name) => columns
+                    .entry(name.clone())
+                    .or_default()
+                    .push(comment.to_string()),
+                Owner::Constraint => {}
+            }
+        } else if depth > 0 {
+            pending.push(comment.to_string());
+        }
+    }
+
+    Ok(SqlComments {
+        table: (!table.is_empty()).then(|| table.join("\n")),
+        columns: columns
+            .into_iter()
+            .filter(|(_, comments)| !comments.is_empty())
+            .map(|(name, comments)| (name, comments.join("\n")))
+            .collect(),
+    })
+}
+
+/// Replaces the `$claims.<name>` references of a SQL expression with `?` placeholders.
+///
+/// Returns the rewritten expression together with the referenced claim names, in the
+/// order of their placeholders. The expression must be a single SQL expression.
+///
+/// # Errors
+/// Returns an error if the expression can't be parsed or references `$claims` without
+/// a claim name.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::claim_placeholders;
+/// let (sql, claims) = claim_placeholders("author_id = $claims.sub OR published").unwrap();
+/// assert_eq!(sql, "author_id = ? OR published");
+/// assert_eq!(claims, vec!["sub"]);
+/// ```
+pub fn claim_placeholders(sql: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let dialect = SQLiteDialect {};
+    let mut tokens = Tokenizer::new(&dialect, sql).tokenize()?.into_iter();
+
+    let mut result = String::with_capacity(sql.len());
+    let mut claims = vec![];
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Placeholder(name) if name == "$claims" => match (tokens.next(), tokens.next()) {
+                (Some(Token::Period), Some(Token::Word(word))) if word.quote_style.is_none() => {
+                    claims.push(word.value);
+                    result.push('?');
+                }
+                _ => return Err(anyhow!("Expected a claim name after $claims in '{}'", sql)),
+            },
+            token => result.push_str(&token.to_string()),
+        }
+    }
+
+    // anything but a single expression would change the statement it is added to
+    let mut parser = Parser::new(&dialect).try_with_sql(&result)?;
+
+    parser
+        .parse_expr()
+        .map_err(|e| anyhow!("Invalid expression '{}': {}", sql, e))?;
+
+    if parser.peek_token().token != Token::EOF {
+        return Err(anyhow!(
+            "Invalid expression '{}': expected a single expression",
+            sql
+        ));
+    }
+
+    Ok((result, claims))
+}
+
 /// Converts a database table/column name to a valid GraphQL identifier.
 ///
 /// This function ensures the resulting name is a valid GraphQL identifier by:
@@ -178,6 +507,7 @@ pub fn is_valid_graphql_identifier(name: &str) -> bool {
 ///
 /// # Examples
 /// ```
+/// # use graph_sql::utils::sanitize_graphql_name;
 /// assert_eq!(sanitize_graphql_name("user-profile"), "user_profile");
 /// assert_eq!(sanitize_graphql_name("123_table"), "_123_table");
 /// assert_eq!(sanitize_graphql_name("valid_name"), "valid_name");
@@ -208,32 +538,555 @@ pub fn sanitize_graphql_name(name: &str) -> String {
     result
 }
 
+impl WithDescription for Field {
+    fn with_description(self, description: Option<String>) -> Self {
+        match description {
+            Some(description) => self.description(description),
+            None => self,
+        }
+    }
+}
+
+impl WithDescription for InputValue {
+    fn with_description(self, description: Option<String>) -> Self {
+        match description {
+            Some(description) => self.description(description),
+            None => self,
+        }
+    }
+}
+
+impl WithDescription for Object {
+    fn with_description(self, description: Option<String>) -> Self {
+        match description {
+            Some(description) => self.description(description),
+            None => self,
+        }
+    }
+}
+
 impl ToSimpleExpr for ValueAccessor<'_> {
     fn to_simple_expr(self, data_type: &ColDataType) -> async_graphql::Result<SimpleExpr> {
         match data_type {
-            ColDataType::String => self.string().map(Into::into),
+            // values of CHECK constraint enums are strings in the database
+            ColDataType::String => self.string().or_else(|_| self.enum_name()).map(Into::into),
             ColDataType::Float => self.f64().map(Into::into),
             ColDataType::Integer => self.i64().map(Into::into),
             ColDataType::Boolean => self.boolean().map(Into::into),
+            ColDataType::BigInt => match self.i64() {
+                Ok(val) => Ok(val.into()),
+                Err(_) => Ok(self
+                    .string()?
+                    .parse::<i64>()
+                    .map_err(|e| anyhow!("Invalid BigInt: {}", e))?
+                    .into()),
+            },
+            ColDataType::Bytes => Ok(BASE64_STANDARD
+                .decode(self.string()?)
+                .map_err(|e| anyhow!("Invalid base64: {}", e))?
+                .into()),
+            // JSON documents are stored as text
+            ColDataType::Json => Ok(self.as_value().clone().into_json()?.to_string().into()),
+            ColDataType::DateTime => match self.string()? {
+                val if is_valid_datetime(val) => Ok(val.into()),
+                val => Err(anyhow!("Invalid DateTime '{}'", val).into()),
+            },
+            ColDataType::Date => match self.string()? {
+                val if is_valid_date(val) => Ok(val.into()),
+                val => Err(anyhow!("Invalid Date '{}'", val).into()),
+            },
         }
     }
 }
 
-pub struct StringFilter {
-    pub eq: Option<String>,
-    pub ne: Option<String>,
-    pub contains: Option<String>,
-    pub r#in: Option<String>,
+impl ToSimpleExpr for serde_json::Value {
+    fn to_simple_expr(self, data_type: &ColDataType) -> async_graphql::Result<SimpleExpr> {
+        if self.is_null() {
+            return Ok(SimpleExpr::Keyword(Keyword::Null));
+        }
+
+        let expr = match data_type {
+            // columns without a declared type keep whatever SQLite stored
+            ColDataType::String => Some(SimpleExpr::Value(json_to_sql_value(&self))),
+            ColDataType::Float => self.as_f64().map(Into::into),
+            ColDataType::Integer => self.as_i64().map(Into::into),
+            ColDataType::BigInt => self
+                .as_i64()
+                .or_else(|| self.as_str().and_then(|val| val.parse().ok()))
+                .map(Into::into),
+            // blobs are read back as hex, see `json_value_expr`
+            ColDataType::Bytes => self.as_str().and_then(decode_hex).map(Into::into),
+            ColDataType::Json => Some(match &self {
+                serde_json::Value::String(val) => val.clone().into(),
+                val => val.to_string().into(),
+            }),
+            ColDataType::DateTime | ColDataType::Date => {
+                self.as_str().map(|val| val.to_string().into())
+            }
+            // SQLite stores booleans as 0/1 integers
+            ColDataType::Boolean => self
+                .as_bool()
+                .or_else(|| self.as_i64().map(|val| val != 0))
+                .map(Into::into),
+        };
+
+        Ok(expr.ok_or(anyhow!("Unable to convert {} to {:?}", self, data_type))?)
+    }
+}
+
+impl ToCondition for ValueAccessor<'_> {
+    fn to_condition(self, table: &TableDef) -> async_graphql::Result<Condition> {
+        let mut condition = Condition::all();
+
+        for (key, val) in self.object()?.iter() {
+            if val.is_null() {
+                continue;
+            }
+
+            match key.as_str() {
+                "and" => {
+                    for item in val.list()?.iter() {
+                        condition = condition.add(item.to_condition(table)?);
+                    }
+                }
+                "or" => {
+                    let mut any = Condition::any();
+
+                    for item in val.list()?.iter() {
+                        any = any.add(item.to_condition(table)?);
+                    }
+
+                    condition = condition.add(any);
+                }
+                "not" => {
+                    condition = condition.add(val.to_condition(table)?.not());
+                }
+                field => {
+                    let col = table
+                        .columns
+                        .iter()
+                        .find(|col| col.name.to_camel_case() == field)
+                        .ok_or(anyhow!("Unknown filter field '{}'", field))?;
+
+                    for (op_name, op_val) in val.object()?.iter() {
+                        if op_val.is_null() {
+                            continue;
+                        }
+
+                        let op = FilterOperator::parse(op_name.as_str(), op_val, &col.data_type)?;
+
+                        // JSON documents are compared in their minified form
+                        let filter = match col.data_type {
+                            ColDataType::Json => DynamicFilterCondition::with_expr(
+                                Expr::cust(json_value_expr(&col.name, &col.data_type)),
+                                op,
+                            ),
+                            _ => DynamicFilterCondition::new(col.name.clone(), op),
+                        };
+
+                        condition = condition.add(SimpleExpr::from(filter));
+                    }
+                }
+            }
+        }
+
+        Ok(condition)
+    }
+}
+
+/// Escapes the `LIKE` wildcard characters in a user supplied string.
+///
+/// The result is meant to be used together with `ESCAPE '\'` so that `%` and `_`
+/// are matched literally by `contains` and `startsWith` filters.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::escape_like;
+/// assert_eq!(escape_like("100%"), "100\\%");
+/// assert_eq!(escape_like("snake_case"), "snake\\_case");
+/// ```
+pub fn escape_like(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+
+    result
+}
+
+/// Encodes the sort key values of a row into an opaque pagination cursor.
+///
+/// Each key is stored together with its column name so that a cursor produced
+/// for one `orderBy` cannot be silently reused with a different one.
+///
+/// # Arguments
+/// * `keys` - The `(column, value)` pairs of the sort keys, in sort order
+///
+/// # Returns
+/// A base64 encoded cursor string
+pub fn encode_cursor(keys: &[(String, serde_json::Value)]) -> String {
+    BASE64_STANDARD.encode(
+        serde_json::Value::from(
+            keys.iter()
+                .map(|(name, value)| serde_json::json!([name, value]))
+                .collect::<Vec<_>>(),
+        )
+        .to_string(),
+    )
+}
+
+/// Decodes a cursor produced by [`encode_cursor`].
+///
+/// # Errors
+/// Returns an error if the cursor is not valid base64 or does not contain
+/// a list of `[column, value]` pairs.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::{decode_cursor, encode_cursor};
+/// let keys = vec![("id".to_string(), serde_json::json!(42))];
+/// assert_eq!(decode_cursor(&encode_cursor(&keys)).unwrap(), keys);
+/// ```
+pub fn decode_cursor(cursor: &str) -> anyhow::Result<Vec<(String, serde_json::Value)>> {
+    let bytes = BASE64_STANDARD
+        .decode(cursor)
+        .map_err(|e| anyhow!("Invalid cursor: {}", e))?;
+
+    let keys = serde_json::from_slice::<Vec<(String, serde_json::Value)>>(&bytes)
+        .map_err(|e| anyhow!("Invalid cursor: {}", e))?;
+
+    Ok(keys)
+}
+
+/// Converts a JSON value read back from SQLite into the matching SQL value.
+///
+/// Unlike [`ToSimpleExpr`] no column type is needed, the JSON type already reflects
+/// the storage class of the value.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::json_to_sql_value;
+/// assert_eq!(json_to_sql_value(&serde_json::json!("MATH")), "MATH".into());
+/// assert_eq!(json_to_sql_value(&serde_json::json!(2)), 2i64.into());
+/// ```
+pub fn json_to_sql_value(value: &serde_json::Value) -> sea_query::Value {
+    match value {
+        serde_json::Value::Null => sea_query::Value::String(None),
+        serde_json::Value::Bool(val) => (*val).into(),
+        serde_json::Value::Number(val) => match val.as_i64() {
+            Some(val) => val.into(),
+            None => val.as_f64().unwrap_or_default().into(),
+        },
+        serde_json::Value::String(val) => val.clone().into(),
+        other => other.to_string().into(),
+    }
+}
+
+/// Reads a column of a row as the value SQLite stores, whatever its declared type.
+pub fn stored_value(row: &SqliteRow, index: usize) -> sqlx::Result<sea_query::Value> {
+    let value = row.try_get_raw(index)?;
+
+    if value.is_null() {
+        return Ok(sea_query::Value::String(None));
+    }
+
+    // the type of a value is its storage class, the declared type is not involved
+    Ok(match value.type_info().name() {
+        "INTEGER" => row.try_get_unchecked::<i64, _>(index)?.into(),
+        "REAL" => row.try_get_unchecked::<f64, _>(index)?.into(),
+        "BLOB" => row.try_get_unchecked::<Vec<u8>, _>(index)?.into(),
+        _ => row.try_get_unchecked::<String, _>(index)?.into(),
+    })
+}
+
+/// Builds the SQL expression of a value read with [`stored_value`], with the affinity of
+/// the column of the given declared type.
+///
+/// Comparisons then convert the other operand like the column would, e.g. a `'7'` text
+/// matches a `7` stored in an `INTEGER` column. Values of another storage class than the
+/// affinity's are kept as they are, since converting them would change them.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::stored_value_expr;
+/// # use sea_query::{Query, SqliteQueryBuilder};
+/// let select = |expr| Query::select().expr(expr).to_string(SqliteQueryBuilder);
+///
+/// assert_eq!(select(stored_value_expr(7i64.into(), "INTEGER")), "SELECT CAST(7 AS NUMERIC)");
+/// assert_eq!(select(stored_value_expr("a".into(), "VARCHAR(8)")), "SELECT CAST('a' AS TEXT)");
+/// assert_eq!(select(stored_value_expr("a".into(), "INTEGER")), "SELECT 'a'");
+/// ```
+pub fn stored_value_expr(value: sea_query::Value, declared_type: &str) -> SimpleExpr {
+    use sea_query::Value;
+
+    let declared_type = declared_type.to_uppercase();
+    let contains = |names: &[&str]| names.iter().any(|name| declared_type.contains(name));
+
+    // https://www.sqlite.org/datatype3.html#determination_of_column_affinity, with INTEGER
+    // cast as NUMERIC since casting to INTEGER truncates reals
+    let affinity = if contains(&["INT"]) {
+        "NUMERIC"
+    } else if contains(&["CHAR", "CLOB", "TEXT"]) {
+        "TEXT"
+    } else if contains(&["BLOB"]) || declared_type.is_empty() {
+        "BLOB"
+    } else if contains(&["REAL", "FLOA", "DOUB"]) {
+        "REAL"
+    } else {
+        "NUMERIC"
+    };
+
+    let cast = match (&value, affinity) {
+        (Value::BigInt(Some(_)) | Value::Double(Some(_)), "NUMERIC" | "REAL") => Some(affinity),
+        (Value::String(Some(_)), "TEXT") => Some(affinity),
+        _ => None,
+    };
+
+    match cast {
+        Some(affinity) => Expr::cust_with_values(format!("CAST(? AS {})", affinity), [value]),
+        None => Expr::val(value).into(),
+    }
+}
+
+/// Builds the SQL expression collecting the values of the given columns into a JSON array.
+///
+/// Used to select (composite) primary keys as a single value.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::json_array_expr;
+/// assert_eq!(json_array_expr(&["post_id", "tag_id"]), "json_array(\"post_id\", \"tag_id\")");
+/// ```
+pub fn json_array_expr<S: AsRef<str>>(columns: &[S]) -> String {
+    format!(
+        "json_array({})",
+        columns
+            .iter()
+            .map(|col| format!("\"{}\"", col.as_ref()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Builds the SQL expression collecting the columns of a row into a JSON object keyed by
+/// column name, each selected with [`json_value_expr`].
+///
+/// # Examples
+/// ```
+/// # use graph_sql::{parser::ColDataType, utils::json_row_expr};
+/// assert_eq!(
+///     json_row_expr(&[("id", &ColDataType::Integer), ("title", &ColDataType::String)]),
+///     "json_object('id', \"id\", 'title', \"title\")"
+/// );
+/// ```
+pub fn json_row_expr(columns: &[(&str, &ColDataType)]) -> String {
+    format!(
+        "json_object({})",
+        columns
+            .iter()
+            .map(|(name, data_type)| format!(
+                "'{}', {}",
+                name.replace('\'', "''"),
+                json_value_expr(name, data_type)
+            ))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Builds the SQL expression selecting a column as a value that can be embedded in JSON.
+///
+/// Blobs cannot be stored in JSON, so they are selected as hex and converted to base64
+/// by [`ColDataType::to_graphql_value`]. JSON columns are embedded as documents rather
+/// than strings when they hold valid JSON.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::{parser::ColDataType, utils::json_value_expr};
+/// assert_eq!(json_value_expr("title", &ColDataType::String), "\"title\"");
+/// assert_eq!(
+///     json_value_expr("avatar", &ColDataType::Bytes),
+///     "iif(\"avatar\" IS NULL, NULL, hex(\"avatar\"))"
+/// );
+/// ```
+pub fn json_value_expr(column: &str, data_type: &ColDataType) -> String {
+    match data_type {
+        ColDataType::Bytes => format!("iif(\"{0}\" IS NULL, NULL, hex(\"{0}\"))", column),
+        ColDataType::Json => format!("iif(json_valid(\"{0}\"), json(\"{0}\"), \"{0}\")", column),
+        _ => format!("\"{}\"", column),
+    }
+}
+
+/// Decodes a hex string as returned by SQLite's `hex()` function.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::decode_hex;
+/// assert_eq!(decode_hex("CAFE"), Some(vec![0xca, 0xfe]));
+/// assert_eq!(decode_hex("CAF"), None);
+/// ```
+pub fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(value.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses a fixed-width run of ASCII digits.
+fn parse_digits(value: &str, width: usize) -> Option<u32> {
+    if value.len() != width || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+/// Checks that a string is a valid calendar date in `YYYY-MM-DD` format.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::is_valid_date;
+/// assert!(is_valid_date("2024-02-29"));
+/// assert!(!is_valid_date("2023-02-29"));
+/// ```
+pub fn is_valid_date(value: &str) -> bool {
+    let mut parts = value.split('-');
+
+    let (Some(year), Some(month), Some(day), None) = (
+        parts.next().and_then(|year| parse_digits(year, 4)),
+        parts.next().and_then(|month| parse_digits(month, 2)),
+        parts.next().and_then(|day| parse_digits(day, 2)),
+        parts.next(),
+    ) else {
+        return false;
+    };
+
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+
+    (1..=days).contains(&day)
 }
 
-impl StringFilter {
-    pub fn to_object() -> InputObject {
-        InputObject::new("string_filter")
-            .field(InputValue::new("eq", TypeRef::named(TypeRef::STRING)))
-            .field(InputValue::new("ne", TypeRef::named(TypeRef::STRING)))
-            .field(InputValue::new("contains", TypeRef::named(TypeRef::STRING)))
-            .field(InputValue::new("in", TypeRef::named(TypeRef::STRING)))
+/// Checks that a string is a valid ISO 8601 date and time.
+///
+/// Accepts both SQLite's `YYYY-MM-DD HH:MM:SS` format and RFC 3339 timestamps, with
+/// optional seconds, fractional seconds and timezone (`Z` or `+HH:MM`).
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::is_valid_datetime;
+/// assert!(is_valid_datetime("2024-01-31 12:30:00"));
+/// assert!(is_valid_datetime("2024-01-31T12:30:00.123+02:00"));
+/// assert!(!is_valid_datetime("2024-01-31"));
+/// ```
+pub fn is_valid_datetime(value: &str) -> bool {
+    let Some((date, time)) = value.split_once(['T', ' ']) else {
+        return false;
+    };
+
+    if !is_valid_date(date) {
+        return false;
     }
+
+    let time = match time.strip_suffix('Z') {
+        Some(time) => time,
+        None => match time.rfind(['+', '-']) {
+            Some(index) => {
+                let valid_offset = time[index + 1..]
+                    .split_once(':')
+                    .and_then(|(hours, minutes)| {
+                        Some(parse_digits(hours, 2)? < 24 && parse_digits(minutes, 2)? < 60)
+                    })
+                    .unwrap_or(false);
+
+                if !valid_offset {
+                    return false;
+                }
+
+                &time[..index]
+            }
+            None => time,
+        },
+    };
+
+    let mut parts = time.split(':');
+
+    let (Some(hours), Some(minutes), seconds, None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+
+    let valid_seconds =
+        match seconds.map(|seconds| seconds.split_once('.').unwrap_or((seconds, "0"))) {
+            None => true,
+            Some((seconds, fraction)) => {
+                parse_digits(seconds, 2).is_some_and(|seconds| seconds < 60)
+                    && !fraction.is_empty()
+                    && fraction.bytes().all(|b| b.is_ascii_digit())
+            }
+        };
+
+    parse_digits(hours, 2).is_some_and(|hours| hours < 24)
+        && parse_digits(minutes, 2).is_some_and(|minutes| minutes < 60)
+        && valid_seconds
+}
+
+/// Returns the operation of a document that a request executes.
+///
+/// Documents with several operations need the operation name of the request, unless
+/// they contain a single operation anyway. Returns `None` when no operation matches.
+///
+/// # Examples
+/// ```
+/// # use graph_sql::utils::selected_operation;
+/// let mut document = async_graphql::parser::parse_query("query A { a } query B { b }").unwrap();
+/// assert!(selected_operation(&mut document, Some("B")).is_some());
+/// assert!(selected_operation(&mut document, None).is_none());
+/// ```
+pub fn selected_operation<'a>(
+    document: &'a mut ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<&'a mut OperationDefinition> {
+    let operation = match (&mut document.operations, operation_name) {
+        (DocumentOperations::Single(operation), _) => Some(operation),
+        (DocumentOperations::Multiple(operations), Some(name)) => {
+            operations.get_mut(&Name::new(name))
+        }
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            operations.values_mut().next()
+        }
+        (DocumentOperations::Multiple(_), None) => None,
+    };
+
+    operation.map(|operation| &mut operation.node)
+}
+
+/// Removes a directive from an operation, returning it if it was present.
+///
+/// Used for operation directives handled outside of the schema, such as `@live`.
+pub fn take_directive(operation: &mut OperationDefinition, name: &str) -> Option<Directive> {
+    let index = operation
+        .directives
+        .iter()
+        .position(|directive| directive.node.name.node == name)?;
+
+    Some(operation.directives.remove(index).node)
 }
 
 #[cfg(test)]
@@ -276,4 +1129,153 @@ mod tests {
         assert_eq!(sanitize_graphql_name(""), "_empty");
         assert_eq!(sanitize_graphql_name("user.email"), "user_email");
     }
+
+    #[test]
+    fn test_check_constraint_enums() {
+        let enums = check_constraint_enums(
+            "CREATE TABLE \"order\" (
+                id INTEGER PRIMARY KEY,
+                status TEXT NOT NULL CHECK (status IN ('pending', 'paid')),
+                kind TEXT,
+                size TEXT CHECK (size NOT IN ('xl')),
+                code TEXT CHECK (code IN ('in-progress', 'done')),
+                flag TEXT CHECK (flag IN ('true', 'false')),
+                quantity INTEGER CHECK (quantity IN (1, 2)),
+                CHECK ((kind IN ('retail', 'wholesale')))
+            )",
+        )
+        .unwrap();
+
+        assert_eq!(enums.len(), 2);
+        assert_eq!(enums["status"], vec!["pending", "paid"]);
+        assert_eq!(enums["kind"], vec!["retail", "wholesale"]);
+        assert!(
+            check_constraint_enums("CREATE VIEW v AS SELECT 1")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("plain"), "plain");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("snake_case"), "snake\\_case");
+        assert_eq!(escape_like("back\\slash"), "back\\\\slash");
+    }
+
+    #[test]
+    fn test_is_valid_date() {
+        assert!(is_valid_date("2024-01-31"));
+        assert!(is_valid_date("2000-02-29"));
+        assert!(!is_valid_date("1900-02-29"));
+        assert!(!is_valid_date("2024-04-31"));
+        assert!(!is_valid_date("2024-13-01"));
+        assert!(!is_valid_date("2024-1-01"));
+        assert!(!is_valid_date("2024-01-01T00:00:00"));
+        assert!(!is_valid_date(""));
+    }
+
+    #[test]
+    fn test_is_valid_datetime() {
+        assert!(is_valid_datetime("2024-01-31 12:30"));
+        assert!(is_valid_datetime("2024-01-31 12:30:59"));
+        assert!(is_valid_datetime("2024-01-31T23:59:59.999Z"));
+        assert!(is_valid_datetime("2024-01-31T00:00:00-05:30"));
+        assert!(!is_valid_datetime("2024-01-31"));
+        assert!(!is_valid_datetime("2024-01-31 24:00:00"));
+        assert!(!is_valid_datetime("2024-01-31 12:60"));
+        assert!(!is_valid_datetime("2024-01-31 12:30:00."));
+        assert!(!is_valid_datetime("2024-01-31 12:30:00+0200"));
+        assert!(!is_valid_datetime("2024-02-30 12:30:00"));
+    }
+
+    #[test]
+    fn test_sql_comments() {
+        let comments = sql_comments(
+            "CREATE TABLE /* Registered accounts */ \"user\" (
+                id INTEGER PRIMARY KEY, -- Account id
+                /* Unique login,
+                   case-insensitive */
+                \"email\" TEXT NOT NULL,
+                -- Display name
+                -- shown on posts
+                name TEXT,
+                status TEXT CHECK (status IN ('a', 'b')), /* Account status */
+                -- Ignored, describes a constraint
+                UNIQUE (email, name), -- also ignored
+                bio TEXT -- Short biography
+                -- dangling comment
+            )",
+        )
+        .unwrap();
+
+        assert_eq!(comments.table.as_deref(), Some("Registered accounts"));
+        assert_eq!(comments.columns["id"], "Account id");
+        assert_eq!(
+            comments.columns["email"],
+            "Unique login,\n                   case-insensitive"
+        );
+        assert_eq!(comments.columns["name"], "Display name\nshown on posts");
+        assert_eq!(comments.columns["status"], "Account status");
+        assert_eq!(comments.columns["bio"], "Short biography");
+        assert_eq!(comments.columns.len(), 5);
+
+        let view = sql_comments(
+            "CREATE VIEW summary -- Post summaries
+            AS SELECT id, -- not a column comment
+            count(*) FROM post",
+        )
+        .unwrap();
+
+        assert_eq!(view.table.as_deref(), Some("Post summaries"));
+        assert!(view.columns.is_empty());
+
+        assert_eq!(
+            sql_comments("CREATE TABLE t (id INTEGER)").unwrap(),
+            SqlComments::default()
+        );
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let keys = vec![
+            ("title".to_string(), serde_json::json!("Hello")),
+            ("published_at".to_string(), serde_json::Value::Null),
+            ("id".to_string(), serde_json::json!(7)),
+        ];
+
+        assert_eq!(decode_cursor(&encode_cursor(&keys)).unwrap(), keys);
+        assert!(decode_cursor("not a cursor").is_err());
+        assert!(decode_cursor(&BASE64_STANDARD.encode("{}")).is_err());
+    }
+
+    #[test]
+    fn test_take_directive() {
+        let mut document =
+            async_graphql::parser::parse_query("query Feed @live(throttle: 100) { a }").unwrap();
+        let operation = selected_operation(&mut document, Some("Feed")).unwrap();
+
+        let live = take_directive(operation, "live").unwrap();
+        assert_eq!(live.name.node, "live");
+        assert!(live.get_argument("throttle").is_some());
+
+        assert!(operation.directives.is_empty());
+        assert!(take_directive(operation, "live").is_none());
+    }
+
+    #[test]
+    fn test_claim_placeholders() {
+        let (sql, claims) = claim_placeholders(
+            "owner = $claims.sub AND (team = $claims.team OR '$claims.x' = note)",
+        )
+        .unwrap();
+
+        assert_eq!(sql, "owner = ? AND (team = ? OR '$claims.x' = note)");
+        assert_eq!(claims, vec!["sub", "team"]);
+
+        assert!(claim_placeholders("owner = $claims").is_err());
+        assert!(claim_placeholders("owner = 1; DROP TABLE post").is_err());
+        assert!(claim_placeholders("owner =").is_err());
+    }
 }
@@ -0,0 +1,174 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE orders (
+    id INTEGER PRIMARY KEY,
+    customer TEXT NOT NULL,
+    status TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    total REAL
+);
+
+INSERT INTO orders (id, customer, status, quantity, total) VALUES
+    (1, 'ada', 'paid', 2, 20.0),
+    (2, 'ada', 'paid', 1, 5.5),
+    (3, 'grace', 'open', 4, NULL),
+    (4, 'grace', 'paid', 3, 30.0),
+    (5, 'linus', 'open', 1, 10.0);
+"#;
+
+#[tokio::test]
+async fn counts_every_page_of_a_list() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                orders(page: 2, perPage: 2, where: { status: { eq: "paid" } }) { id }
+                paid: ordersTotalCount(where: { status: { eq: "paid" } })
+                all: ordersTotalCount
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "orders": [{ "id": 4 }], "paid": 3, "all": 5 })
+    );
+}
+
+#[tokio::test]
+async fn counts_only_the_rows_allowed_by_the_select_policy() {
+    let server = TestServer::with_config(
+        SCHEMA,
+        r#"
+        [[policy]]
+        table = "orders"
+        select = "customer = $claims.sub"
+        "#,
+    )
+    .await;
+
+    let data = server
+        .query_as(
+            json!({ "sub": "grace" }),
+            r#"{
+                ordersTotalCount
+                ordersConnection(first: 1) { totalCount }
+                ordersAggregate { count }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "ordersTotalCount": 2,
+            "ordersConnection": { "totalCount": 2 },
+            "ordersAggregate": { "count": 2 },
+        })
+    );
+}
+
+#[tokio::test]
+async fn aggregates_numeric_columns_matching_where() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                ordersAggregate {
+                    count
+                    sum { quantity total }
+                    avg { quantity total }
+                    min { quantity total }
+                    max { quantity total }
+                }
+                paid: ordersAggregate(where: { status: { eq: "paid" } }) {
+                    count
+                    sum { quantity }
+                }
+                none: ordersAggregate(where: { id: { gt: 10 } }) {
+                    count
+                    sum { quantity }
+                    avg { total }
+                }
+            }"#,
+        )
+        .await;
+
+    // nulls are left out of the aggregates, and empty sets have none
+    assert_eq!(
+        data,
+        json!({
+            "ordersAggregate": {
+                "count": 5,
+                "sum": { "quantity": 11, "total": 65.5 },
+                "avg": { "quantity": 2.2, "total": 16.375 },
+                "min": { "quantity": 1, "total": 5.5 },
+                "max": { "quantity": 4, "total": 30.0 },
+            },
+            "paid": { "count": 3, "sum": { "quantity": 6 } },
+            "none": { "count": 0, "sum": { "quantity": null }, "avg": { "total": null } },
+        })
+    );
+}
+
+#[tokio::test]
+async fn aggregates_only_integer_and_float_columns() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                sum: __type(name: "ordersAggregateFields") { fields { name type { name } } }
+                avg: __type(name: "ordersAggregateAvgFields") { fields { name type { name } } }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "sum": { "fields": [
+                { "name": "id", "type": { "name": "Int" } },
+                { "name": "quantity", "type": { "name": "Int" } },
+                { "name": "total", "type": { "name": "Float" } },
+            ] },
+            "avg": { "fields": [
+                { "name": "id", "type": { "name": "Float" } },
+                { "name": "quantity", "type": { "name": "Float" } },
+                { "name": "total", "type": { "name": "Float" } },
+            ] },
+        })
+    );
+
+    let response = server
+        .execute("{ ordersAggregate { sum { customer } } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+}
+
+#[tokio::test]
+async fn leaves_sum_out_of_tables_without_numeric_columns() {
+    let server = TestServer::new(
+        "CREATE TABLE tag (name TEXT PRIMARY KEY); INSERT INTO tag (name) VALUES ('rust');",
+    )
+    .await;
+
+    let data = server
+        .query(r#"{ tagAggregate { count } __type(name: "tagAggregate") { fields { name } } }"#)
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "tagAggregate": { "count": 1 },
+            "__type": { "fields": [{ "name": "count" }] },
+        })
+    );
+}
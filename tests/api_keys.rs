@@ -0,0 +1,140 @@
+mod common;
+
+use common::{connect, test_config};
+use graph_sql::auth::{ApiKeyValidator, Authenticator, Claims};
+use jsonwebtoken::{EncodingKey, Header, get_current_timestamp};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+const SCHEMA: &str = r#"
+CREATE TABLE api_key (
+    id INTEGER PRIMARY KEY,
+    key_hash TEXT NOT NULL UNIQUE,
+    role TEXT,
+    service TEXT,
+    claims TEXT,
+    expires_at,
+    revoked_at TEXT
+);
+"#;
+
+const CONFIG: &str = r#"
+[auth.jwt]
+algorithm = "HS256"
+secret = "secret"
+
+[auth.api-key]
+table = "api_key"
+role-column = "role"
+subject-column = "service"
+claims-column = "claims"
+expires-column = "expires_at"
+revoked-column = "revoked_at"
+"#;
+
+/// Keys with the SQL of their expiry and revocation.
+const KEYS: &[(&str, &str, &str)] = &[
+    ("valid", "'2999-01-01 00:00:00'", "NULL"),
+    ("timestamp", "4102444800", "NULL"),
+    ("forever", "NULL", "NULL"),
+    ("revoked", "NULL", "'2024-01-01'"),
+    ("expired", "'2000-01-01 00:00:00'", "NULL"),
+    ("garbage", "'soon'", "NULL"),
+];
+
+async fn authenticator() -> Authenticator {
+    let config = test_config(CONFIG);
+    let db = connect(&config).await;
+
+    sqlx::raw_sql(SCHEMA).execute(&db).await.unwrap();
+
+    for (key, expires_at, revoked_at) in KEYS {
+        sqlx::query(&format!(
+            r#"INSERT INTO api_key (key_hash, role, service, claims, expires_at, revoked_at)
+               VALUES (?, 'service', 'billing', '{{"tier": "gold"}}', {}, {})"#,
+            expires_at, revoked_at
+        ))
+        .bind(format!("{:x}", Sha256::digest(key.as_bytes())))
+        .execute(&db)
+        .await
+        .unwrap();
+    }
+
+    let auth = config.auth.unwrap();
+
+    Authenticator::try_from(&auth)
+        .unwrap()
+        .with_api_keys(ApiKeyValidator::new(auth.api_key.unwrap(), db))
+}
+
+async fn authenticate(key: &str) -> Result<Claims, String> {
+    authenticator()
+        .await
+        .authenticate(None, Some(key))
+        .await
+        .map(Option::unwrap)
+        .map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn accepts_valid_keys_with_their_claims() {
+    for key in ["valid", "timestamp", "forever"] {
+        let claims = authenticate(key).await.unwrap();
+
+        assert_eq!(
+            claims.values(),
+            json!({ "tier": "gold", "sub": "billing", "role": "service" })
+                .as_object()
+                .unwrap()
+        );
+        assert_eq!(claims.role(), "service");
+    }
+}
+
+#[tokio::test]
+async fn rejects_unknown_revoked_and_expired_keys() {
+    for key in ["unknown", "revoked", "expired", "garbage"] {
+        assert_eq!(
+            authenticate(key).await.unwrap_err(),
+            "Invalid API key",
+            "accepted {}",
+            key
+        );
+    }
+}
+
+#[tokio::test]
+async fn prefers_the_key_over_a_token() {
+    let token = jsonwebtoken::encode(
+        &Header::default(),
+        &json!({ "sub": "ada", "exp": get_current_timestamp() + 3600 }),
+        &EncodingKey::from_secret(b"secret"),
+    )
+    .unwrap();
+
+    let authorization = format!("Bearer {}", token);
+    let auth = authenticator().await;
+
+    let claims = auth
+        .authenticate(Some(&authorization), Some("valid"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(claims.get("sub"), Some(&json!("billing")));
+
+    // a rejected key fails the request rather than falling back to the token
+    assert!(
+        auth.authenticate(Some(&authorization), Some("expired"))
+            .await
+            .is_err()
+    );
+
+    let claims = auth
+        .authenticate(Some(&authorization), None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(claims.get("sub"), Some(&json!("ada")));
+}
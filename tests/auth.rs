@@ -0,0 +1,403 @@
+mod common;
+
+use async_graphql::futures_util::{SinkExt, StreamExt};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use common::{connect, test_config};
+use graph_sql::{
+    GraphSQL,
+    auth::{Authenticator, Claims},
+};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, get_current_timestamp};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::{Message, client::IntoClientRequest};
+use tower::ServiceExt;
+
+const SCHEMA: &str = r#"
+CREATE TABLE note (id INTEGER PRIMARY KEY, owner TEXT NOT NULL);
+
+INSERT INTO note (id, owner) VALUES (1, 'ada'), (2, 'grace');
+"#;
+
+const CONFIG: &str = r#"
+[auth.jwt]
+algorithm = "HS256"
+secret = "secret"
+issuer = ["https://issuer.test"]
+audience = ["graph-sql"]
+required-claims = ["sub"]
+leeway = 30
+
+[[policy]]
+table = "note"
+select = "owner = $claims.sub"
+"#;
+
+const JWKS_CONFIG: &str = r#"
+[auth.jwt]
+algorithm = "ES256"
+jwks-file = "tests/fixtures/jwks.json"
+"#;
+
+const NOTES: &str = "{ notes(page: 1, perPage: 10) { id } }";
+
+/// Token signed with `secret`, holding valid claims overridden by `claims`.
+fn token_with(secret: &str, claims: Value) -> String {
+    let mut values = json!({
+        "sub": "ada",
+        "iss": "https://issuer.test",
+        "aud": "graph-sql",
+        "exp": get_current_timestamp() + 3600,
+    });
+
+    for (name, value) in claims.as_object().unwrap() {
+        match value {
+            Value::Null => values.as_object_mut().unwrap().remove(name),
+            value => values
+                .as_object_mut()
+                .unwrap()
+                .insert(name.clone(), value.clone()),
+        };
+    }
+
+    jsonwebtoken::encode(
+        &Header::default(),
+        &values,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+fn token(claims: Value) -> String {
+    token_with("secret", claims)
+}
+
+/// ES256 token signed with the PEM key of a fixture, under the given key id.
+fn es256_token(pem: &[u8], kid: Option<&str>) -> String {
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = kid.map(str::to_string);
+
+    jsonwebtoken::encode(
+        &header,
+        &json!({ "sub": "ada", "exp": get_current_timestamp() + 3600 }),
+        &EncodingKey::from_ec_pem(pem).unwrap(),
+    )
+    .unwrap()
+}
+
+fn authenticator(config: &str) -> Authenticator {
+    Authenticator::try_from(test_config(config).auth.as_ref().unwrap()).unwrap()
+}
+
+/// Claims of a bearer token, or the message it is rejected with.
+async fn authenticate_with(config: &str, token: &str) -> Result<Claims, String> {
+    authenticator(config)
+        .authenticate(Some(&format!("Bearer {}", token)), None)
+        .await
+        .map(Option::unwrap)
+        .map_err(|e| e.to_string())
+}
+
+async fn authenticate(token: &str) -> Result<Claims, String> {
+    authenticate_with(CONFIG, token).await
+}
+
+async fn server() -> (Router, TcpListener) {
+    let config = test_config(CONFIG);
+    let db = connect(&config).await;
+
+    sqlx::raw_sql(SCHEMA).execute(&db).await.unwrap();
+
+    GraphSQL::new(config).build(&db).await.unwrap()
+}
+
+/// Posts a query with the given `Authorization` header, returning the status and body.
+async fn post(authorization: Option<String>) -> (StatusCode, Value) {
+    let mut request = Request::post("/").header(header::CONTENT_TYPE, "application/json");
+
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+
+    let response = server()
+        .await
+        .0
+        .oneshot(
+            request
+                .body(Body::from(json!({ "query": NOTES }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn accepts_a_valid_token() {
+    let claims = authenticate(&token(json!({ "role": "editor" })))
+        .await
+        .unwrap();
+
+    assert_eq!(claims.get("sub"), Some(&json!("ada")));
+    assert_eq!(claims.role(), "editor");
+}
+
+#[tokio::test]
+async fn rejects_a_bad_signature() {
+    let error = authenticate(&token_with("other", json!({})))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error, "Invalid token: InvalidSignature");
+}
+
+#[tokio::test]
+async fn rejects_a_wrong_issuer_or_audience() {
+    for claims in [
+        json!({ "iss": "https://other.test" }),
+        json!({ "iss": null }),
+        json!({ "aud": "other" }),
+        json!({ "aud": null }),
+    ] {
+        assert!(
+            authenticate(&token(claims.clone())).await.is_err(),
+            "accepted {}",
+            claims
+        );
+    }
+}
+
+#[tokio::test]
+async fn tolerates_expiry_within_the_leeway() {
+    let now = get_current_timestamp();
+
+    assert!(
+        authenticate(&token(json!({ "exp": now - 10 })))
+            .await
+            .is_ok()
+    );
+    assert_eq!(
+        authenticate(&token(json!({ "exp": now - 100 })))
+            .await
+            .unwrap_err(),
+        "Invalid token: ExpiredSignature"
+    );
+    assert!(authenticate(&token(json!({ "exp": null }))).await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_a_missing_required_claim() {
+    let error = authenticate(&token(json!({ "sub": null })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error, "Invalid token: Missing required claim: sub");
+}
+
+#[tokio::test]
+async fn selects_the_jwks_key_of_a_token() {
+    let first = include_bytes!("fixtures/es256-first.pem");
+    let second = include_bytes!("fixtures/es256-second.pem");
+
+    for token in [
+        es256_token(first, Some("first")),
+        es256_token(second, Some("second")),
+    ] {
+        assert!(authenticate_with(JWKS_CONFIG, &token).await.is_ok());
+    }
+
+    assert_eq!(
+        authenticate_with(JWKS_CONFIG, &es256_token(second, Some("first")))
+            .await
+            .unwrap_err(),
+        "Invalid token: InvalidSignature"
+    );
+    assert_eq!(
+        authenticate_with(JWKS_CONFIG, &es256_token(second, Some("third")))
+            .await
+            .unwrap_err(),
+        "Invalid token: Unknown key id Some(\"third\")"
+    );
+}
+
+#[tokio::test]
+async fn tries_every_jwks_key_on_tokens_without_a_key_id() {
+    let second = include_bytes!("fixtures/es256-second.pem");
+
+    let claims = authenticate_with(JWKS_CONFIG, &es256_token(second, None))
+        .await
+        .unwrap();
+
+    assert_eq!(claims.get("sub"), Some(&json!("ada")));
+
+    let other = include_bytes!("fixtures/es256-other.pem");
+
+    assert_eq!(
+        authenticate_with(JWKS_CONFIG, &es256_token(other, None))
+            .await
+            .unwrap_err(),
+        "Invalid token: InvalidSignature"
+    );
+}
+
+#[tokio::test]
+async fn runs_requests_with_the_claims_of_their_token() {
+    let (status, body) = post(Some(format!("Bearer {}", token(json!({}))))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "data": { "notes": [{ "id": 1 }] } }));
+
+    let (status, body) = post(None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "data": { "notes": [] } }));
+}
+
+#[tokio::test]
+async fn rejects_invalid_tokens_with_401() {
+    let (status, body) = post(Some(format!("Bearer {}", token_with("other", json!({}))))).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        body,
+        json!({ "errors": [{ "message": "Invalid token: InvalidSignature" }] })
+    );
+
+    let (status, _) = post(Some(token(json!({})))).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn authenticates_connections_with_their_init_payload() {
+    let auth = authenticator(CONFIG);
+    let upgrade_claims = Claims::new(
+        json!({ "sub": "grace" }).as_object().unwrap().clone(),
+        "role",
+    );
+
+    let data = auth
+        .connection_data(
+            &json!({ "authorization": format!("Bearer {}", token(json!({}))) }),
+            Some(upgrade_claims.clone()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        Claims::from_data(&data).unwrap().get("sub"),
+        Some(&json!("ada"))
+    );
+
+    // without credentials in the payload, the claims of the upgrade request are kept
+    let data = auth
+        .connection_data(&json!({}), Some(upgrade_claims))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        Claims::from_data(&data).unwrap().get("sub"),
+        Some(&json!("grace"))
+    );
+
+    let error = auth
+        .connection_data(
+            &json!({ "Authorization": format!("Bearer {}", token(json!({ "exp": 0 }))) }),
+            None,
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.message, "Invalid token: ExpiredSignature");
+}
+
+/// Opens a `graphql-transport-ws` connection initialized with `payload`, returning the
+/// messages the server sends back for a query.
+async fn query_over_websocket(payload: Value) -> Vec<Message> {
+    let (router, listener) = server().await;
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(axum::serve(listener, router).into_future());
+
+    let mut request = format!("ws://{}/ws", addr).into_client_request().unwrap();
+
+    request.headers_mut().insert(
+        header::SEC_WEBSOCKET_PROTOCOL,
+        "graphql-transport-ws".parse().unwrap(),
+    );
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+    for message in [
+        json!({ "type": "connection_init", "payload": payload }),
+        json!({ "type": "subscribe", "id": "1", "payload": { "query": NOTES } }),
+    ] {
+        socket
+            .send(Message::text(message.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let mut messages = vec![];
+
+    while let Some(Ok(message)) = socket.next().await {
+        let done = match &message {
+            Message::Text(text) => text.contains(r#""type":"complete""#),
+            Message::Close(_) => true,
+            _ => false,
+        };
+
+        messages.push(message);
+
+        if done {
+            break;
+        }
+    }
+
+    messages
+}
+
+#[tokio::test]
+async fn serves_subscriptions_with_the_claims_of_the_init_payload() {
+    let messages =
+        query_over_websocket(json!({ "Authorization": format!("Bearer {}", token(json!({}))) }))
+            .await;
+
+    let next = messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::Text(text) => serde_json::from_str::<Value>(text).ok(),
+            _ => None,
+        })
+        .find(|message| message["type"] == "next")
+        .unwrap();
+
+    assert_eq!(
+        next["payload"],
+        json!({ "data": { "notes": [{ "id": 1 }] } })
+    );
+}
+
+#[tokio::test]
+async fn closes_connections_with_an_invalid_init_payload() {
+    let messages = query_over_websocket(
+        json!({ "Authorization": format!("Bearer {}", token_with("other", json!({}))) }),
+    )
+    .await;
+
+    assert!(
+        matches!(messages.last(), Some(Message::Close(Some(frame))) if frame.reason.contains("InvalidSignature")),
+        "unexpected messages: {:?}",
+        messages
+    );
+}
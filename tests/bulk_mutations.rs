@@ -0,0 +1,113 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE product (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    price INTEGER NOT NULL,
+    stock INTEGER NOT NULL DEFAULT 10
+);
+
+INSERT INTO product (id, name, price) VALUES (1, 'pen', 2), (2, 'ink', 5), (3, 'paper', 1);
+"#;
+
+#[tokio::test]
+async fn inserts_every_value() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                insertProductMany(values: [{ name: "desk", price: 120 }, { name: "lamp", price: 30 }]) {
+                    affectedRows
+                    nodes { name price }
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "insertProductMany": {
+            "affectedRows": 2,
+            "nodes": [{ "name": "desk", "price": 120 }, { "name": "lamp", "price": 30 }],
+        } })
+    );
+    assert_eq!(server.count("product").await, 5);
+}
+
+#[tokio::test]
+async fn inserts_nothing_when_a_value_fails() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(
+            r#"mutation {
+                insertProductMany(values: [{ name: "desk", price: 120 }, { name: "pen", price: 3 }]) {
+                    affectedRows
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(server.count("product").await, 3);
+}
+
+#[tokio::test]
+async fn updates_every_matching_row() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                updateProductMany(where: { price: { lt: 5 } }, set: { stock: 0 }) {
+                    affectedRows
+                    nodes { name stock }
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "updateProductMany": {
+            "affectedRows": 2,
+            "nodes": [{ "name": "pen", "stock": 0 }, { "name": "paper", "stock": 0 }],
+        } })
+    );
+    assert_eq!(
+        server
+            .scalar::<i64>("SELECT COUNT(*) FROM product WHERE stock = 0")
+            .await,
+        2
+    );
+}
+
+#[tokio::test]
+async fn deletes_every_matching_row() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                deleteProductMany(where: { name: { in: ["ink", "paper", "glue"] } }) {
+                    affectedRows
+                    nodes { id name }
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "deleteProductMany": {
+            "affectedRows": 2,
+            "nodes": [{ "id": 2, "name": "ink" }, { "id": 3, "name": "paper" }],
+        } })
+    );
+    assert_eq!(server.count("product").await, 1);
+}
@@ -0,0 +1,173 @@
+//! Server built over an in-memory database, shared by the integration tests.
+
+#![allow(dead_code)] // every test binary uses its own part of it
+
+use std::{sync::Arc, time::Duration};
+
+use async_graphql::{
+    Data, Executor, Request, Response,
+    futures_util::{StreamExt, stream::BoxStream},
+};
+use graph_sql::{GraphSQL, auth::Claims, config::GraphSQLConfig, permission::RoleSchemas};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+const CONFIG: &str = r#"
+[server]
+host = "127.0.0.1"
+port = 0
+
+[graphql]
+
+[database]
+use-env = false
+database-url = "sqlite::memory:"
+"#;
+
+pub struct TestServer {
+    pub db: SqlitePool,
+    pub schema: RoleSchemas,
+}
+
+impl TestServer {
+    /// Creates the tables of `sql` and builds the schema serving them.
+    pub async fn new(sql: &str) -> Self {
+        Self::with_config(sql, "").await
+    }
+
+    /// Same as [`TestServer::new`], with the TOML of `config` merged into the configuration.
+    pub async fn with_config(sql: &str, config: &str) -> Self {
+        let config = test_config(config);
+        let db = connect(&config).await;
+
+        sqlx::raw_sql(sql).execute(&db).await.unwrap();
+
+        let schema = GraphSQL::new(config).build_schemas(&db).await.unwrap();
+
+        Self { db, schema }
+    }
+
+    pub async fn execute(&self, request: impl Into<Request>) -> Response {
+        self.schema.execute(request.into()).await
+    }
+
+    /// Runs an anonymous request, failing the test on errors.
+    pub async fn query(&self, query: &str) -> Value {
+        data(self.execute(query).await)
+    }
+
+    /// Runs a request authenticated with the given claims.
+    pub async fn execute_as(&self, claims: Value, query: &str) -> Response {
+        self.execute(Request::new(query).data(claims_of(claims)))
+            .await
+    }
+
+    /// Runs a request authenticated with the given claims, failing the test on errors.
+    pub async fn query_as(&self, claims: Value, query: &str) -> Value {
+        data(self.execute_as(claims, query).await)
+    }
+
+    /// Starts a subscription or a live query, authenticated with the given claims.
+    pub fn execute_stream(
+        &self,
+        claims: Option<Value>,
+        query: &str,
+    ) -> BoxStream<'static, Response> {
+        let session = claims.map(|claims| {
+            let mut data = Data::default();
+            data.insert(claims_of(claims));
+            Arc::new(data)
+        });
+
+        self.schema.execute_stream(Request::new(query), session)
+    }
+
+    /// Starts a subscription, polled until it waits for changes so that writes made
+    /// afterwards reach it.
+    pub async fn subscribe(
+        &self,
+        claims: Option<Value>,
+        query: &str,
+    ) -> BoxStream<'static, Response> {
+        let mut stream = self.execute_stream(claims, query);
+
+        assert!(next(&mut stream).await.is_none(), "unexpected response");
+
+        stream
+    }
+
+    /// Runs a SQL query returning a single value.
+    pub async fn scalar<T>(&self, sql: &str) -> T
+    where
+        T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+    {
+        sqlx::query_scalar(sql).fetch_one(&self.db).await.unwrap()
+    }
+
+    /// Number of rows of a table.
+    pub async fn count(&self, table: &str) -> i64 {
+        self.scalar(&format!("SELECT COUNT(*) FROM \"{}\"", table))
+            .await
+    }
+}
+
+/// Configuration of an in-memory database with the TOML of `config` merged in.
+pub fn test_config(config: &str) -> GraphSQLConfig {
+    fn merge(base: &mut toml::Table, other: toml::Table) {
+        for (key, value) in other {
+            match (base.get_mut(&key), value) {
+                (Some(toml::Value::Table(base)), toml::Value::Table(other)) => merge(base, other),
+                (_, value) => {
+                    base.insert(key, value);
+                }
+            }
+        }
+    }
+
+    let mut base: toml::Table = toml::from_str(CONFIG).unwrap();
+
+    merge(&mut base, toml::from_str(config).unwrap());
+
+    base.try_into().unwrap()
+}
+
+/// Opens the database of a configuration; in-memory databases live as long as the pool.
+pub async fn connect(config: &GraphSQLConfig) -> SqlitePool {
+    config.database.create_connection().await.unwrap()
+}
+
+/// Claims of a token with the given payload, holding the role in `role`.
+pub fn claims_of(claims: Value) -> Claims {
+    match claims {
+        Value::Object(values) => Claims::new(values, "role"),
+        _ => panic!("claims must be a JSON object"),
+    }
+}
+
+/// Data of a response, failing the test on errors.
+pub fn data(response: Response) -> Value {
+    assert!(
+        response.errors.is_empty(),
+        "unexpected errors: {:?}",
+        response.errors
+    );
+
+    response.data.into_json().unwrap()
+}
+
+/// Next response of a stream, `None` when none comes within a short delay.
+pub async fn next(stream: &mut BoxStream<'static, Response>) -> Option<Response> {
+    tokio::time::timeout(Duration::from_millis(200), stream.next())
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Messages of the errors of a response.
+pub fn errors(response: &Response) -> Vec<String> {
+    response
+        .errors
+        .iter()
+        .map(|error| error.message.clone())
+        .collect()
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE enrollment (
+    student TEXT NOT NULL,
+    course TEXT NOT NULL,
+    grade INTEGER,
+    PRIMARY KEY (student, course)
+);
+
+INSERT INTO enrollment (student, course, grade) VALUES
+    ('ada', 'math', 90), ('ada', 'physics', 80), ('grace', 'math', 70);
+"#;
+
+#[tokio::test]
+async fn looks_rows_up_by_every_key_column() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                a: enrollment(pk: { student: "ada", course: "physics" }) { student course grade }
+                b: enrollment(pk: { student: "grace", course: "math" }) { grade }
+                c: enrollment(pk: { student: "grace", course: "physics" }) { grade }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "a": { "student": "ada", "course": "physics", "grade": 80 },
+            "b": { "grade": 70 },
+            "c": null,
+        })
+    );
+}
+
+#[tokio::test]
+async fn updates_and_deletes_the_row_of_the_key() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                updateEnrollment(pk: { student: "ada", course: "math" }, value: { grade: 95 }) {
+                    student course grade
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "updateEnrollment": { "student": "ada", "course": "math", "grade": 95 } })
+    );
+
+    let data = server
+        .query(r#"mutation { deleteEnrollment(pk: { student: "ada", course: "physics" }) }"#)
+        .await;
+
+    assert_eq!(data, json!({ "deleteEnrollment": 1 }));
+
+    let data = server
+        .query(
+            r#"{ enrollments(page: 1, perPage: 10, orderBy: [{ field: STUDENT }, { field: COURSE }]) {
+                student course grade
+            } }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "enrollments": [
+            { "student": "ada", "course": "math", "grade": 95 },
+            { "student": "grace", "course": "math", "grade": 70 },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn inserts_rows_sharing_part_of_the_key() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                insertEnrollment(value: { student: "grace", course: "physics", grade: 60 }) {
+                    student course grade
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "insertEnrollment": { "student": "grace", "course": "physics", "grade": 60 } })
+    );
+
+    let response = server
+        .execute(
+            r#"mutation {
+                insertEnrollment(value: { student: "grace", course: "math" }) { grade }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(server.count("enrollment").await, 4);
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::TestServer;
+use serde_json::{Value, json};
+
+const SCHEMA: &str = r#"
+CREATE TABLE task (id INTEGER PRIMARY KEY, title TEXT NOT NULL, priority INTEGER);
+
+INSERT INTO task (id, title, priority) VALUES
+    (1, 'write', 2),
+    (2, 'read', NULL),
+    (3, 'plan', 1),
+    (4, 'ship', 2),
+    (5, 'rest', NULL),
+    (6, 'test', 1),
+    (7, 'fix', 2);
+"#;
+
+/// Ids of the tasks sorted by `order_by`, as returned by the list query.
+async fn sorted_ids(server: &TestServer, order_by: &str) -> Vec<Value> {
+    let data = server
+        .query(&format!(
+            "{{ tasks(page: 1, perPage: 10, orderBy: {}) {{ id }} }}",
+            order_by
+        ))
+        .await;
+
+    data["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["id"].clone())
+        .collect()
+}
+
+/// Runs a connection query with the given paging arguments, returning the ids of its
+/// nodes and its page info.
+async fn page(server: &TestServer, order_by: &str, arguments: &str) -> (Vec<Value>, Value) {
+    let data = server
+        .query(&format!(
+            r#"{{
+                tasksConnection(orderBy: {}, {}) {{
+                    edges {{ cursor node {{ id }} }}
+                    pageInfo {{ hasNextPage hasPreviousPage startCursor endCursor }}
+                    totalCount
+                }}
+            }}"#,
+            order_by, arguments
+        ))
+        .await;
+
+    let connection = &data["tasksConnection"];
+
+    assert_eq!(connection["totalCount"], json!(7));
+
+    let edges = connection["edges"].as_array().unwrap();
+
+    if let (Some(first), Some(last)) = (edges.first(), edges.last()) {
+        assert_eq!(connection["pageInfo"]["startCursor"], first["cursor"]);
+        assert_eq!(connection["pageInfo"]["endCursor"], last["cursor"]);
+    }
+
+    (
+        edges
+            .iter()
+            .map(|edge| edge["node"]["id"].clone())
+            .collect(),
+        connection["pageInfo"].clone(),
+    )
+}
+
+const ORDERS: &[&str] = &[
+    "[]",
+    "[{ field: PRIORITY }]",
+    "[{ field: PRIORITY, order: DESC }]",
+    "[{ field: PRIORITY, nulls: LAST }]",
+    "[{ field: PRIORITY, order: DESC, nulls: FIRST }, { field: ID, order: DESC }]",
+];
+
+#[tokio::test]
+async fn pages_forward_across_null_and_duplicate_sort_keys() {
+    let server = TestServer::new(SCHEMA).await;
+
+    for order_by in ORDERS {
+        let mut ids = vec![];
+        let mut arguments = "first: 3".to_string();
+
+        loop {
+            let (page_ids, page_info) = page(&server, order_by, &arguments).await;
+
+            assert_eq!(page_info["hasPreviousPage"], json!(!ids.is_empty()));
+
+            ids.extend(page_ids);
+
+            if page_info["hasNextPage"] == json!(false) {
+                break;
+            }
+
+            arguments = format!("first: 3, after: {}", page_info["endCursor"]);
+        }
+
+        assert_eq!(ids, sorted_ids(&server, order_by).await, "{}", order_by);
+    }
+}
+
+#[tokio::test]
+async fn pages_backward_across_null_and_duplicate_sort_keys() {
+    let server = TestServer::new(SCHEMA).await;
+
+    for order_by in ORDERS {
+        let mut ids = vec![];
+        let mut arguments = "last: 3".to_string();
+
+        loop {
+            let (mut page_ids, page_info) = page(&server, order_by, &arguments).await;
+
+            assert_eq!(page_info["hasNextPage"], json!(!ids.is_empty()));
+
+            page_ids.extend(ids);
+            ids = page_ids;
+
+            if page_info["hasPreviousPage"] == json!(false) {
+                break;
+            }
+
+            arguments = format!("last: 3, before: {}", page_info["startCursor"]);
+        }
+
+        assert_eq!(ids, sorted_ids(&server, order_by).await, "{}", order_by);
+    }
+}
+
+#[tokio::test]
+async fn pages_between_two_cursors() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let order_by = "[{ field: PRIORITY }]";
+
+    let (_, first) = page(&server, order_by, "first: 1").await;
+    let (_, last) = page(&server, order_by, "last: 1").await;
+
+    let (ids, page_info) = page(
+        &server,
+        order_by,
+        &format!(
+            "first: 10, after: {}, before: {}",
+            first["endCursor"], last["startCursor"]
+        ),
+    )
+    .await;
+
+    assert_eq!(ids, sorted_ids(&server, order_by).await[1..6]);
+
+    // paging forward, the next page is only reported within `before`
+    assert_eq!(page_info["hasNextPage"], json!(false));
+    assert_eq!(page_info["hasPreviousPage"], json!(true));
+}
+
+#[tokio::test]
+async fn rejects_a_malformed_cursor() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(r#"{ tasksConnection(first: 2, after: "not a cursor") { totalCount } }"#)
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE post (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL,
+    views INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'draft',
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+"#;
+
+#[tokio::test]
+async fn inserts_rows_omitting_defaulted_columns() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                first: insertPost(value: { title: "first" }) { id title views status }
+                second: insertPost(value: { title: "second", views: 3, status: "published" }) {
+                    id views status
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "first": { "id": 1, "title": "first", "views": 0, "status": "draft" },
+            "second": { "id": 2, "views": 3, "status": "published" },
+        })
+    );
+
+    // expression defaults are evaluated by SQLite
+    let (created_at,): (Option<String>,) =
+        sqlx::query_as("SELECT created_at FROM post WHERE id = 1")
+            .fetch_one(&server.db)
+            .await
+            .unwrap();
+
+    assert!(created_at.is_some());
+
+    let data = server
+        .query(
+            r#"mutation { insertPostMany(values: [{ title: "third" }]) { nodes { id views } } }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "insertPostMany": { "nodes": [{ "id": 3, "views": 0 }] } })
+    );
+}
+
+#[tokio::test]
+async fn describes_defaults_in_insert_inputs() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                __type(name: "insertPostInput") {
+                    inputFields { name description type { kind } }
+                }
+            }"#,
+        )
+        .await;
+
+    // the autoincrement key is left out, defaulted columns are optional
+    assert_eq!(
+        data,
+        json!({ "__type": { "inputFields": [
+            { "name": "title", "description": null, "type": { "kind": "NON_NULL" } },
+            { "name": "views", "description": "Defaults to `0`", "type": { "kind": "SCALAR" } },
+            { "name": "status", "description": "Defaults to `'draft'`", "type": { "kind": "SCALAR" } },
+            {
+                "name": "createdAt",
+                "description": "Defaults to `datetime('now')`",
+                "type": { "kind": "SCALAR" },
+            },
+        ] } })
+    );
+}
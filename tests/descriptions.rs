@@ -0,0 +1,82 @@
+mod common;
+
+use common::{connect, test_config};
+use graph_sql::GraphSQL;
+
+const SCHEMA: &str = r#"
+CREATE TABLE post ( -- Articles published on the blog
+    id INTEGER PRIMARY KEY,
+    -- Headline shown in listings
+    title TEXT NOT NULL,
+    slug TEXT NOT NULL /* URL-friendly identifier */,
+    body TEXT
+);
+
+CREATE TABLE category /* Groups of posts */ (
+    id INTEGER PRIMARY KEY,
+    parent_category_id INTEGER, -- For hierarchical categories
+    name TEXT NOT NULL
+);
+"#;
+
+/// SDL of the schema built over `sql` with the given configuration.
+async fn sdl(sql: &str, config: &str) -> String {
+    let config = test_config(config);
+    let db = connect(&config).await;
+    let graph_sql = GraphSQL::new(config);
+
+    sqlx::raw_sql(sql).execute(&db).await.unwrap();
+
+    let tables = graph_sql.introspect(&db).await.unwrap();
+
+    graph_sql
+        .build_schema(tables)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .sdl()
+}
+
+#[tokio::test]
+async fn describes_tables_and_columns_with_their_sql_comments() {
+    let sdl = sdl(SCHEMA, "").await;
+
+    for expected in [
+        "\"\"\"\nArticles published on the blog\n\"\"\"\ntype postNode {",
+        "\t\"\"\"\n\tHeadline shown in listings\n\t\"\"\"\n\ttitle: String!",
+        "\t\"\"\"\n\tURL-friendly identifier\n\t\"\"\"\n\tslug: String!",
+        "\"\"\"\nGroups of posts\n\"\"\"\ntype categoryNode {",
+        "\t\"\"\"\n\tFor hierarchical categories\n\t\"\"\"\n\tparentCategoryId: Int",
+    ] {
+        assert!(sdl.contains(expected), "{}", expected);
+    }
+
+    assert!(!sdl.contains("\"\"\"\n\tbody: String"));
+}
+
+#[tokio::test]
+async fn prefers_configured_descriptions_over_comments() {
+    let sdl = sdl(
+        SCHEMA,
+        r#"
+        [descriptions.post]
+        table = "Blog articles"
+
+        [descriptions.post.columns]
+        slug = "URL-friendly identifier of the post"
+        body = "Markdown source"
+        "#,
+    )
+    .await;
+
+    for expected in [
+        "\"\"\"\nBlog articles\n\"\"\"\ntype postNode {",
+        "\t\"\"\"\n\tHeadline shown in listings\n\t\"\"\"\n\ttitle: String!",
+        "\t\"\"\"\n\tURL-friendly identifier of the post\n\t\"\"\"\n\tslug: String!",
+        "\t\"\"\"\n\tMarkdown source\n\t\"\"\"\n\tbody: String",
+    ] {
+        assert!(sdl.contains(expected), "{}", expected);
+    }
+
+    assert!(!sdl.contains("Articles published on the blog"));
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE address (
+    id INTEGER PRIMARY KEY,
+    street TEXT NOT NULL,
+    type TEXT NOT NULL CHECK (type IN ('billing', 'shipping', 'both')),
+    status TEXT DEFAULT 'active' CHECK (status IN ('active', 'archived'))
+);
+
+INSERT INTO address (id, street, type, status) VALUES
+    (1, 'main', 'billing', 'active'),
+    (2, 'side', 'shipping', 'archived'),
+    (3, 'back', 'both', NULL);
+"#;
+
+#[tokio::test]
+async fn generates_enums_from_check_constraints() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                __type(name: "AddressType") { kind enumValues { name } }
+                address(id: 1) { type status }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "__type": {
+                "kind": "ENUM",
+                "enumValues": [{ "name": "billing" }, { "name": "shipping" }, { "name": "both" }],
+            },
+            "address": { "type": "billing", "status": "active" },
+        })
+    );
+}
+
+#[tokio::test]
+async fn filters_by_enum_values() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                shipping: addresses(page: 1, perPage: 10, where: { type: { in: [shipping, both] } }) { id }
+                active: addresses(page: 1, perPage: 10, where: { status: { ne: archived } }) { id }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "shipping": [{ "id": 2 }, { "id": 3 }],
+            "active": [{ "id": 1 }],
+        })
+    );
+}
+
+#[tokio::test]
+async fn writes_enum_values() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                insertAddress(value: { street: "new", type: shipping }) { id type status }
+                updateAddress(id: 1, value: { type: both, status: archived }) { type status }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "insertAddress": { "id": 4, "type": "shipping", "status": "active" },
+            "updateAddress": { "type": "both", "status": "archived" },
+        })
+    );
+}
+
+#[tokio::test]
+async fn rejects_values_outside_the_enum() {
+    let server = TestServer::new(SCHEMA).await;
+
+    for mutation in [
+        r#"mutation { insertAddress(value: { street: "new", type: home }) { id } }"#,
+        "mutation { updateAddress(id: 1, value: { status: deleted }) { id } }",
+    ] {
+        let response = server.execute(mutation).await;
+
+        assert_eq!(response.errors.len(), 1, "{}", mutation);
+    }
+
+    assert_eq!(server.count("address").await, 3);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::TestServer;
+use serde_json::{Value, json};
+
+const SCHEMA: &str = r#"
+CREATE TABLE product (id INTEGER PRIMARY KEY, name TEXT NOT NULL, price REAL, stock INTEGER);
+
+INSERT INTO product (id, name, price, stock) VALUES
+    (1, 'apple', 1.5, 10),
+    (2, 'banana', 0.5, NULL),
+    (3, '100% juice', 3.0, 0),
+    (4, 'snake_case', 2.0, 5),
+    (5, 'snakeXcase', 2.5, 7);
+"#;
+
+/// Ids of the products matching `filter`, in key order.
+async fn ids(server: &TestServer, filter: &str) -> Value {
+    let data = server
+        .query(&format!(
+            "{{ products(page: 1, perPage: 10, where: {}) {{ id }} }}",
+            filter
+        ))
+        .await;
+
+    Value::Array(
+        data["products"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|product| product["id"].clone())
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn filters_with_every_operator() {
+    let server = TestServer::new(SCHEMA).await;
+
+    assert_eq!(
+        ids(&server, r#"{ name: { eq: "apple" } }"#).await,
+        json!([1])
+    );
+    assert_eq!(
+        ids(&server, "{ id: { ne: 1, lte: 3 } }").await,
+        json!([2, 3])
+    );
+    assert_eq!(
+        ids(&server, "{ price: { gt: 1.5, lt: 3.0 } }").await,
+        json!([4, 5])
+    );
+    assert_eq!(ids(&server, "{ price: { gte: 3.0 } }").await, json!([3]));
+    assert_eq!(
+        ids(&server, "{ id: { in: [2, 4, 9] } }").await,
+        json!([2, 4])
+    );
+    assert_eq!(
+        ids(&server, "{ stock: { isNull: true } }").await,
+        json!([2])
+    );
+    assert_eq!(
+        ids(&server, "{ stock: { isNull: false, lt: 6 } }").await,
+        json!([3, 4])
+    );
+}
+
+#[tokio::test]
+async fn nests_and_or_not() {
+    let server = TestServer::new(SCHEMA).await;
+
+    assert_eq!(
+        ids(
+            &server,
+            r#"{
+                or: [
+                    { and: [{ price: { lt: 1.0 } }, { stock: { isNull: true } }] },
+                    { and: [{ price: { gte: 2.0 } }, { not: { name: { startsWith: "snake" } } }] }
+                ]
+            }"#
+        )
+        .await,
+        json!([2, 3])
+    );
+
+    // comparisons with NULL are unknown, and so are their negations
+    assert_eq!(
+        ids(
+            &server,
+            "{ not: { or: [{ id: { eq: 1 } }, { stock: { gte: 5 } }] } }"
+        )
+        .await,
+        json!([3])
+    );
+
+    // fields of the same input are combined with the nested combinators
+    assert_eq!(
+        ids(
+            &server,
+            "{ price: { gte: 1.0 }, or: [{ id: { eq: 1 } }, { id: { eq: 2 } }] }"
+        )
+        .await,
+        json!([1])
+    );
+}
+
+#[tokio::test]
+async fn matches_like_wildcards_literally() {
+    let server = TestServer::new(SCHEMA).await;
+
+    assert_eq!(
+        ids(&server, r#"{ name: { contains: "%" } }"#).await,
+        json!([3])
+    );
+    assert_eq!(
+        ids(&server, r#"{ name: { contains: "e_c" } }"#).await,
+        json!([4])
+    );
+    assert_eq!(
+        ids(&server, r#"{ name: { startsWith: "snake_" } }"#).await,
+        json!([4])
+    );
+    assert_eq!(
+        ids(&server, r#"{ name: { startsWith: "_" } }"#).await,
+        json!([])
+    );
+    assert_eq!(
+        ids(&server, r#"{ name: { contains: "an" } }"#).await,
+        json!([2])
+    );
+}
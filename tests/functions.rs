@@ -0,0 +1,134 @@
+mod common;
+
+use async_graphql::parser::types::OperationType;
+use common::{TestServer, claims_of, connect, errors, test_config};
+use graph_sql::functions::{RequestInfo, RequestScope};
+use serde_json::{Value, json};
+
+const SCHEMA: &str = r#"
+CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE note (
+    id INTEGER PRIMARY KEY,
+    owner_id INTEGER NOT NULL REFERENCES user(id),
+    body TEXT NOT NULL,
+    created_by TEXT DEFAULT (graphsql_claim('sub'))
+);
+CREATE TABLE audit (operation TEXT, type TEXT, role TEXT);
+
+CREATE TRIGGER note_insert BEFORE INSERT ON note
+WHEN graphsql_role() IS 'anonymous'
+BEGIN
+    SELECT RAISE(ABORT, 'sign in to write notes');
+END;
+
+CREATE TRIGGER note_audit AFTER INSERT ON note
+BEGIN
+    INSERT INTO audit VALUES (graphsql_request('operation'), graphsql_request('type'), graphsql_role());
+END;
+
+INSERT INTO user (id, name) VALUES (1, 'ada'), (2, 'grace');
+INSERT INTO note (id, owner_id, body) VALUES (1, 1, 'mine'), (2, 2, 'theirs');
+"#;
+
+const POLICY: &str = r#"
+[[policy]]
+table = "note"
+select = "owner_id = graphsql_claim('sub')"
+"#;
+
+fn ada() -> Value {
+    json!({ "sub": "1", "role": "editor" })
+}
+
+#[tokio::test]
+async fn exposes_the_request_until_the_scope_drops() {
+    let db = connect(&test_config("")).await;
+    let mut conn = db.acquire().await.unwrap();
+
+    let sql = "SELECT json_array(graphsql_claim('sub'), graphsql_role(), graphsql_request('type'))";
+
+    let request = RequestInfo {
+        claims: claims_of(ada()),
+        operation_name: None,
+        operation_type: Some(OperationType::Query),
+    };
+
+    let scope = RequestScope::enter(&mut conn, Some(&request))
+        .await
+        .unwrap();
+
+    let values: Value = sqlx::query_scalar(sql).fetch_one(&mut *conn).await.unwrap();
+
+    assert_eq!(values, json!(["1", "editor", "query"]));
+
+    drop(scope);
+
+    let values: Value = sqlx::query_scalar(sql).fetch_one(&mut *conn).await.unwrap();
+
+    assert_eq!(values, json!([null, null, null]));
+}
+
+#[tokio::test]
+async fn stamps_writes_with_the_request() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query_as(
+            ada(),
+            r#"mutation AddNote { insertNote(value: { ownerId: 1, body: "new" }) { createdBy } }"#,
+        )
+        .await;
+
+    assert_eq!(data, json!({ "insertNote": { "createdBy": "1" } }));
+
+    // the rows inserted by the schema have no request
+    assert_eq!(
+        server
+            .scalar::<Value>(
+                "SELECT json_group_array(json_array(operation, type, role)) FROM audit"
+            )
+            .await,
+        json!([
+            [null, null, null],
+            [null, null, null],
+            ["AddNote", "mutation", "editor"]
+        ])
+    );
+
+    let response = server
+        .execute(r#"mutation { insertNote(value: { ownerId: 1, body: "anonymous" }) { id } }"#)
+        .await;
+
+    assert_eq!(errors(&response).len(), 1);
+    assert!(errors(&response)[0].contains("sign in to write notes"));
+    assert_eq!(server.count("note").await, 3);
+}
+
+#[tokio::test]
+async fn reads_in_the_scope_of_the_request() {
+    let server = TestServer::with_config(SCHEMA, POLICY).await;
+
+    let data = server
+        .query_as(
+            ada(),
+            r#"{
+                notes(page: 1, perPage: 10) { id }
+                theirs: note(id: 2) { id }
+                user(id: 1) { notes { id } }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "notes": [{ "id": 1 }],
+            "theirs": null,
+            "user": { "notes": [{ "id": 1 }] },
+        })
+    );
+
+    let data = server.query("{ notes(page: 1, perPage: 10) { id } }").await;
+
+    assert_eq!(data, json!({ "notes": [] }));
+}
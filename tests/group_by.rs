@@ -0,0 +1,117 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE orders (
+    id INTEGER PRIMARY KEY,
+    customer TEXT NOT NULL,
+    status TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    total REAL
+);
+
+INSERT INTO orders (id, customer, status, quantity, total) VALUES
+    (1, 'ada', 'paid', 2, 20.0),
+    (2, 'ada', 'paid', 1, 5.5),
+    (3, 'grace', 'open', 4, NULL),
+    (4, 'grace', 'paid', 3, 30.0),
+    (5, 'linus', 'open', 1, 10.0);
+"#;
+
+#[tokio::test]
+async fn groups_rows_by_their_keys() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                ordersGroupBy(by: [CUSTOMER, STATUS]) {
+                    key { customer status id }
+                    count
+                    sum { total }
+                }
+            }"#,
+        )
+        .await;
+
+    // only the grouped columns of the key are set
+    assert_eq!(
+        data,
+        json!({ "ordersGroupBy": [
+            { "key": { "customer": "ada", "status": "paid", "id": null }, "count": 2, "sum": { "total": 25.5 } },
+            { "key": { "customer": "grace", "status": "open", "id": null }, "count": 1, "sum": { "total": null } },
+            { "key": { "customer": "grace", "status": "paid", "id": null }, "count": 1, "sum": { "total": 30.0 } },
+            { "key": { "customer": "linus", "status": "open", "id": null }, "count": 1, "sum": { "total": 10.0 } },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn filters_rows_with_where_and_groups_with_having() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let groups = |arguments: &str| {
+        format!(
+            "{{ ordersGroupBy(by: [CUSTOMER], {}) {{ key {{ customer }} count }} }}",
+            arguments
+        )
+    };
+
+    let data = server.query(&groups("having: { count: { gte: 2 } }")).await;
+
+    assert_eq!(
+        data,
+        json!({ "ordersGroupBy": [
+            { "key": { "customer": "ada" }, "count": 2 },
+            { "key": { "customer": "grace" }, "count": 2 },
+        ] })
+    );
+
+    // `where` applies to the rows before grouping, `having` to the groups
+    let data = server
+        .query(&groups(
+            r#"where: { status: { eq: "paid" } }, having: { count: { gte: 2 } }"#,
+        ))
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "ordersGroupBy": [{ "key": { "customer": "ada" }, "count": 2 }] })
+    );
+
+    let data = server
+        .query(&groups(
+            "having: { or: [{ sum: { quantity: { gt: 5 } } }, { max: { total: { lt: 15 } } }] }",
+        ))
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "ordersGroupBy": [
+            { "key": { "customer": "grace" }, "count": 2 },
+            { "key": { "customer": "linus" }, "count": 1 },
+        ] })
+    );
+
+    let data = server
+        .query(&groups(
+            "having: { avg: { quantity: { gte: 1.5 } }, not: { min: { total: { eq: 30 } } } }",
+        ))
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "ordersGroupBy": [{ "key": { "customer": "ada" }, "count": 2 }] })
+    );
+}
+
+#[tokio::test]
+async fn requires_a_grouped_column() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server.execute("{ ordersGroupBy(by: []) { count } }").await;
+
+    assert_eq!(response.errors.len(), 1);
+}
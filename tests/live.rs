@@ -0,0 +1,90 @@
+mod common;
+
+use common::{TestServer, data, next};
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE author (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE book (
+    id INTEGER PRIMARY KEY,
+    author_id INTEGER NOT NULL REFERENCES author(id),
+    title TEXT NOT NULL
+);
+
+INSERT INTO author (id, name) VALUES (1, 'Le Guin');
+INSERT INTO book (id, author_id, title) VALUES (1, 1, 'The Dispossessed'), (2, 1, 'Lavinia');
+"#;
+
+#[tokio::test]
+async fn reruns_after_a_write_to_a_read_table() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut stream = server.execute_stream(
+        None,
+        "query @live { books(page: 1, perPage: 10, orderBy: [{ field: ID }]) { title } }",
+    );
+
+    assert_eq!(
+        data(next(&mut stream).await.unwrap()),
+        json!({ "books": [{ "title": "The Dispossessed" }, { "title": "Lavinia" }] })
+    );
+
+    server
+        .query(r#"mutation { insertBook(value: { authorId: 1, title: "Tehanu" }) { id } }"#)
+        .await;
+
+    assert_eq!(
+        data(next(&mut stream).await.unwrap()),
+        json!({ "books": [
+            { "title": "The Dispossessed" },
+            { "title": "Lavinia" },
+            { "title": "Tehanu" },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn reruns_after_a_write_to_a_related_table() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut stream = server.execute_stream(None, "query @live { book(id: 1) { author { name } } }");
+
+    assert_eq!(
+        data(next(&mut stream).await.unwrap()),
+        json!({ "book": { "author": { "name": "Le Guin" } } })
+    );
+
+    server
+        .query(r#"mutation { updateAuthor(id: 1, value: { name: "Ursula K. Le Guin" }) { id } }"#)
+        .await;
+
+    assert_eq!(
+        data(next(&mut stream).await.unwrap()),
+        json!({ "book": { "author": { "name": "Ursula K. Le Guin" } } })
+    );
+}
+
+#[tokio::test]
+async fn pushes_only_changed_results() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut stream = server.execute_stream(None, "query @live { book(id: 1) { title } }");
+
+    assert_eq!(
+        data(next(&mut stream).await.unwrap()),
+        json!({ "book": { "title": "The Dispossessed" } })
+    );
+
+    server
+        .query(r#"mutation { updateBook(id: 2, value: { title: "Gifts" }) { id } }"#)
+        .await;
+
+    assert!(next(&mut stream).await.is_none());
+
+    server.query("mutation { deleteBook(id: 1) }").await;
+
+    assert_eq!(
+        data(next(&mut stream).await.unwrap()),
+        json!({ "book": null })
+    );
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE post (id INTEGER PRIMARY KEY, title TEXT NOT NULL);
+CREATE TABLE tag (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE post_tag (
+    post_id INTEGER NOT NULL REFERENCES post(id),
+    tag_id INTEGER NOT NULL REFERENCES tag(id),
+    PRIMARY KEY (post_id, tag_id)
+);
+
+INSERT INTO post (id, title) VALUES (1, 'first'), (2, 'second');
+INSERT INTO tag (id, name) VALUES (1, 'rust'), (2, 'sql'), (3, 'graphql');
+INSERT INTO post_tag (post_id, tag_id) VALUES (1, 1), (1, 2), (2, 2);
+"#;
+
+const TAGS_OF_POSTS: &str =
+    "{ posts(page: 1, perPage: 10) { title tags(orderBy: [{ field: NAME }]) { name } } }";
+
+#[tokio::test]
+async fn lists_related_rows_through_the_junction() {
+    let server = TestServer::new(SCHEMA).await;
+
+    assert_eq!(
+        server.query(TAGS_OF_POSTS).await,
+        json!({ "posts": [
+            { "title": "first", "tags": [{ "name": "rust" }, { "name": "sql" }] },
+            { "title": "second", "tags": [{ "name": "sql" }] },
+        ] })
+    );
+
+    assert_eq!(
+        server
+            .query("{ tag(id: 2) { posts(orderBy: [{ field: ID }]) { title } } }")
+            .await,
+        json!({ "tag": { "posts": [{ "title": "first" }, { "title": "second" }] } })
+    );
+}
+
+#[tokio::test]
+async fn connects_and_disconnects_rows() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query("mutation { connectPostTag(postId: 2, tagId: 3) { post { title } tag { name } } }")
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "connectPostTag": { "post": { "title": "second" }, "tag": { "name": "graphql" } } })
+    );
+
+    let data = server
+        .query("mutation { disconnectPostTag(postId: 1, tagId: 2) }")
+        .await;
+
+    assert_eq!(data, json!({ "disconnectPostTag": 1 }));
+
+    assert_eq!(
+        server.query(TAGS_OF_POSTS).await,
+        json!({ "posts": [
+            { "title": "first", "tags": [{ "name": "rust" }] },
+            { "title": "second", "tags": [{ "name": "graphql" }, { "name": "sql" }] },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn disconnecting_unlinked_rows_removes_nothing() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query("mutation { disconnectPostTag(postId: 2, tagId: 1) }")
+        .await;
+
+    assert_eq!(data, json!({ "disconnectPostTag": 0 }));
+    assert_eq!(server.count("post_tag").await, 3);
+}
+
+#[tokio::test]
+async fn connecting_linked_rows_returns_the_existing_link() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query("mutation { connectPostTag(postId: 1, tagId: 1) { post { id } tag { id } } }")
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "connectPostTag": { "post": { "id": 1 }, "tag": { "id": 1 } } })
+    );
+    assert_eq!(server.count("post_tag").await, 3);
+}
+
+/// Sorted names of the list fields of a type.
+async fn list_fields(server: &TestServer, type_name: &str) -> Vec<String> {
+    let data = server
+        .query(&format!(
+            r#"{{ __type(name: "{}") {{ fields {{ name type {{ kind ofType {{ kind }} }} }} }} }}"#,
+            type_name
+        ))
+        .await;
+
+    let mut names = data["__type"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|field| {
+            field["type"]["kind"] == "LIST" || field["type"]["ofType"]["kind"] == "LIST"
+        })
+        .map(|field| field["name"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn names_fields_after_the_junction_when_several_lead_to_a_table() {
+    let server = TestServer::new(
+        r#"
+        CREATE TABLE student (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE course (id INTEGER PRIMARY KEY, title TEXT NOT NULL);
+        CREATE TABLE enrollment (
+            student_id INTEGER NOT NULL REFERENCES student(id),
+            course_id INTEGER NOT NULL REFERENCES course(id),
+            PRIMARY KEY (student_id, course_id)
+        );
+        CREATE TABLE wishlist (
+            student_id INTEGER NOT NULL REFERENCES student(id),
+            course_id INTEGER NOT NULL REFERENCES course(id),
+            PRIMARY KEY (student_id, course_id)
+        );
+        CREATE TABLE mentorship (
+            mentor_id INTEGER NOT NULL REFERENCES student(id),
+            mentee_id INTEGER NOT NULL REFERENCES student(id),
+            PRIMARY KEY (mentor_id, mentee_id)
+        );
+        "#,
+    )
+    .await;
+
+    assert_eq!(
+        list_fields(&server, "studentNode").await,
+        [
+            "coursesViaEnrollment",
+            "coursesViaWishlist",
+            "enrollments",
+            "mentorshipsByMentee",
+            "mentorshipsByMentor",
+            "studentsByMentee",
+            "studentsByMentor",
+            "wishlists",
+        ]
+    );
+    assert_eq!(
+        list_fields(&server, "courseNode").await,
+        [
+            "enrollments",
+            "studentsViaEnrollment",
+            "studentsViaWishlist",
+            "wishlists",
+        ]
+    );
+}
+
+const ENROLLMENTS: &str = r#"
+CREATE TABLE student (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE course (id INTEGER PRIMARY KEY, title TEXT NOT NULL);
+CREATE TABLE enrollment (
+    student_id INTEGER NOT NULL REFERENCES student(id),
+    course_id INTEGER NOT NULL REFERENCES course(id),
+    grade TEXT,
+    note TEXT,
+    PRIMARY KEY (student_id, course_id)
+);
+
+INSERT INTO student (id, name) VALUES (1, 'ada');
+INSERT INTO course (id, title) VALUES (1, 'logic');
+INSERT INTO enrollment (student_id, course_id, grade) VALUES (1, 1, 'A');
+"#;
+
+#[tokio::test]
+async fn keeps_junctions_with_payload_columns_as_tables() {
+    let server = TestServer::new(ENROLLMENTS).await;
+
+    assert_eq!(list_fields(&server, "studentNode").await, ["enrollments"]);
+    assert_eq!(list_fields(&server, "courseNode").await, ["enrollments"]);
+
+    let response = server
+        .execute("mutation { connectEnrollment(studentId: 1, courseId: 1) { grade } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+
+    // the payload stays reachable through the junction rows
+    assert_eq!(
+        server
+            .query("{ student(id: 1) { enrollments { grade course { title } } } }")
+            .await,
+        json!({ "student": { "enrollments": [{ "grade": "A", "course": { "title": "logic" } }] } })
+    );
+}
+
+#[tokio::test]
+async fn links_configured_junctions_with_payload_columns() {
+    let server = TestServer::with_config(
+        ENROLLMENTS,
+        r#"
+        [graphql]
+        junction-tables = ["enrollment"]
+        "#,
+    )
+    .await;
+
+    assert_eq!(
+        list_fields(&server, "studentNode").await,
+        ["courses", "enrollments"]
+    );
+
+    let data = server
+        .query("{ course(id: 1) { students { name } } }")
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "course": { "students": [{ "name": "ada" }] } })
+    );
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE post (
+    id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES user(id),
+    title TEXT NOT NULL CHECK (length(title) > 0)
+);
+
+INSERT INTO user (id, name) VALUES (1, 'ada');
+"#;
+
+#[tokio::test]
+async fn inserts_children_and_parents_along_with_a_row() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                insertUser(value: { name: "grace", posts: { create: [{ title: "a" }, { title: "b" }] } }) {
+                    name
+                    posts(orderBy: [{ field: TITLE }]) { title }
+                }
+                insertPost(value: { title: "c", user: { create: { name: "linus" } } }) {
+                    title
+                    user { name }
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "insertUser": { "name": "grace", "posts": [{ "title": "a" }, { "title": "b" }] },
+            "insertPost": { "title": "c", "user": { "name": "linus" } },
+        })
+    );
+}
+
+#[tokio::test]
+async fn rolls_the_insert_back_when_a_child_fails() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(
+            r#"mutation {
+                insertUser(value: { name: "grace", posts: { create: [{ title: "a" }, { title: "" }] } }) {
+                    id
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(server.count("user").await, 1);
+    assert_eq!(server.count("post").await, 0);
+}
+
+#[tokio::test]
+async fn rolls_the_insert_back_when_the_parent_fails() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(
+            r#"mutation {
+                insertPost(value: { title: "", user: { create: { name: "grace" } } }) { id }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(server.count("user").await, 1);
+    assert_eq!(server.count("post").await, 0);
+}
+
+#[tokio::test]
+async fn rolls_the_update_back_when_a_child_fails() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(
+            r#"mutation {
+                updateUser(id: 1, value: { name: "ada lovelace", posts: { create: [{ title: "" }] } }) {
+                    name
+                }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(
+        server
+            .scalar::<String>("SELECT name FROM user WHERE id = 1")
+            .await,
+        "ada"
+    );
+    assert_eq!(server.count("post").await, 0);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::TestServer;
+use serde_json::{Value, json};
+
+const SCHEMA: &str = r#"
+CREATE TABLE task (id INTEGER PRIMARY KEY, title TEXT NOT NULL, priority INTEGER, due TEXT);
+
+INSERT INTO task (id, title, priority, due) VALUES
+    (1, 'write', 2, '2024-03-01'),
+    (2, 'read', NULL, '2024-01-01'),
+    (3, 'plan', 1, NULL),
+    (4, 'ship', 2, '2024-02-01'),
+    (5, 'rest', NULL, NULL);
+"#;
+
+/// Ids of the tasks sorted by `order_by`.
+async fn ids(server: &TestServer, order_by: &str) -> Value {
+    let data = server
+        .query(&format!(
+            "{{ tasks(page: 1, perPage: 10, orderBy: {}) {{ id }} }}",
+            order_by
+        ))
+        .await;
+
+    Value::Array(
+        data["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["id"].clone())
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn sorts_by_several_keys_then_the_primary_key() {
+    let server = TestServer::new(SCHEMA).await;
+
+    assert_eq!(ids(&server, "[]").await, json!([1, 2, 3, 4, 5]));
+    assert_eq!(
+        ids(&server, "[{ field: PRIORITY, order: DESC }]").await,
+        json!([1, 4, 3, 2, 5])
+    );
+    assert_eq!(
+        ids(
+            &server,
+            "[{ field: PRIORITY, order: DESC }, { field: DUE, order: ASC }]"
+        )
+        .await,
+        json!([4, 1, 3, 5, 2])
+    );
+}
+
+#[tokio::test]
+async fn places_nulls_first_or_last() {
+    let server = TestServer::new(SCHEMA).await;
+
+    // SQLite sorts nulls first in ascending order
+    assert_eq!(
+        ids(&server, "[{ field: PRIORITY }]").await,
+        json!([2, 5, 3, 1, 4])
+    );
+    assert_eq!(
+        ids(&server, "[{ field: PRIORITY, nulls: LAST }]").await,
+        json!([3, 1, 4, 2, 5])
+    );
+    assert_eq!(
+        ids(&server, "[{ field: PRIORITY, order: DESC, nulls: FIRST }]").await,
+        json!([2, 5, 1, 4, 3])
+    );
+    assert_eq!(
+        ids(
+            &server,
+            "[{ field: DUE, order: DESC, nulls: LAST }, { field: ID, order: DESC }]"
+        )
+        .await,
+        json!([1, 4, 2, 5, 3])
+    );
+}
+
+#[tokio::test]
+async fn pages_through_a_stable_order() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut ids = vec![];
+
+    for page in 1..=3 {
+        let data = server
+            .query(&format!(
+                "{{ tasks(page: {}, perPage: 2, orderBy: [{{ field: PRIORITY }}]) {{ id }} }}",
+                page
+            ))
+            .await;
+
+        ids.extend(data["tasks"].as_array().unwrap().iter().cloned());
+    }
+
+    // ties on the priority are broken by the primary key, so no row is skipped
+    assert_eq!(
+        Value::Array(ids),
+        json!([{ "id": 2 }, { "id": 5 }, { "id": 3 }, { "id": 1 }, { "id": 4 }])
+    );
+}
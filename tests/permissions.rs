@@ -0,0 +1,129 @@
+mod common;
+
+use common::{TestServer, errors};
+use serde_json::{Value, json};
+
+const SCHEMA: &str = r#"
+CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL, password_hash TEXT);
+CREATE TABLE post (id INTEGER PRIMARY KEY, author_id INTEGER NOT NULL REFERENCES user(id), title TEXT NOT NULL);
+
+INSERT INTO user (id, name, password_hash) VALUES (1, 'ada', 'x');
+INSERT INTO post (id, author_id, title) VALUES (1, 1, 'hello');
+"#;
+
+const PERMISSIONS: &str = r#"
+[[permission]]
+table = "user"
+read = { deny = ["password_hash"] }
+
+[[permission]]
+table = "post"
+role = "reader"
+operations = { deny = ["delete"] }
+
+[[permission]]
+table = "user"
+role = "guest"
+operations = { deny = ["list", "view"] }
+"#;
+
+async fn server() -> TestServer {
+    TestServer::with_config(SCHEMA, PERMISSIONS).await
+}
+
+fn role(name: &str) -> Value {
+    json!({ "sub": "1", "role": name })
+}
+
+/// Names of the fields of a type in the schema of a role.
+async fn fields(server: &TestServer, role: Option<&str>, type_name: &str) -> Vec<String> {
+    let query = format!(
+        r#"{{ __type(name: "{}") {{ fields {{ name }} }} }}"#,
+        type_name
+    );
+
+    let data = match role {
+        Some(name) => server.query_as(self::role(name), &query).await,
+        None => server.query(&query).await,
+    };
+
+    data["__type"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn leaves_hidden_columns_out_of_every_schema() {
+    let server = server().await;
+
+    for role in [None, Some("reader"), Some("guest")] {
+        let fields = fields(&server, role, "userNode").await;
+
+        assert!(fields.contains(&"name".to_string()));
+        assert!(!fields.contains(&"passwordHash".to_string()));
+    }
+
+    let response = server.execute("{ user(id: 1) { passwordHash } }").await;
+
+    assert_eq!(
+        errors(&response),
+        [r#"Unknown field "passwordHash" on type "userNode"."#]
+    );
+}
+
+#[tokio::test]
+async fn leaves_denied_operations_out_of_the_schema_of_a_role() {
+    let server = server().await;
+
+    assert!(
+        fields(&server, None, "Mutation")
+            .await
+            .contains(&"deletePost".to_string())
+    );
+
+    let mutations = fields(&server, Some("reader"), "Mutation").await;
+
+    assert!(!mutations.contains(&"deletePost".to_string()));
+    assert!(!mutations.contains(&"deletePostMany".to_string()));
+    assert!(mutations.contains(&"updatePost".to_string()));
+
+    let response = server
+        .execute_as(role("reader"), "mutation { deletePost(id: 1) }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(server.count("post").await, 1);
+}
+
+#[tokio::test]
+async fn exposes_foreign_keys_to_unreadable_tables_as_columns() {
+    let server = server().await;
+
+    assert_eq!(
+        fields(&server, None, "postNode").await,
+        ["id", "author", "title"]
+    );
+    assert_eq!(
+        fields(&server, Some("guest"), "postNode").await,
+        ["id", "authorId", "title"]
+    );
+    assert!(
+        fields(&server, None, "userNode")
+            .await
+            .contains(&"posts".to_string())
+    );
+    assert!(
+        !fields(&server, Some("guest"), "userNode")
+            .await
+            .contains(&"posts".to_string())
+    );
+
+    let data = server
+        .query_as(role("guest"), "{ post(id: 1) { authorId title } }")
+        .await;
+
+    assert_eq!(data, json!({ "post": { "authorId": 1, "title": "hello" } }));
+}
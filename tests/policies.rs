@@ -0,0 +1,199 @@
+mod common;
+
+use common::{TestServer, errors};
+use serde_json::{Value, json};
+
+const SCHEMA: &str = r#"
+CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE post (
+    id INTEGER PRIMARY KEY,
+    author_id INTEGER NOT NULL REFERENCES user(id),
+    title TEXT NOT NULL,
+    published INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE comment (id INTEGER PRIMARY KEY, post_id INTEGER REFERENCES post(id), body TEXT NOT NULL);
+CREATE TABLE tag (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE post_tag (
+    post_id INTEGER NOT NULL REFERENCES post(id),
+    tag_id INTEGER NOT NULL REFERENCES tag(id),
+    hidden INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (post_id, tag_id)
+);
+CREATE TABLE setting (key TEXT PRIMARY KEY, owner_id INTEGER NOT NULL, value TEXT NOT NULL);
+
+INSERT INTO user (id, name) VALUES (1, 'ada'), (2, 'grace');
+INSERT INTO post (id, author_id, title, published) VALUES
+    (1, 1, 'ada draft', 0),
+    (2, 2, 'grace public', 1),
+    (3, 2, 'grace draft', 0),
+    (4, 1, 'ada public', 1);
+INSERT INTO comment (id, post_id, body) VALUES (1, 2, 'nice'), (2, 3, 'early');
+INSERT INTO tag (id, name) VALUES (1, 'rust'), (2, 'sql');
+INSERT INTO post_tag (post_id, tag_id, hidden) VALUES (2, 1, 0), (2, 2, 1);
+INSERT INTO setting (key, owner_id, value) VALUES ('theme', 2, 'dark');
+"#;
+
+const POLICIES: &str = r#"
+[graphql]
+junction-tables = ["post_tag"]
+
+[[policy]]
+table = "post"
+select = "published OR author_id = $claims.sub"
+insert = "author_id = $claims.sub"
+update = "author_id = $claims.sub AND (NOT published OR title <> '')"
+
+[[policy]]
+table = "post_tag"
+select = "NOT hidden"
+
+[[policy]]
+table = "setting"
+select = "TRUE"
+insert = "owner_id = $claims.sub"
+update = "TRUE"
+"#;
+
+async fn server() -> TestServer {
+    TestServer::with_config(SCHEMA, POLICIES).await
+}
+
+fn ada() -> Value {
+    json!({ "sub": "1" })
+}
+
+#[tokio::test]
+async fn filters_lists_and_views() {
+    let server = server().await;
+
+    let data = server
+        .query_as(
+            ada(),
+            r#"{
+                posts(page: 1, perPage: 10, orderBy: [{ field: ID }]) { id }
+                draft: post(id: 3) { id }
+                own: post(id: 1) { id }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "posts": [{ "id": 1 }, { "id": 2 }, { "id": 4 }],
+            "draft": null,
+            "own": { "id": 1 },
+        })
+    );
+
+    let data = server
+        .query(r#"{ posts(page: 1, perPage: 10, orderBy: [{ field: ID }]) { id } }"#)
+        .await;
+
+    assert_eq!(data, json!({ "posts": [{ "id": 2 }, { "id": 4 }] }));
+}
+
+#[tokio::test]
+async fn hides_rows_referenced_by_foreign_keys_and_relations() {
+    let server = server().await;
+
+    let data = server
+        .query_as(
+            ada(),
+            r#"{
+                comments(page: 1, perPage: 10, orderBy: [{ field: ID }]) { id post { id } }
+                user(id: 2) { posts(orderBy: [{ field: ID }]) { id } }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "comments": [{ "id": 1, "post": { "id": 2 } }, { "id": 2, "post": null }],
+            "user": { "posts": [{ "id": 2 }] },
+        })
+    );
+}
+
+#[tokio::test]
+async fn hides_rows_linked_by_hidden_junction_rows() {
+    let server = server().await;
+
+    let data = server
+        .query_as(ada(), "{ post(id: 2) { tags { name } } }")
+        .await;
+
+    assert_eq!(data, json!({ "post": { "tags": [{ "name": "rust" }] } }));
+}
+
+#[tokio::test]
+async fn rolls_back_nested_inserts_failing_the_check() {
+    let server = server().await;
+
+    let response = server
+        .execute_as(
+            ada(),
+            r#"mutation {
+                insertUser(value: { name: "linus", posts: { create: [{ title: "hello" }] } }) { id }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        errors(&response),
+        vec!["Row written to 'post' violates its policies"]
+    );
+    assert_eq!(server.count("user").await, 2);
+    assert_eq!(server.count("post").await, 4);
+}
+
+#[tokio::test]
+async fn rolls_back_bulk_updates_failing_the_check() {
+    let server = server().await;
+
+    let response = server
+        .execute_as(
+            ada(),
+            r#"mutation {
+                updatePostMany(where: { id: { in: [1, 4] } }, set: { title: "" }) { affectedRows }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(
+        server
+            .scalar::<String>("SELECT title FROM post WHERE id = 1")
+            .await,
+        "ada draft"
+    );
+}
+
+#[tokio::test]
+async fn checks_upserts_against_the_policy_of_their_path() {
+    let server = server().await;
+
+    let upsert = |key: &str| {
+        format!(
+            r#"mutation {{
+                upsertSetting(
+                    value: {{ key: "{}", ownerId: 2, value: "light" }},
+                    onConflict: {{ constraint: PRIMARY_KEY, update: [VALUE] }}
+                ) {{ key value }}
+            }}"#,
+            key
+        )
+    };
+
+    // updating someone else's setting is allowed, creating one isn't
+    assert_eq!(
+        server.query_as(ada(), &upsert("theme")).await,
+        json!({ "upsertSetting": { "key": "theme", "value": "light" } })
+    );
+
+    let response = server.execute_as(ada(), &upsert("locale")).await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(server.count("setting").await, 1);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use async_graphql::dataloader::Loader;
+use common::TestServer;
+use graph_sql::loader::{RelationLoader, RelationRowDef};
+use sea_query::Alias;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE post (
+    id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES user(id),
+    title TEXT NOT NULL
+);
+
+INSERT INTO user (id, name) VALUES (1, 'ada'), (2, 'grace'), (3, 'linus');
+INSERT INTO post (id, user_id, title) VALUES
+    (1, 1, 'b'), (2, 2, 'c'), (3, 1, 'a'), (4, 1, 'c');
+"#;
+
+#[tokio::test]
+async fn lists_the_children_of_every_parent() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            "{ users(page: 1, perPage: 10) { name posts(orderBy: [{ field: TITLE }]) { title } } }",
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "users": [
+            { "name": "ada", "posts": [{ "title": "a" }, { "title": "b" }, { "title": "c" }] },
+            { "name": "grace", "posts": [{ "title": "c" }] },
+            { "name": "linus", "posts": [] },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn paginates_and_filters_the_children_of_each_parent() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{ users(page: 1, perPage: 10) {
+                posts(page: 2, perPage: 1, where: { title: { ne: "a" } }, orderBy: [{ field: TITLE }]) {
+                    title
+                }
+            } }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "users": [
+            { "posts": [{ "title": "c" }] },
+            { "posts": [] },
+            { "posts": [] },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn loads_the_children_of_many_parents_at_once() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let loader = RelationLoader {
+        db: server.db.clone().into(),
+        request: Default::default(),
+    };
+
+    let key = |value| RelationRowDef {
+        table: Alias::new("post"),
+        foreign_column: Alias::new("user_id"),
+        primary_columns: vec![Alias::new("id")],
+        filter: None,
+        sorts: vec![],
+        offset: None,
+        limit: None,
+        junction: None,
+        value,
+    };
+
+    let keys = [key(json!(1)), key(json!(2)), key(json!(3))];
+    let children = loader.load(&keys).await.unwrap();
+
+    assert_eq!(children.len(), 3);
+    assert_eq!(children[&keys[0]].len(), 3);
+    assert_eq!(children[&keys[1]], vec![json!([2])]);
+    assert!(children[&keys[2]].is_empty());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE legacy (code TEXT NOT NULL, label TEXT);
+
+INSERT INTO legacy (code, label) VALUES ('a', 'first'), ('b', NULL), ('c', 'third');
+"#;
+
+#[tokio::test]
+async fn identifies_rows_of_tables_without_a_primary_key_by_rowid() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"{
+                legacies(page: 1, perPage: 10, orderBy: [{ field: CODE, order: DESC }]) {
+                    rowid code
+                }
+                legacy(rowid: 2) { rowid code label }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "legacies": [
+                { "rowid": 3, "code": "c" },
+                { "rowid": 2, "code": "b" },
+                { "rowid": 1, "code": "a" },
+            ],
+            "legacy": { "rowid": 2, "code": "b", "label": null },
+        })
+    );
+}
+
+#[tokio::test]
+async fn writes_rows_by_rowid() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                insertLegacy(value: { code: "d" }) { rowid code }
+                updateLegacy(rowid: 2, value: { label: "second" }) { rowid label }
+                deleteLegacy(rowid: 1)
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "insertLegacy": { "rowid": 4, "code": "d" },
+            "updateLegacy": { "rowid": 2, "label": "second" },
+            "deleteLegacy": 1,
+        })
+    );
+
+    assert_eq!(server.count("legacy").await, 3);
+}
+
+#[tokio::test]
+async fn keeps_the_rowid_read_only() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(r#"mutation { insertLegacy(value: { rowid: 9, code: "d" }) { rowid } }"#)
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+
+    let response = server
+        .execute("mutation { updateLegacy(rowid: 1, value: { rowid: 9 }) { rowid } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+}
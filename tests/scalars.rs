@@ -0,0 +1,120 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE event (
+    id INTEGER PRIMARY KEY,
+    at DATETIME,
+    day DATE,
+    payload BLOB,
+    meta JSON,
+    big BIGINT,
+    price DECIMAL(10,2),
+    name VARCHAR(255)
+);
+"#;
+
+#[tokio::test]
+async fn round_trips_custom_scalars() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                insertEvent(value: {
+                    at: "2024-01-31T12:30:00Z"
+                    day: "2024-02-29"
+                    payload: "aGVsbG8="
+                    meta: { tags: ["a", "b"], count: 2 }
+                    big: "9007199254740993"
+                    price: 10.25
+                    name: "launch"
+                }) { id at day payload meta big price name }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "insertEvent": {
+            "id": 1,
+            "at": "2024-01-31T12:30:00Z",
+            "day": "2024-02-29",
+            "payload": "aGVsbG8=",
+            "meta": { "tags": ["a", "b"], "count": 2 },
+            "big": 9007199254740993i64,
+            "price": 10.25,
+            "name": "launch",
+        } })
+    );
+
+    // blobs are stored as bytes, not as their base64 text
+    let (payload,): (Vec<u8>,) = sqlx::query_as("SELECT payload FROM event")
+        .fetch_one(&server.db)
+        .await
+        .unwrap();
+
+    assert_eq!(payload, b"hello");
+}
+
+#[tokio::test]
+async fn rejects_malformed_scalar_input() {
+    let server = TestServer::new(SCHEMA).await;
+
+    for value in [
+        r#"at: "yesterday""#,
+        r#"at: "2024-13-01 00:00:00""#,
+        r#"day: "2023-02-29""#,
+        r#"day: "2024-01-31T12:30:00Z""#,
+        r#"payload: "not base64!""#,
+        r#"big: "12x""#,
+        "big: 1.5",
+    ] {
+        let response = server
+            .execute(format!(
+                "mutation {{ insertEvent(value: {{ {} }}) {{ id }} }}",
+                value
+            ))
+            .await;
+
+        assert_eq!(response.errors.len(), 1, "{}", value);
+    }
+
+    assert_eq!(server.count("event").await, 0);
+}
+
+#[tokio::test]
+async fn filters_by_custom_scalars() {
+    let server = TestServer::new(&format!(
+        "{}{}",
+        SCHEMA,
+        r#"
+            INSERT INTO event (id, at, day, big) VALUES
+                (1, '2024-01-01 08:00:00', '2024-01-01', 1),
+                (2, '2024-02-01 08:00:00', '2024-02-01', 9007199254740993),
+                (3, '2024-03-01 08:00:00', '2024-03-01', NULL);
+            "#
+    ))
+    .await;
+
+    let data = server
+        .query(
+            r#"{
+                later: events(page: 1, perPage: 10, where: { at: { gt: "2024-01-15 00:00:00" } }) { id }
+                before: events(page: 1, perPage: 10, where: { day: { lt: "2024-03-01" } }) { id }
+                big: events(page: 1, perPage: 10, where: { big: { eq: "9007199254740993" } }) { id }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "later": [{ "id": 2 }, { "id": 3 }],
+            "before": [{ "id": 1 }, { "id": 2 }],
+            "big": [{ "id": 2 }],
+        })
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{TestServer, data, next};
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE note (id INTEGER PRIMARY KEY, owner_id INTEGER NOT NULL, title TEXT NOT NULL);
+
+INSERT INTO note (id, owner_id, title) VALUES (1, 1, 'mine'), (2, 2, 'theirs');
+"#;
+
+const POLICY: &str = r#"
+[[policy]]
+table = "note"
+select = "owner_id = $claims.sub"
+delete = "TRUE"
+"#;
+
+#[tokio::test]
+async fn notifies_writes_matching_where() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut stream = server
+        .subscribe(
+            None,
+            r#"subscription { noteChanged(where: { ownerId: { eq: 1 } }) { operation node { title } } }"#,
+        )
+        .await;
+
+    server
+        .query(
+            r#"mutation {
+                other: insertNote(value: { ownerId: 2, title: "skipped" }) { id }
+                mine: insertNote(value: { ownerId: 1, title: "new" }) { id }
+            }"#,
+        )
+        .await;
+
+    let event = data(next(&mut stream).await.unwrap());
+
+    assert_eq!(
+        event,
+        json!({ "noteChanged": { "operation": "INSERT", "node": { "title": "new" } } })
+    );
+    assert!(next(&mut stream).await.is_none());
+}
+
+#[tokio::test]
+async fn notifies_deletes_matching_where() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut stream = server
+        .subscribe(
+            None,
+            r#"subscription { noteChanged(where: { title: { eq: "mine" } }) { operation node { id } } }"#,
+        )
+        .await;
+
+    server.query("mutation { deleteNote(id: 2) }").await;
+    server
+        .query(r#"mutation { deleteNoteMany(where: { id: { eq: 1 } }) { affectedRows } }"#)
+        .await;
+
+    let event = data(next(&mut stream).await.unwrap());
+
+    assert_eq!(
+        event,
+        json!({ "noteChanged": { "operation": "DELETE", "node": null } })
+    );
+    assert!(next(&mut stream).await.is_none());
+}
+
+#[tokio::test]
+async fn notifies_deletes_allowed_by_the_select_policy() {
+    let server = TestServer::with_config(SCHEMA, POLICY).await;
+
+    let mut stream = server
+        .subscribe(
+            Some(json!({ "sub": "1" })),
+            "subscription { noteChanged { operation } }",
+        )
+        .await;
+
+    assert_eq!(
+        server.query("mutation { deleteNote(id: 2) }").await,
+        json!({ "deleteNote": 1 })
+    );
+    assert!(next(&mut stream).await.is_none());
+
+    server.query("mutation { deleteNote(id: 1) }").await;
+
+    let event = data(next(&mut stream).await.unwrap());
+
+    assert_eq!(event, json!({ "noteChanged": { "operation": "DELETE" } }));
+}
+
+#[tokio::test]
+async fn notifies_every_delete_without_filter() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let mut stream = server
+        .subscribe(None, "subscription { noteChanged { operation } }")
+        .await;
+
+    server
+        .query(r#"mutation { deleteNoteMany(where: { id: { in: [1, 2] } }) { affectedRows } }"#)
+        .await;
+
+    for _ in 0..2 {
+        let event = data(next(&mut stream).await.unwrap());
+
+        assert_eq!(event, json!({ "noteChanged": { "operation": "DELETE" } }));
+    }
+}
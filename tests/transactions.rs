@@ -0,0 +1,115 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE account (id INTEGER PRIMARY KEY, balance INTEGER NOT NULL CHECK (balance >= 0));
+
+INSERT INTO account (id, balance) VALUES (1, 100), (2, 0);
+"#;
+
+/// Moves 150 from an account holding 100, failing on the second field.
+const OVERDRAWN_TRANSFER: &str = r#"
+    credit: updateAccount(id: 2, value: { balance: 150 }) { balance }
+    debit: updateAccount(id: 1, value: { balance: -50 }) { balance }
+"#;
+
+async fn balances(server: &TestServer) -> Vec<i64> {
+    sqlx::query_scalar("SELECT balance FROM account ORDER BY id")
+        .fetch_all(&server.db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn fields_are_committed_one_by_one_by_default() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(format!("mutation {{ {} }}", OVERDRAWN_TRANSFER))
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(balances(&server).await, vec![100, 150]);
+}
+
+#[tokio::test]
+async fn transaction_rolls_every_field_back_on_failure() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let response = server
+        .execute(format!(
+            "mutation @transaction {{ {} }}",
+            OVERDRAWN_TRANSFER
+        ))
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(response.data.into_json().unwrap(), json!(null));
+    assert_eq!(balances(&server).await, vec![100, 0]);
+}
+
+#[tokio::test]
+async fn transaction_commits_every_field_on_success() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation @transaction {
+                debit: updateAccount(id: 1, value: { balance: 40 }) { balance }
+                credit: updateAccount(id: 2, value: { balance: 60 }) { balance }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "debit": { "balance": 40 }, "credit": { "balance": 60 } })
+    );
+    assert_eq!(balances(&server).await, vec![40, 60]);
+}
+
+#[tokio::test]
+async fn dry_run_returns_the_results_without_writing() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation @dryRun {
+                insertAccount(value: { balance: 10 }) { balance }
+                updateAccount(id: 1, value: { balance: 40 }) { balance }
+                deleteAccount(id: 2)
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "insertAccount": { "balance": 10 },
+            "updateAccount": { "balance": 40 },
+            "deleteAccount": 1,
+        })
+    );
+    assert_eq!(balances(&server).await, vec![100, 0]);
+}
+
+#[tokio::test]
+async fn every_mutation_is_transactional_when_configured() {
+    let server = TestServer::with_config(
+        SCHEMA,
+        r#"
+        [graphql]
+        transactional-mutations = true
+        "#,
+    )
+    .await;
+
+    let response = server
+        .execute(format!("mutation {{ {} }}", OVERDRAWN_TRANSFER))
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+    assert_eq!(balances(&server).await, vec![100, 0]);
+}
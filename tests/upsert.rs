@@ -0,0 +1,108 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE setting (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+CREATE TABLE user (
+    id INTEGER PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    logins INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO setting (key, value) VALUES ('theme', 'dark');
+INSERT INTO user (id, email, name, logins) VALUES (1, 'ada@example.com', 'Ada', 3);
+"#;
+
+#[tokio::test]
+async fn upserts_on_the_primary_key() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let upsert = |key: &str, value: &str| {
+        format!(
+            r#"mutation {{
+                upsertSetting(
+                    value: {{ key: "{}", value: "{}" }},
+                    onConflict: {{ constraint: PRIMARY_KEY, update: [VALUE] }}
+                ) {{ key value }}
+            }}"#,
+            key, value
+        )
+    };
+
+    assert_eq!(
+        server.query(&upsert("theme", "light")).await,
+        json!({ "upsertSetting": { "key": "theme", "value": "light" } })
+    );
+    assert_eq!(
+        server.query(&upsert("locale", "fr")).await,
+        json!({ "upsertSetting": { "key": "locale", "value": "fr" } })
+    );
+
+    let data = server
+        .query(r#"{ settings(page: 1, perPage: 10, orderBy: [{ field: KEY }]) { key value } }"#)
+        .await;
+
+    assert_eq!(
+        data,
+        json!({ "settings": [
+            { "key": "locale", "value": "fr" },
+            { "key": "theme", "value": "light" },
+        ] })
+    );
+}
+
+#[tokio::test]
+async fn upserts_on_a_unique_constraint() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                existing: upsertUser(
+                    value: { email: "ada@example.com", name: "Ada Lovelace" },
+                    onConflict: { constraint: EMAIL, update: [NAME] }
+                ) { id email name logins }
+                new: upsertUser(
+                    value: { email: "grace@example.com", name: "Grace" },
+                    onConflict: { constraint: EMAIL, update: [NAME] }
+                ) { email name logins }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "existing": { "id": 1, "email": "ada@example.com", "name": "Ada Lovelace", "logins": 3 },
+            "new": { "email": "grace@example.com", "name": "Grace", "logins": 0 },
+        })
+    );
+    assert_eq!(server.count("user").await, 2);
+}
+
+#[tokio::test]
+async fn keeps_the_conflicting_row_without_update_columns() {
+    let server = TestServer::new(SCHEMA).await;
+
+    let data = server
+        .query(
+            r#"mutation {
+                upsertUser(
+                    value: { email: "ada@example.com", name: "Someone else" },
+                    onConflict: { constraint: EMAIL, update: [] }
+                ) { name }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(data, json!({ "upsertUser": null }));
+    assert_eq!(
+        server
+            .scalar::<String>("SELECT name FROM user WHERE id = 1")
+            .await,
+        "Ada"
+    );
+}
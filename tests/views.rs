@@ -0,0 +1,109 @@
+mod common;
+
+use common::TestServer;
+use serde_json::json;
+
+const SCHEMA: &str = r#"
+CREATE TABLE user (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE post (
+    id INTEGER PRIMARY KEY,
+    author_id INTEGER NOT NULL REFERENCES user(id),
+    title TEXT NOT NULL
+);
+CREATE VIEW post_summary AS
+    SELECT post.id AS post_id, user.id AS author_id, post.title, user.name
+    FROM post JOIN user ON user.id = post.author_id;
+CREATE VIEW authorship AS SELECT id AS post_id, author_id FROM post;
+
+INSERT INTO user (id, name) VALUES (1, 'ada');
+INSERT INTO post (id, author_id, title) VALUES (1, 1, 'first');
+"#;
+
+const CONFIG: &str = r#"
+[views.post_summary]
+key = ["post_id"]
+
+[[views.post_summary.foreign-keys]]
+column = "post_id"
+table = "post"
+to = "id"
+
+[[views.post_summary.foreign-keys]]
+column = "author_id"
+table = "user"
+to = "id"
+
+[[views.authorship.foreign-keys]]
+column = "post_id"
+table = "post"
+to = "id"
+
+[[views.authorship.foreign-keys]]
+column = "author_id"
+table = "user"
+to = "id"
+"#;
+
+#[tokio::test]
+async fn relates_views_through_configured_foreign_keys() {
+    let server = TestServer::with_config(SCHEMA, CONFIG).await;
+
+    let data = server
+        .query(
+            r#"{
+                postSummary(post_id: 1) { title post { id } author { name } }
+                user(id: 1) { postSummaries { title } }
+            }"#,
+        )
+        .await;
+
+    assert_eq!(
+        data,
+        json!({
+            "postSummary": { "title": "first", "post": { "id": 1 }, "author": { "name": "ada" } },
+            "user": { "postSummaries": [{ "title": "first" }] },
+        })
+    );
+}
+
+#[tokio::test]
+async fn never_links_views_as_junctions() {
+    // `authorship` holds nothing but two foreign keys, like a junction table would
+    let server = TestServer::with_config(SCHEMA, CONFIG).await;
+
+    let data = server
+        .query(
+            r#"{
+                user: __type(name: "userNode") { fields { name } }
+                post: __type(name: "postNode") { fields { name } }
+                mutation: __type(name: "Mutation") { fields { name } }
+            }"#,
+        )
+        .await;
+
+    let names = |type_name: &str| {
+        let mut names = data[type_name]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        names.sort();
+        names
+    };
+
+    assert_eq!(
+        names("user"),
+        ["authorships", "id", "name", "postSummaries", "posts"]
+    );
+    assert_eq!(
+        names("post"),
+        ["author", "authorships", "id", "postSummaries", "title"]
+    );
+    assert!(
+        !names("mutation")
+            .iter()
+            .any(|name| name.ends_with("Authorship") || name.ends_with("PostSummary"))
+    );
+}